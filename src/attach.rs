@@ -0,0 +1,55 @@
+use gtk::prelude::*;
+use std::os::raw::c_ulong;
+
+/* --attach XID makes the dialog transient for a window of another program,
+ * by setting WM_TRANSIENT_FOR like GTK does for its own parents. Window
+ * managers keep it above that window and usually center it there. This only
+ * works on X11, Wayland has no ids of foreign windows.
+ */
+
+// Window ids as xwininfo (0x3a00007) or xdotool (60817415) print them.
+pub fn parse_xid(value: &str) -> Option<c_ulong> {
+    let xid = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => c_ulong::from_str_radix(hex, 16).ok()?,
+        None => value.parse().ok()?,
+    };
+    if xid == 0 {
+        return None;
+    }
+    Some(xid)
+}
+
+pub fn attach(window: &gtk::Window, xid: c_ulong) {
+    window.set_type_hint(gdk::WindowTypeHint::Dialog);
+    // The property needs the X window, which exists once it is realized.
+    window.connect_realize(move |w| {
+        let gdk_window = match w.get_window() {
+            Some(gdk_window) => gdk_window,
+            None => return,
+        };
+        gdk::property_change(
+            &gdk_window,
+            &gdk::Atom::intern("WM_TRANSIENT_FOR"),
+            &gdk::Atom::intern("WINDOW"),
+            32,
+            gdk::PropMode::Replace,
+            gdk::ChangeData::ULong(xid),
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_xid;
+
+    #[test]
+    fn window_ids() {
+        assert_eq!(Some(0x3a0_0007), parse_xid("0x3a00007"));
+        assert_eq!(Some(60_817_415), parse_xid("60817415"));
+        assert_eq!(None, parse_xid("0"));
+        assert_eq!(None, parse_xid("firefox"));
+    }
+}
@@ -0,0 +1,120 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/* Appends one line per event to the audit log file.
+ * Errors while writing are reported on stderr but never stop the dialog.
+ */
+#[derive(Clone, Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn dialog_shown(&self, message_type: &str, message: &str) {
+        self.append(&format!(
+            "event=shown type={} message={:?}",
+            message_type, message
+        ));
+    }
+
+    pub fn button_pressed(&self, label: &str, command: &str) {
+        self.append(&format!(
+            "event=pressed label={:?} command={:?}",
+            label, command
+        ));
+    }
+
+    pub fn command_finished(
+        &self,
+        label: &str,
+        status: &std::io::Result<std::process::ExitStatus>,
+    ) {
+        let status = match status {
+            Ok(s) => match s.code() {
+                Some(code) => code.to_string(),
+                None => "signal".to_string(),
+            },
+            Err(e) => format!("{:?}", e.to_string()),
+        };
+        self.append(&format!(
+            "event=finished label={:?} status={}",
+            label, status
+        ));
+    }
+
+    // Status of an HTTP or open button, e.g. "HTTP 200 OK" or "Opened"
+    pub fn request_finished(&self, label: &str, status: &str) {
+        self.append(&format!(
+            "event=finished label={:?} status={:?}",
+            label, status
+        ));
+    }
+
+    fn append(&self, record: &str) {
+        let user = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
+        let line = format!("{} user={} {}\n", timestamp(), user, record);
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| f.write_all(line.as_bytes()));
+        if let Err(e) = result {
+            warning!(
+                "Couldn't write audit log {}: {}",
+                self.path.to_string_lossy(),
+                e
+            );
+        }
+    }
+}
+
+// RFC 3339 timestamp in UTC, e.g. 2019-03-01T12:00:00Z
+fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+// Converts days since 1970-01-01 into a (year, month, day) triple.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::civil_from_days;
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!((1970, 1, 1), civil_from_days(0));
+        assert_eq!((2000, 3, 1), civil_from_days(11_017));
+        assert_eq!((2019, 2, 28), civil_from_days(17_955));
+    }
+}
@@ -0,0 +1,150 @@
+use gdk::ScreenExt;
+use gtk::prelude::*;
+use std::os::raw::c_ulong;
+
+/* --bar docks the dialog to the top edge of the primary monitor like
+ * i3-nagbar: a strip as wide as the monitor, without decorations. The space
+ * is reserved through the EWMH struts, so other windows don't cover it.
+ * Wayland compositors ignore both, there the bar becomes a layer surface of
+ * gtk-layer-shell if the compositor offers zwlr_layer_shell_v1.
+ */
+
+#[cfg(feature = "layer-shell")]
+mod layer_shell {
+    use glib::translate::ToGlibPtr;
+    use std::os::raw::{c_int, c_void};
+
+    // GtkLayerShellLayer and GtkLayerShellEdge
+    const LAYER_TOP: c_int = 2;
+    const EDGE_LEFT: c_int = 0;
+    const EDGE_RIGHT: c_int = 1;
+    const EDGE_TOP: c_int = 2;
+
+    #[link(name = "gtk-layer-shell")]
+    extern "C" {
+        fn gtk_layer_is_supported() -> c_int;
+        fn gtk_layer_init_for_window(window: *mut c_void);
+        fn gtk_layer_set_layer(window: *mut c_void, layer: c_int);
+        fn gtk_layer_set_anchor(window: *mut c_void, edge: c_int, anchor_to_edge: c_int);
+        fn gtk_layer_auto_exclusive_zone_enable(window: *mut c_void);
+        fn gtk_layer_set_keyboard_interactivity(window: *mut c_void, interactivity: c_int);
+    }
+
+    // False outside of Wayland sessions. Needs gtk to be initialized.
+    pub fn is_supported() -> bool {
+        unsafe { gtk_layer_is_supported() != 0 }
+    }
+
+    // Has to happen before the window is realized.
+    pub fn dock(window: &gtk::Window) {
+        let object: *mut glib::object::GObject = window.to_glib_none().0;
+        let window = object as *mut c_void;
+        unsafe {
+            gtk_layer_init_for_window(window);
+            gtk_layer_set_layer(window, LAYER_TOP);
+            for edge in &[EDGE_LEFT, EDGE_RIGHT, EDGE_TOP] {
+                gtk_layer_set_anchor(window, *edge, 1);
+            }
+            // Keeps other windows from covering the bar, like the struts.
+            gtk_layer_auto_exclusive_zone_enable(window);
+            // Otherwise the buttons can't be reached with the keyboard.
+            gtk_layer_set_keyboard_interactivity(window, 1);
+        }
+    }
+}
+
+#[cfg(feature = "layer-shell")]
+pub fn layer_shell_supported() -> bool {
+    layer_shell::is_supported()
+}
+
+#[cfg(not(feature = "layer-shell"))]
+pub fn layer_shell_supported() -> bool {
+    false
+}
+
+/* _NET_WM_STRUT_PARTIAL for a bar at x, y of the root window: left, right,
+ * top and bottom, followed by the start and end of each along its edge.
+ */
+fn strut_partial(x: i32, y: i32, width: i32, height: i32) -> [c_ulong; 12] {
+    let top = (y + height) as c_ulong;
+    let start = x as c_ulong;
+    let end = (x + width - 1) as c_ulong;
+    [0, 0, top, 0, 0, 0, 0, 0, start, end, 0, 0]
+}
+
+fn reserve(window: &gtk::Window, values: &[c_ulong; 12]) {
+    let gdk_window = match window.get_window() {
+        Some(w) => w,
+        None => return,
+    };
+    let cardinal = gdk::Atom::intern("CARDINAL");
+    gdk::property_change(
+        &gdk_window,
+        &gdk::Atom::intern("_NET_WM_STRUT_PARTIAL"),
+        &cardinal,
+        32,
+        gdk::PropMode::Replace,
+        gdk::ChangeData::ULongs(values),
+    );
+    // For window managers which only know the older property.
+    gdk::property_change(
+        &gdk_window,
+        &gdk::Atom::intern("_NET_WM_STRUT"),
+        &cardinal,
+        32,
+        gdk::PropMode::Replace,
+        gdk::ChangeData::ULongs(&values[..4]),
+    );
+}
+
+pub fn dock(window: &gtk::Window) {
+    window.set_decorated(false);
+    #[cfg(feature = "layer-shell")]
+    {
+        if layer_shell::is_supported() {
+            layer_shell::dock(window);
+            return;
+        }
+    }
+    window.set_type_hint(gdk::WindowTypeHint::Dock);
+    let screen = match window.get_screen() {
+        Some(s) => s,
+        None => {
+            warning!("Couldn't dock the bar, there is no screen.");
+            return;
+        }
+    };
+    let area = screen.get_monitor_geometry(screen.get_primary_monitor());
+    window.set_size_request(area.width, -1);
+    window.move_(area.x, area.y);
+    // The height is only known once the content is laid out.
+    window.connect_size_allocate(move |w, allocation| {
+        reserve(
+            w,
+            &strut_partial(area.x, area.y, area.width, allocation.height),
+        );
+    });
+    window.connect_realize(move |w| {
+        let height = w.get_allocated_height();
+        reserve(w, &strut_partial(area.x, area.y, area.width, height));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strut_partial;
+
+    #[test]
+    fn struts() {
+        assert_eq!(
+            [0, 0, 30, 0, 0, 0, 0, 0, 0, 1919, 0, 0],
+            strut_partial(0, 0, 1920, 30)
+        );
+        // Second monitor right of the first one
+        assert_eq!(
+            [0, 0, 40, 0, 0, 0, 0, 0, 1920, 4479, 0, 0],
+            strut_partial(1920, 0, 2560, 40)
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{create_gtk_dialog, spec, strip_mnemonics, Configuration, ParseError, PROGRAM_NAME};
+use gtk::prelude::*;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::path::Path;
+use std::rc::Rc;
+
+/* Dialog pipelines for --chain FILE. Pressing a button of a chained dialog
+ * doesn't run its action, the spec in FILE is shown instead, in the same
+ * process. Strings of that spec may use
+ *
+ *   {result}         label of the pressed button, without mnemonics
+ *   {result_index}   its position, starting at 0
+ *
+ * The entered PIN (or prompt value) is no string to replace, it would show up
+ * in ps and logs. The actions of the next spec get it through --secret-via
+ * like a PIN of their own. The next spec can chain further. Cancel ends the
+ * whole chain.
+ */
+
+// The window of a chained dialog, it is closed once the next one is shown.
+pub type WindowSlot = Rc<RefCell<Option<gtk::Window>>>;
+
+pub fn substitute(value: &Value, replacements: &[(&str, &str)]) -> Value {
+    match value {
+        Value::String(s) => Value::String(
+            replacements
+                .iter()
+                .fold(s.clone(), |s, (from, to)| s.replace(from, to)),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute(item, replacements))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| (key.clone(), substitute(item, replacements)))
+                .collect(),
+        ),
+        v => v.clone(),
+    }
+}
+
+fn next(
+    path: &Path,
+    label: &str,
+    index: usize,
+    pin: Option<&str>,
+) -> Result<Configuration, ParseError> {
+    let label = strip_mnemonics(label);
+    let index = index.to_string();
+    let replacements = [
+        ("{result}", label.as_str()),
+        ("{result_index}", index.as_str()),
+    ];
+    let spec_args = spec::read(path)
+        .map_err(|e| e.message)
+        .and_then(|(_, spec)| {
+            if spec.to_string().contains("{result_pin}") {
+                return Err(String::from(
+                    "{result_pin} isn't replaced, the actions get the PIN through --secret-via.",
+                ));
+            }
+            spec::to_args(&spec::with_defaults(&substitute(&spec, &replacements)))
+        })
+        .map_err(|e| ParseError::wrong_argument(format!("{}: {}", path.to_string_lossy(), e)))?;
+    let mut args = vec![OsString::from(PROGRAM_NAME)];
+    args.extend(spec_args);
+    let mut config = Configuration::new(&args)?;
+    config.chained_input = pin.map(String::from);
+    Ok(config)
+}
+
+/* Makes the buttons of a chained dialog show the next one. The window has to
+ * be put into the returned slot once it is created. Without --chain nothing
+ * changes.
+ */
+pub fn follow(config: &mut Configuration) -> WindowSlot {
+    let slot: WindowSlot = Rc::new(RefCell::new(None));
+    let path = match &config.chain {
+        Some(p) => p.clone(),
+        None => return slot,
+    };
+    let labels: Vec<String> = config.buttons.iter().map(|b| b.label.clone()).collect();
+    let window_slot = slot.clone();
+    config.on_choice = Some(Rc::new(move |index: Option<usize>, pin: Option<String>| {
+        let index = match index {
+            Some(i) => i,
+            None => {
+                gtk::main_quit();
+                return;
+            }
+        };
+        let mut config = match next(&path, &labels[index], index, pin.as_deref()) {
+            Ok(c) => c,
+            Err(e) => std::process::exit(crate::handle_error(e)),
+        };
+        let chained = follow(&mut config);
+        let (window, _) = create_gtk_dialog(&config);
+        window.show_all();
+        *chained.borrow_mut() = Some(window);
+        if let Some(window) = window_slot.borrow_mut().take() {
+            window.destroy();
+        }
+    }));
+    slot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next, substitute};
+    use serde_json::json;
+
+    #[test]
+    fn result_is_substituted() {
+        let spec = json!({
+            "message": "What should happen to {result}?",
+            "button": [{"label": "_Start", "action": "virsh start {result}", "index": 2}]
+        });
+        let next = substitute(&spec, &[("{result}", "vm1")]);
+        assert_eq!(json!("What should happen to vm1?"), next["message"]);
+        assert_eq!(json!("virsh start vm1"), next["button"][0]["action"]);
+        assert_eq!(json!(2), next["button"][0]["index"]);
+    }
+
+    #[test]
+    fn pin_is_passed_on() {
+        let path = std::env::temp_dir().join(format!("chain-{}.toml", std::process::id()));
+        let spec = "[[button]]\nlabel = \"_Mount\"\naction = \"mount-vault\"\ntype = \"shell\"\n";
+        std::fs::write(&path, spec).unwrap();
+        let config = next(&path, "_OK", 0, Some("1234")).unwrap();
+        assert_eq!(Some(String::from("1234")), config.chained_input);
+        std::fs::write(
+            &path,
+            "[[button]]\nlabel = \"_Mount\"\naction = \"mount {result_pin}\"\n",
+        )
+        .unwrap();
+        assert!(next(&path, "_OK", 0, Some("1234")).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+use crate::PROGRAM_NAME;
+use std::fs::File;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+
+/* --dedup KEY keeps dialogs with the same key from stacking up. The first one
+ * holds an exclusive lock on a file named after the key until it exits, all
+ * later ones find the lock taken and exit right away.
+ */
+
+fn lock_path(key: &str) -> PathBuf {
+    let key: String = key
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join(format!("{}-dedup-{}.lock", PROGRAM_NAME, key)),
+        None => std::env::temp_dir().join(format!(
+            "{}-{}-dedup-{}.lock",
+            PROGRAM_NAME,
+            unsafe { libc::getuid() },
+            key
+        )),
+    }
+}
+
+/* Returns the locked file, which has to be kept open as long as the dialog is
+ * shown, or None if a dialog with this key is already there.
+ */
+pub fn lock(key: &str) -> std::io::Result<Option<File>> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .mode(0o600)
+        .open(lock_path(key))?;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(Some(file));
+    }
+    let e = std::io::Error::last_os_error();
+    if e.kind() == std::io::ErrorKind::WouldBlock {
+        Ok(None)
+    } else {
+        Err(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lock;
+
+    #[test]
+    fn second_dialog_is_suppressed() {
+        let key = format!("test-{}", std::process::id());
+        let first = lock(&key).unwrap();
+        assert!(first.is_some());
+        assert!(lock(&key).unwrap().is_none());
+        drop(first);
+        assert!(lock(&key).unwrap().is_some());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{apply_themes, running_in_flatpak, Configuration};
+
+/* Report of --diagnose: everything a button depends on which isn't visible
+ * in the dialog. Each line is "name: finding", problems are marked with "!".
+ */
+
+fn env(name: &str) -> String {
+    match std::env::var(name) {
+        Ok(v) if !v.is_empty() => v,
+        _ => String::from("unset"),
+    }
+}
+
+#[cfg(not(feature = "terminal-exec"))]
+fn terminal() -> String {
+    String::from("! disabled, built without the terminal-exec feature")
+}
+
+#[cfg(all(feature = "terminal-exec", target_os = "macos"))]
+fn terminal() -> String {
+    String::from("Terminal.app")
+}
+
+#[cfg(all(feature = "terminal-exec", windows))]
+fn terminal() -> String {
+    String::from("cmd")
+}
+
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn terminal() -> String {
+    if running_in_flatpak() {
+        return format!(
+            "{} on the host, through flatpak-spawn",
+            crate::terminal::fallback_terminal().0.to_string_lossy()
+        );
+    }
+    match crate::terminal::terminal_name() {
+        Some(terminal) => terminal,
+        None => format!(
+            "! {} not found in PATH",
+            crate::terminal::fallback_terminal().0.to_string_lossy()
+        ),
+    }
+}
+
+fn display() -> String {
+    let session = format!(
+        "WAYLAND_DISPLAY: {}, DISPLAY: {}, GDK_BACKEND: {}",
+        env("WAYLAND_DISPLAY"),
+        env("DISPLAY"),
+        env("GDK_BACKEND")
+    );
+    match gtk::init() {
+        Ok(()) => format!("reachable ({})", session),
+        Err(_) => format!("! not reachable ({})", session),
+    }
+}
+
+fn layer_shell_finding(compiled: bool, wayland: bool, supported: bool) -> String {
+    if !compiled {
+        String::from("! disabled, built without the layer-shell feature")
+    } else if !wayland {
+        String::from("not applicable, this is no Wayland session")
+    } else if supported {
+        String::from("available, the compositor offers zwlr_layer_shell_v1")
+    } else {
+        String::from("! the compositor doesn't offer zwlr_layer_shell_v1, --bar can't dock")
+    }
+}
+
+// What --bar docks with on Wayland, needs gtk like the icon theme.
+fn layer_shell() -> String {
+    use glib::ObjectExt;
+    if !gtk::is_initialized() {
+        return String::from("! unknown, gtk isn't available");
+    }
+    // GDK_BACKEND=x11 runs through XWayland, where the struts are used.
+    let wayland =
+        gdk::Display::get_default().is_some_and(|d| d.get_type().name() == "GdkWaylandDisplay");
+    layer_shell_finding(
+        cfg!(feature = "layer-shell"),
+        wayland,
+        wayland && crate::bar::layer_shell_supported(),
+    )
+}
+
+// Needs gtk, so it is only looked at if the display is reachable.
+fn icon_theme() -> String {
+    use gtk::{IconThemeExt, SettingsExt};
+    if !gtk::is_initialized() {
+        return String::from("! unknown, gtk isn't available");
+    }
+    let name = gtk::Settings::get_default()
+        .and_then(|s| s.get_property_gtk_icon_theme_name())
+        .unwrap_or_else(|| String::from("unknown"));
+    match gtk::IconTheme::get_default() {
+        // Cancel always shows this icon.
+        Some(theme) if theme.has_icon("window-close") => name,
+        Some(_) => format!("! {} lacks window-close, icons may be missing", name),
+        None => format!("! {} couldn't be loaded", name),
+    }
+}
+
+fn runtime_dir() -> String {
+    #[cfg(all(feature = "terminal-exec", unix))]
+    {
+        match crate::terminal::runtime_dir() {
+            Ok(dir) => dir.to_string_lossy().into_owned(),
+            Err(e) => format!("! {}", e),
+        }
+    }
+    #[cfg(not(all(feature = "terminal-exec", unix)))]
+    String::from("not used")
+}
+
+pub fn report(config: &Configuration) -> String {
+    let display = display();
+    if gtk::is_initialized() {
+        apply_themes(config);
+    }
+    let lines = [
+        ("terminal", terminal()),
+        ("flatpak", running_in_flatpak().to_string()),
+        ("display", display),
+        ("layer-shell", layer_shell()),
+        ("icon theme", icon_theme()),
+        ("runtime directory", runtime_dir()),
+    ];
+    lines
+        .iter()
+        .map(|(name, finding)| format!("{}: {}\n", name, finding))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::layer_shell_finding;
+
+    #[test]
+    fn layer_shell_lines() {
+        assert_eq!(
+            "! disabled, built without the layer-shell feature",
+            layer_shell_finding(false, true, false)
+        );
+        assert_eq!(
+            "not applicable, this is no Wayland session",
+            layer_shell_finding(true, false, false)
+        );
+        assert_eq!(
+            "available, the compositor offers zwlr_layer_shell_v1",
+            layer_shell_finding(true, true, true)
+        );
+        assert!(layer_shell_finding(true, true, false).starts_with("! "));
+    }
+}
@@ -0,0 +1,54 @@
+use gtk::prelude::*;
+
+/* Textual description of a widget hierarchy, one widget per line and indented
+ * by depth. Only the properties that matter for the dialog are printed:
+ * names, labels, icons, style classes and default/focus/sensitivity state.
+ */
+pub fn widget_tree(widget: &gtk::Widget) -> String {
+    let mut out = String::new();
+    dump_widget(widget, 0, &mut out);
+    out
+}
+
+fn dump_widget(widget: &gtk::Widget, depth: usize, out: &mut String) {
+    out.push_str(&"  ".repeat(depth));
+    let type_name = widget.get_type().name();
+    out.push_str(type_name.as_str());
+    // Without a name of its own gtk reports the type name.
+    if let Some(name) = WidgetExt::get_name(widget).filter(|n| *n != type_name) {
+        out.push_str(&format!(" #{}", name));
+    }
+    if let Some(window) = widget.downcast_ref::<gtk::Window>() {
+        if let Some(title) = window.get_title() {
+            out.push_str(&format!(" title={:?}", title));
+        }
+    }
+    if let Some(label) = widget.downcast_ref::<gtk::Label>() {
+        out.push_str(&format!(" {:?}", label.get_text().unwrap_or_default()));
+    }
+    if let Some(image) = widget.downcast_ref::<gtk::Image>() {
+        if let Some(icon) = image.get_property_icon_name() {
+            out.push_str(&format!(" icon={}", icon));
+        }
+    }
+    if let Some(context) = widget.get_style_context() {
+        for class in context.list_classes() {
+            out.push_str(&format!(" .{}", class));
+        }
+    }
+    if widget.has_default() {
+        out.push_str(" [default]");
+    }
+    if widget.is_focus() {
+        out.push_str(" [focus]");
+    }
+    if !widget.get_sensitive() {
+        out.push_str(" [insensitive]");
+    }
+    out.push('\n');
+    if let Some(container) = widget.downcast_ref::<gtk::Container>() {
+        for child in container.get_children() {
+            dump_widget(&child, depth + 1, out);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::audit::AuditLog;
+use std::sync::mpsc::{channel, TryRecvError};
+
+const METHODS: &[&str] = &["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE"];
+
+/* Request of a --button-http button. The body is a template, {value} (or
+ * {pin}) gets replaced with the entered PIN or the value of the prompt and
+ * {label} with the label of the button.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    pub body: Option<String>,
+}
+
+impl Request {
+    pub fn new(method: &str, url: &str, body: Option<String>) -> Result<Self, String> {
+        let method = method.to_ascii_uppercase();
+        if !METHODS.contains(&method.as_str()) {
+            return Err(format!(
+                "HTTP method {} is none of {}.",
+                method,
+                METHODS.join(", ")
+            ));
+        }
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("{} is no http(s) URL.", url));
+        }
+        Ok(Request {
+            method,
+            url: String::from(url),
+            body,
+        })
+    }
+
+    pub fn body_for(&self, label: &str, value: Option<&str>) -> Option<String> {
+        let value = value.unwrap_or_default();
+        self.body.as_ref().map(|b| {
+            b.replace("{pin}", value)
+                .replace("{value}", value)
+                .replace("{label}", &crate::strip_mnemonics(label))
+        })
+    }
+
+    // Text shown below the buttons, e.g. "HTTP 200 OK"
+    fn perform(&self, body: Option<String>) -> String {
+        let request = ureq::request(&self.method, &self.url);
+        let result = match body {
+            Some(b) => request.send_string(&b),
+            None => request.call(),
+        };
+        match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => {
+                format!("HTTP {} {}", response.status(), response.status_text())
+            }
+            Err(e) => format!("Request failed: {}", e),
+        }
+    }
+}
+
+/* Sends the request in a separate thread and shows the status in `status`
+ * once it is there. With exit_after_action the program quits afterwards.
+ */
+pub fn send(
+    request: &Request,
+    label: &str,
+    pin: Option<&str>,
+    status: &gtk::Label,
+    audit_log: &Option<AuditLog>,
+    exit_after_action: bool,
+) {
+    use gtk::LabelExt;
+    use gtk::WidgetExt;
+    if let Some(log) = audit_log {
+        log.button_pressed(label, &format!("{} {}", request.method, request.url));
+    }
+    let (tx, rx) = channel();
+    let body = request.body_for(label, pin);
+    let request = request.clone();
+    std::thread::spawn(move || {
+        let _ = tx.send(request.perform(body));
+    });
+    let status = status.clone();
+    let audit_log = audit_log.clone();
+    let label = String::from(label);
+    gtk::timeout_add(100, move || match rx.try_recv() {
+        Ok(text) => {
+            status.set_text(&text);
+            status.show();
+            if let Some(log) = &audit_log {
+                log.request_finished(&label, &text);
+            }
+            if exit_after_action {
+                gtk::main_quit();
+            }
+            gtk::Continue(false)
+        }
+        Err(TryRecvError::Empty) => gtk::Continue(true),
+        Err(TryRecvError::Disconnected) => gtk::Continue(false),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Request;
+
+    #[test]
+    fn request_template() {
+        let request = Request::new(
+            "post",
+            "http://localhost/",
+            Some(String::from("{\"pin\": \"{pin}\"}")),
+        )
+        .unwrap();
+        assert_eq!("POST", request.method);
+        assert_eq!(
+            Some(String::from("{\"pin\": \"1234\"}")),
+            request.body_for("_Open", Some("1234"))
+        );
+        let request = Request::new(
+            "put",
+            "http://localhost/",
+            Some(String::from("{label}={value}")),
+        )
+        .unwrap();
+        assert_eq!(
+            Some(String::from("Volume=40")),
+            request.body_for("_Volume", Some("40"))
+        );
+        assert!(Request::new("FETCH", "http://localhost/", None).is_err());
+        assert!(Request::new("GET", "ftp://localhost/", None).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use crate::mnemonic;
+use crate::spec::{self, SpecError};
+use serde_json::Value;
+use std::ffi::OsString;
+use std::path::Path;
+
+/* Style checks of `options-window-gtk lint CONFIG`. Unlike check, everything
+ * reported here works, but could be nicer to use. Every finding comes with a
+ * concrete suggestion.
+ */
+
+// More buttons than this make the vertical button list taller than most screens want.
+const MAX_BUTTONS: usize = 6;
+
+// Substrings of actions which can't be undone.
+const DESTRUCTIVE: &[&str] = &[
+    "rm -r",
+    "rm -f",
+    "reboot",
+    "shutdown",
+    "poweroff",
+    "halt",
+    "mkfs",
+    "dd ",
+    "wipefs",
+    "kill",
+    "git push --force",
+    "git reset --hard",
+];
+
+// Icon names of the freedesktop naming spec for common label words.
+const ICONS: &[(&str, &str)] = &[
+    ("reboot", "system-reboot"),
+    ("restart", "system-reboot"),
+    ("shut", "system-shutdown"),
+    ("power", "system-shutdown"),
+    ("log out", "system-log-out"),
+    ("logout", "system-log-out"),
+    ("lock", "system-lock-screen"),
+    ("suspend", "media-playback-pause"),
+    ("delete", "edit-delete"),
+    ("remove", "edit-delete"),
+    ("clean", "edit-clear"),
+    ("open", "document-open"),
+    ("save", "document-save"),
+    ("log", "text-x-generic"),
+    ("update", "system-software-update"),
+    ("help", "help-browser"),
+];
+
+pub struct Lint {
+    pub line: Option<usize>,
+    pub message: String,
+    pub suggestion: String,
+}
+
+fn suggested_icon(label: &str) -> &'static str {
+    let label = label.replace('_', "").to_lowercase();
+    ICONS
+        .iter()
+        .find(|(word, _)| label.contains(word))
+        .map_or("dialog-information", |(_, icon)| icon)
+}
+
+// Puts the mnemonic on the first letter no other button (or Cancel) uses yet.
+fn suggested_label(label: &str, taken: &[char]) -> String {
+    let position = label
+        .char_indices()
+        .find(|(_, c)| c.is_alphanumeric() && !taken.contains(&c.to_ascii_lowercase()))
+        .map_or(0, |(i, _)| i);
+    format!("{}_{}", &label[..position], &label[position..])
+}
+
+fn is_destructive(button: &Value) -> bool {
+    if button.get("method").and_then(Value::as_str) == Some("DELETE") {
+        return true;
+    }
+    let action = button
+        .get("action")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    DESTRUCTIVE.iter().any(|d| action.contains(d))
+}
+
+pub fn lint(source: &str, spec: &Value) -> Result<Vec<Lint>, String> {
+    let map = spec::top_level(spec)?;
+    let buttons = spec::buttons(map);
+    let mut lints = Vec::new();
+    if buttons.len() > MAX_BUTTONS {
+        lints.push(Lint {
+            line: spec::locate(source, "button", MAX_BUTTONS),
+            message: format!("{} buttons don't fit a compact dialog.", buttons.len()),
+            suggestion: format!(
+                "keep at most {} and move the rest into a follow-up dialog with chain",
+                MAX_BUTTONS
+            ),
+        });
+    }
+    let labels: Vec<&str> = buttons
+        .iter()
+        .map(|b| b.get("label").and_then(Value::as_str).unwrap_or_default())
+        .collect();
+    let mut taken: Vec<char> = labels.iter().filter_map(|l| mnemonic(l)).collect();
+    taken.push('c');
+    for (i, (button, label)) in buttons.iter().zip(&labels).enumerate() {
+        let line = spec::locate(source, "label", i);
+        if mnemonic(label).is_none() {
+            let fixed = suggested_label(label, &taken);
+            taken.extend(mnemonic(&fixed));
+            lints.push(Lint {
+                line,
+                message: format!("{} has no mnemonic.", label),
+                suggestion: format!("label = \"{}\"", fixed),
+            });
+        }
+        let is_http = button.get("type").and_then(Value::as_str) == Some("http");
+        if !is_http && button.get("icon").is_none() {
+            lints.push(Lint {
+                line,
+                message: format!("{} has no icon.", label),
+                suggestion: format!("icon = \"{}\"", suggested_icon(label)),
+            });
+        }
+        let confirmed = ["confirm", "require-typing", "hold-to-confirm"]
+            .iter()
+            .any(|key| button.get(*key).is_some());
+        if is_destructive(button) && !confirmed {
+            lints.push(Lint {
+                line,
+                message: format!("{} can't be undone but needs no confirmation.", label),
+                suggestion: String::from("hold-to-confirm = 1000"),
+            });
+        }
+    }
+    Ok(lints)
+}
+
+// Entry point of `options-window-gtk lint CONFIG`, returns the exit code.
+pub fn run_lint(args: &[OsString]) -> i32 {
+    if args.len() != 1 {
+        println!("Usage: {} lint CONFIG", crate::PROGRAM_NAME);
+        return 2;
+    }
+    let path = Path::new(&args[0]);
+    let name = path.to_string_lossy();
+    let result = spec::read(path).and_then(|(source, spec)| {
+        lint(&source, &spec).map_err(|e| SpecError {
+            line: None,
+            message: e,
+        })
+    });
+    let lints = match result {
+        Ok(l) => l,
+        Err(e) => {
+            println!("{}: {}", name, e.message);
+            return 1;
+        }
+    };
+    for l in &lints {
+        match l.line {
+            Some(line) => println!(
+                "{}:{}: {} Suggestion: {}",
+                name, line, l.message, l.suggestion
+            ),
+            None => println!("{}: {} Suggestion: {}", name, l.message, l.suggestion),
+        }
+    }
+    if lints.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lint;
+
+    #[test]
+    fn lints_with_suggestions() {
+        let source = "message = \"Disk full\"\n\
+                      [[button]]\nlabel = \"_Clean up\"\naction = \"ncdu /\"\nicon = \"edit-clear\"\n\
+                      [[button]]\nlabel = \"Delete cache\"\naction = \"rm -rf ~/.cache\"\n";
+        let spec: serde_json::Value = toml::from_str(source).unwrap();
+        let lints = lint(source, &spec).unwrap();
+        let found: Vec<(Option<usize>, &str)> = lints
+            .iter()
+            .map(|l| (l.line, l.suggestion.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                (Some(7), "label = \"_Delete cache\""),
+                (Some(7), "icon = \"edit-delete\""),
+                (Some(7), "hold-to-confirm = 1000"),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn confirm_counts_as_confirmation() {
+        let source = "[[button]]\nlabel = \"_Delete cache\"\naction = \"rm -rf ~/.cache\"\n\
+                      icon = \"edit-delete\"\nconfirm = \"Delete it?\"\n";
+        let spec: serde_json::Value = toml::from_str(source).unwrap();
+        assert!(lint(source, &spec).unwrap().is_empty());
+    }
+}
@@ -1,17 +1,146 @@
+#[cfg(all(feature = "dbus", unix))]
+extern crate dbus;
+extern crate gdk;
+extern crate gdk_pixbuf;
+extern crate gio;
+extern crate glib;
 extern crate gtk;
+#[cfg(unix)]
+extern crate libc;
+extern crate pango;
+extern crate qrcode;
+#[cfg(all(feature = "terminal-exec", unix))]
 extern crate rand;
+#[cfg(feature = "http")]
+extern crate ureq;
 
+// Prints a warning to stderr unless --quiet was given.
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        if !crate::is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+mod attach;
+mod audit;
+mod bar;
+mod chain;
+#[cfg(unix)]
+mod dedup;
+mod diagnose;
+mod dump;
+#[cfg(feature = "http")]
+mod http;
+mod lint;
+mod man;
+#[cfg(feature = "notifications")]
+mod notify;
+#[cfg(all(feature = "dbus", unix))]
+mod polkit;
+mod position;
+mod preview;
+mod prompt;
+mod qr;
+mod queue;
+mod reload;
+#[cfg(feature = "scripting")]
+mod replay;
+#[cfg(feature = "dbus")]
+mod secret;
+mod server;
+#[cfg(all(feature = "dbus", unix))]
+mod signal;
+mod spec;
+mod strict;
+mod survey;
+mod terminal;
+mod wizard;
+
+use audit::AuditLog;
 use gtk::prelude::*;
-use rand::Rng;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::io::Write;
-use std::os::unix::ffi::OsStrExt;
-use std::os::unix::ffi::OsStringExt;
-use std::os::unix::fs::PermissionsExt;
+#[cfg(feature = "terminal-exec")]
+use terminal::exec_in_terminal;
 
 const PROGRAM_NAME: &str = "options-window-gtk";
 const VERSION: &str = "0.1.0";
+#[cfg(feature = "terminal-exec")]
 const DEFAULT_TERMINAL: &str = "i3-sensible-terminal";
+const FLATPAK_INFO: &str = "/.flatpak-info";
+
+static QUIET: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+fn set_quiet() {
+    QUIET.store(true, std::sync::atomic::Ordering::Relaxed);
+}
+
+fn is_quiet() -> bool {
+    QUIET.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/* Exit code with --exit-after-action: the --code of the button which ended the
+ * program, otherwise 0. Cancel and closing the window leave it at 1.
+ */
+static ACTION_EXIT_CODE: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(1);
+
+/* `-m -` reads the message from stdin, `--checklist -` the items. That happens
+ * once, the arguments are parsed again for --hot-reload.
+ */
+static STDIN_TEXT: std::sync::OnceLock<Result<String, String>> = std::sync::OnceLock::new();
+
+fn stdin_text(what: &str) -> Result<String, ParseError> {
+    let text = STDIN_TEXT.get_or_init(|| {
+        let mut input = Vec::new();
+        std::io::Read::read_to_end(&mut std::io::stdin(), &mut input)
+            .map(|_| message_text(&input))
+            .map_err(|e| e.to_string())
+    });
+    text.clone().map_err(|e| {
+        ParseError::wrong_argument(format!("Couldn't read the {} from stdin: {}", what, e))
+    })
+}
+
+// Output of other programs, without the final newline.
+fn message_text(input: &[u8]) -> String {
+    String::from_utf8_lossy(input)
+        .trim_end_matches(['\n', '\r'])
+        .to_string()
+}
+
+/* Whether an argument is the next option instead of an optional value, so
+ * such values may start with '-' as long as they don't look like -m or --x.
+ */
+#[cfg_attr(not(feature = "http"), allow(dead_code))]
+fn is_option(arg: &str) -> bool {
+    let mut chars = arg.chars();
+    if chars.next() != Some('-') {
+        return false;
+    }
+    match chars.next() {
+        Some('-') => arg.len() > 2,
+        Some(c) => c.is_ascii_alphabetic() && arg.len() == 2,
+        None => false,
+    }
+}
+
+/* With --print-pid every action gets its own process group, so a supervisor
+ * can terminate it with everything it started.
+ */
+#[cfg(unix)]
+static OWN_PROCESS_GROUP: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/* Loaded with application priority, so rules in the user's gtk.css take precedence. */
+const DEFAULT_CSS: &str = "
+separator.accent { min-height: 4px; }
+separator.accent.error { background-color: #cc0000; }
+separator.accent.warning { background-color: #edd400; }
+separator.accent.info { background-color: #3465a4; }
+label.action-status.success { color: #4e9a06; }
+label.action-status.failure { color: #cc0000; }
+";
 
 #[derive(PartialEq, Clone)]
 enum ParseErrorType {
@@ -48,6 +177,18 @@ impl ParseError {
         }
     }
 
+    pub fn feature_disabled(option: &OsStr, feature: &str) -> Self {
+        ParseError {
+            error_type: ParseErrorType::WrongArgument,
+            message: format!(
+                "{} is not available, {} was built without the {} feature.",
+                option.to_string_lossy(),
+                PROGRAM_NAME,
+                feature
+            ),
+        }
+    }
+
     pub fn version_requested() -> Self {
         ParseError {
             error_type: ParseErrorType::VersionInfoRequested,
@@ -68,12 +209,25 @@ impl std::fmt::Debug for ParseError {
 }
 
 #[derive(Clone, Debug)]
+#[allow(clippy::upper_case_acronyms)]
 enum MessageType {
+    INFO,
     WARNING,
     ERROR,
 }
 
-type CommandFunction = fn(&Command) -> std::io::Result<std::process::Child>;
+impl MessageType {
+    fn name(&self) -> &'static str {
+        match self {
+            MessageType::INFO => "info",
+            MessageType::WARNING => "warning",
+            MessageType::ERROR => "error",
+        }
+    }
+}
+
+// Prepares the process for a command, stdin and the environment are set up by the caller.
+type CommandFunction = fn(&Command) -> std::io::Result<std::process::Command>;
 
 #[derive(Clone)]
 pub struct Command {
@@ -86,103 +240,575 @@ impl Command {
         Self { command, exec }
     }
 
-    pub fn execute(&self) {
-        (self.exec)(&self).expect("Failed to spawn child process.");
+    pub fn execute(&self) -> std::process::Child {
+        self.execute_with_input(None, &SecretDelivery::Stdin, false)
     }
+
+    /* Spawns the command and hands `input` (e.g. an entered PIN) over as the
+     * delivery says. Secrets never end up in the command line, only the number
+     * of the pipe replaces {secret_fd}. With capture_stderr the caller reads
+     * stderr from the child.
+     */
+    pub fn execute_with_input(
+        &self,
+        input: Option<&[u8]>,
+        delivery: &SecretDelivery,
+        capture_stderr: bool,
+    ) -> std::process::Child {
+        let mut command = self.clone();
+        let mut stdin_data = None;
+        #[cfg(unix)]
+        let mut secret_pipe = None;
+        if let Some(data) = input {
+            match delivery {
+                SecretDelivery::Stdin => stdin_data = Some(data),
+                SecretDelivery::Env(_) => {}
+                #[cfg(unix)]
+                SecretDelivery::Fd => match secret_pipe_with(data) {
+                    Ok(pipe) => {
+                        use std::os::unix::io::AsRawFd;
+                        let fd = pipe.as_raw_fd().to_string();
+                        command.command = OsString::from(
+                            self.command.to_string_lossy().replace("{secret_fd}", &fd),
+                        );
+                        secret_pipe = Some(pipe);
+                    }
+                    Err(e) => warning!("Couldn't create secret pipe: {}", e),
+                },
+            }
+        }
+        let mut process = (self.exec)(&command).expect("Failed to prepare child process.");
+        if let (Some(data), SecretDelivery::Env(name)) = (input, delivery) {
+            process.env(name, String::from_utf8_lossy(data).as_ref());
+        }
+        #[cfg(unix)]
+        {
+            if let Some(pipe) = &secret_pipe {
+                use std::os::unix::io::AsRawFd;
+                use std::os::unix::process::CommandExt;
+                let fd = pipe.as_raw_fd();
+                // The pipe has to survive exec, std opens everything close-on-exec.
+                unsafe {
+                    process.pre_exec(move || {
+                        if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+                            return Err(std::io::Error::last_os_error());
+                        }
+                        Ok(())
+                    });
+                }
+            }
+        }
+        #[cfg(unix)]
+        {
+            if OWN_PROCESS_GROUP.load(std::sync::atomic::Ordering::Relaxed) {
+                use std::os::unix::process::CommandExt;
+                process.process_group(0);
+            }
+        }
+        process.stdin(match stdin_data {
+            Some(_) => std::process::Stdio::piped(),
+            None => std::process::Stdio::inherit(),
+        });
+        if capture_stderr {
+            process.stderr(std::process::Stdio::piped());
+        }
+        let mut child = process.spawn().expect("Failed to spawn child process.");
+        if let (Some(data), Some(mut pipe)) = (stdin_data, child.stdin.take()) {
+            if let Err(e) = pipe.write_all(data) {
+                warning!("Couldn't write to child process: {}", e);
+            }
+        }
+        child
+    }
+}
+
+/* Returns the reading end of a socket pair which already holds data. The
+ * writing end is closed, so the child reads the secret followed by EOF.
+ */
+#[cfg(unix)]
+fn secret_pipe_with(data: &[u8]) -> std::io::Result<std::os::unix::net::UnixStream> {
+    let (reader, mut writer) = std::os::unix::net::UnixStream::pair()?;
+    writer.write_all(data)?;
+    Ok(reader)
+}
+
+fn running_in_flatpak() -> bool {
+    std::path::Path::new(FLATPAK_INFO).exists()
+}
+
+/* Inside a Flatpak sandbox the commands we are asked to run usually only exist
+ * on the host, so they are forwarded through the Flatpak portal (flatpak-spawn).
+ */
+fn host_command<S: AsRef<std::ffi::OsStr>>(program: S) -> std::process::Command {
+    if running_in_flatpak() {
+        let mut cmd = std::process::Command::new("flatpak-spawn");
+        cmd.arg("--host").arg(program);
+        cmd
+    } else {
+        std::process::Command::new(program)
+    }
+}
+
+#[cfg(unix)]
+fn exec_in_shell(command: &Command) -> std::io::Result<std::process::Command> {
+    let mut cmd = host_command("/bin/sh");
+    cmd.arg("-c").arg(&command.command);
+    Ok(cmd)
+}
+
+#[cfg(windows)]
+fn exec_in_shell(command: &Command) -> std::io::Result<std::process::Command> {
+    let mut cmd = std::process::Command::new("cmd");
+    cmd.arg("/C").arg(&command.command);
+    Ok(cmd)
 }
 
-fn exec_in_shell(command: &Command) -> std::io::Result<std::process::Child> {
-    std::process::Command::new("/bin/sh")
-        .arg("-c")
-        .arg(&command.command)
-        .spawn()
+/* Opens a new window in the tmux session used last, the server picks the
+ * session itself if we aren't running inside tmux.
+ */
+fn exec_in_tmux(command: &Command) -> std::io::Result<std::process::Command> {
+    let mut cmd = host_command("tmux");
+    cmd.arg("new-window").arg(&command.command);
+    Ok(cmd)
 }
 
-/* The method used here is roughly the same as in i3-nagbar:
- * A temporary script with the command and a link to this executable is created.
- * Afterwards the terminal emulator gets called with -e <link>
- * If this executable gets called with a '.cmd' ending it starts a shell with the
- * script as parameter.
- *
- * The reason for this is that not all terminal emulators handle -e the same way.
- *
- * There might be some security issues with this...
-*/
-fn exec_in_terminal(command: &Command) -> std::io::Result<std::process::Child> {
-    let tmpdir = match std::env::var_os("XDG_RUNTIME_DIR") {
-        Some(v) => std::path::PathBuf::from(v),
-        None => std::env::temp_dir(),
+/* Opens -o buttons with the default application through gio, inside a Flatpak
+ * gio goes through the OpenURI portal. Nothing is left running to watch.
+ */
+fn open_target(
+    label: &str,
+    target: &OsStr,
+    audit_log: &Option<AuditLog>,
+    status: Option<&gtk::Label>,
+) {
+    let uri = target_uri(target);
+    if let Some(log) = audit_log {
+        log.button_pressed(label, &uri);
+    }
+    let result = gio::AppInfo::launch_default_for_uri(&uri, None::<&gio::AppLaunchContext>);
+    let summary = match &result {
+        Ok(()) => String::from("Opened"),
+        Err(e) => {
+            warning!("Couldn't open {}: {}", uri, e);
+            format!("Couldn't be opened: {}", e)
+        }
     };
-    let mut script_path = tmpdir.clone();
-    let mut link_path = tmpdir.clone();
+    if let Some(log) = audit_log {
+        log.request_finished(label, &summary);
+    }
+    if let Some(glyph) = status {
+        set_action_status(glyph, result.is_ok(), &summary);
+    }
+}
 
-    let rnd: String = rand::thread_rng()
-        .sample_iter(&rand::distributions::Alphanumeric)
-        .take(30)
-        .collect();
-    let script_name = OsString::from(format!("{}_{}.sh", PROGRAM_NAME, rnd));
-    script_path.push(script_name);
-    let link_name = OsString::from(format!("{}_{}.cmd", PROGRAM_NAME, rnd));
-    link_path.push(link_name);
-    {
-        let mut script_file = std::fs::File::create(&script_path)?;
-        let mut p = script_file.metadata()?.permissions();
-        p.set_mode(0o700);
-        script_file
-            .set_permissions(p)
-            .expect("Couldn't set permissions for script file");
-        script_file.write_all(b"#!/bin/sh\n")?;
-        script_file.write_all(b"rm ")?;
-        script_file.write_all(&script_path.as_os_str().as_bytes())?;
-        script_file.write_all(b"\n")?;
-        script_file.write_all(command.command.as_bytes())?;
-        script_file.write_all(b"\n")?;
-        script_file.flush()?;
-    }
-    std::os::unix::fs::symlink(std::env::current_exe()?, &link_path)?;
-    std::process::Command::new(DEFAULT_TERMINAL)
-        .arg("-v")
-        .arg("-e")
-        .arg(link_path.as_os_str())
-        .spawn()
+// URIs are kept, paths become file:// URIs (relative ones to the working directory).
+fn target_uri(target: &OsStr) -> String {
+    let file = gio::File::new_for_commandline_arg(target);
+    gio::FileExt::get_uri(&file).unwrap_or_else(|| target.to_string_lossy().to_string())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum GtkBackend {
+    Broadway,
+    Offscreen,
+}
+
+// Forced with --backend, keep-above and positioning only work reliably on X11 (or XWayland).
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DisplayBackend {
+    X11,
+    Wayland,
+}
+
+impl DisplayBackend {
+    fn gdk_name(self) -> &'static str {
+        match self {
+            DisplayBackend::X11 => "x11",
+            DisplayBackend::Wayland => "wayland",
+        }
+    }
+}
+
+// How an entered secret (the PIN) is handed to the button's command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SecretDelivery {
+    Stdin,
+    Env(String),
+    #[cfg(unix)]
+    Fd,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum FocusTarget {
+    Button(usize),
+    Cancel,
+}
+
+// How --layout arranges the buttons.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ButtonLayout {
+    Vertical,
+    Horizontal,
+}
+
+// Where the icon of a button goes, Only shows the label as tooltip.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum IconLayout {
+    Right,
+    Left,
+    Only,
+}
+
+/* Size of an icon as GtkIconSize or in pixels, set with --icon-size and
+ * --button-icon-size. Pixels override the size of theme icons.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum IconSize {
+    Named(i32),
+    Pixels(i32),
+}
+
+// The names of GtkIconSize, their value and their usual size in pixels.
+const ICON_SIZES: &[(&str, i32, i32)] = &[
+    ("menu", 1, 16),
+    ("small-toolbar", 2, 16),
+    ("large-toolbar", 3, 24),
+    ("button", 4, 16),
+    ("dnd", 5, 32),
+    ("dialog", 6, 48),
+];
+
+impl IconSize {
+    fn parse(value: &str) -> Option<IconSize> {
+        if let Some((_, size, _)) = ICON_SIZES
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(value))
+        {
+            return Some(IconSize::Named(*size));
+        }
+        match value.parse::<i32>() {
+            Ok(pixels) if pixels > 0 => Some(IconSize::Pixels(pixels)),
+            _ => None,
+        }
+    }
+
+    // Image files are scaled to this.
+    fn pixels(self) -> i32 {
+        match self {
+            IconSize::Named(size) => ICON_SIZES
+                .iter()
+                .find(|(_, s, _)| *s == size)
+                .map_or(16, |(_, _, pixels)| *pixels),
+            IconSize::Pixels(pixels) => pixels,
+        }
+    }
+}
+
+// Set with --style, GTK themes draw destructive buttons red and suggested ones blue.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ButtonStyle {
+    Normal,
+    Destructive,
+    Suggested,
+}
+
+// What pressing a button does.
+#[derive(Clone)]
+enum Action {
+    Command(Command),
+    // Opened by gio with the default application
+    Open(OsString),
+    // Sent by the dialog itself, no process is started
+    #[cfg(feature = "http")]
+    Http(http::Request),
 }
 
 #[derive(Clone)]
 pub struct Button {
     label: String,
     icon: Option<OsString>,
-    command: Command,
+    action: Action,
+    require_typing: Option<String>,
+    hold_to_confirm: Option<u32>,
+    icon_layout: IconLayout,
+    // Exit code of the program with --exit-after-action
+    exit_code: Option<i32>,
+    // --keep-open or --close, overrides --exit-after-action
+    exit_after: Option<bool>,
+    tooltip: Option<String>,
+    style: ButtonStyle,
+    // Question of --confirm, asked before the action runs
+    confirm: Option<String>,
+    // Label and command of the action on middle or right click
+    secondary: Option<(String, Action)>,
+}
+
+/* Gets the index of the pressed button (None for cancel) and the entered PIN.
+ * If set, it replaces running the action, used by --serve.
+ */
+type ChoiceHandler = std::rc::Rc<dyn Fn(Option<usize>, Option<String>)>;
+
+// What --print writes for the pressed button.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PrintMode {
+    Label,
+    Index,
+    // Only the PIN or the value of the prompt
+    Value,
 }
 
 #[derive(Clone)]
 pub struct Configuration {
     message: String,
     exit_after_action: bool,
+    no_cancel: bool,
+    layout: ButtonLayout,
+    columns: Option<u32>,
+    cancel_label: String,
+    cancel_action: Option<Command>,
     message_type: MessageType,
     buttons: Vec<Button>,
+    // Name and first button of each --tab
+    tabs: Vec<(String, usize)>,
+    audit_log: Option<AuditLog>,
+    pin: bool,
+    prompt: Option<prompt::Prompt>,
+    pin_length: Option<u32>,
+    qr_code: Option<qrcode::QrCode>,
+    show_elapsed: bool,
+    image: Option<std::path::PathBuf>,
+    footer: Option<String>,
+    title: Option<String>,
+    class: Option<String>,
+    padding: u32,
+    spacing: i32,
+    border_width: u32,
+    icon_size: IconSize,
+    button_icon_size: IconSize,
+    button_min_width: Option<u32>,
+    width: Option<u32>,
+    height: Option<u32>,
+    position: position::Position,
+    offset: (i32, i32),
+    no_keep_above: bool,
+    no_sticky: bool,
+    no_urgent: bool,
+    headerbar: bool,
+    attach: Option<std::os::raw::c_ulong>,
+    focus: Option<FocusTarget>,
+    default_button: Option<FocusTarget>,
+    focus_order: Vec<FocusTarget>,
+    button_timeouts: Vec<(FocusTarget, u32)>,
+    // Seconds without interaction and the button pressed then
+    idle_timeout: Option<(u32, FocusTarget)>,
+    dedup: Option<String>,
+    delay: Option<u32>,
+    // --at as hour and minute, turned into a delay once the program runs
+    at: Option<(u32, u32)>,
+    cancel_if: Option<Command>,
+    close_when: Option<Command>,
+    poll_interval: Option<u32>,
+    // --if-succeeds (true) or --if-failed (false)
+    show_if: Option<(Command, bool)>,
+    screenshot: Option<std::path::PathBuf>,
+    dump_ui: bool,
+    dump_config: bool,
+    diagnose: bool,
+    #[cfg(all(feature = "dbus", unix))]
+    polkit_agent: bool,
+    #[cfg(all(feature = "dbus", unix))]
+    instance: String,
+    gtk_backend: Option<GtkBackend>,
+    backend: Option<DisplayBackend>,
+    terminal_mode: terminal::TerminalMode,
+    terminal: Option<OsString>,
+    terminal_template: Option<String>,
+    gtk_theme: Option<String>,
+    icon_theme: Option<String>,
+    // --dark or --light, the last one given counts
+    prefer_dark: Option<bool>,
+    serve: Option<String>,
+    pipe_to: Option<Command>,
+    print: Option<PrintMode>,
+    notify_result: bool,
+    print_pid: Option<std::path::PathBuf>,
+    show_status: bool,
+    queue: bool,
+    chain: Option<std::path::PathBuf>,
+    // The PIN or value entered in the previous step of a chain, passed on like a PIN.
+    chained_input: Option<String>,
+    wizard: Option<std::path::PathBuf>,
+    hot_reload: bool,
+    bar: bool,
+    survey: Option<std::path::PathBuf>,
+    strict: bool,
+    preview_types: bool,
+    on_choice: Option<ChoiceHandler>,
+    align: Option<gtk::Justification>,
+    markup: bool,
+    font: Option<String>,
+    css: Option<std::path::PathBuf>,
+    text_scale: Option<f64>,
+    secret_delivery: SecretDelivery,
+    #[cfg(feature = "dbus")]
+    secret_store: Option<secret::SecretStore>,
+    #[cfg(feature = "scripting")]
+    replay: Vec<replay::Step>,
 }
 
 impl Configuration {
     pub fn new(args: &[OsString]) -> Result<Self, ParseError> {
+        let expanded = spec::expand(args)?;
+        let args = expanded.as_slice();
         let mut config = Configuration {
             buttons: Vec::new(),
+            tabs: Vec::new(),
             message_type: MessageType::ERROR,
             exit_after_action: false,
+            no_cancel: false,
+            layout: ButtonLayout::Vertical,
+            columns: None,
+            cancel_label: String::from("_Cancel"),
+            cancel_action: None,
             message: String::from("This could be your text!"),
+            audit_log: None,
+            pin: false,
+            prompt: None,
+            pin_length: None,
+            qr_code: None,
+            show_elapsed: false,
+            image: None,
+            footer: None,
+            title: None,
+            class: None,
+            padding: 0,
+            spacing: 5,
+            icon_size: IconSize::Named(6),
+            button_icon_size: IconSize::Named(4),
+            border_width: 10,
+            button_min_width: None,
+            width: None,
+            height: None,
+            position: position::Position::Center,
+            offset: (0, 0),
+            no_keep_above: false,
+            no_sticky: false,
+            no_urgent: false,
+            headerbar: false,
+            attach: None,
+            focus: None,
+            default_button: None,
+            focus_order: Vec::new(),
+            button_timeouts: Vec::new(),
+            idle_timeout: None,
+            dedup: None,
+            delay: None,
+            at: None,
+            cancel_if: None,
+            close_when: None,
+            poll_interval: None,
+            show_if: None,
+            screenshot: None,
+            dump_ui: false,
+            dump_config: false,
+            diagnose: false,
+            #[cfg(all(feature = "dbus", unix))]
+            polkit_agent: false,
+            #[cfg(all(feature = "dbus", unix))]
+            instance: signal::default_instance(),
+            gtk_backend: None,
+            backend: None,
+            terminal_mode: terminal::TerminalMode::Auto,
+            terminal: None,
+            terminal_template: None,
+            gtk_theme: None,
+            icon_theme: None,
+            prefer_dark: None,
+            serve: None,
+            pipe_to: None,
+            print: None,
+            notify_result: false,
+            print_pid: None,
+            show_status: false,
+            queue: false,
+            chain: None,
+            wizard: None,
+            hot_reload: false,
+            bar: false,
+            survey: None,
+            strict: false,
+            preview_types: false,
+            on_choice: None,
+            chained_input: None,
+            align: None,
+            markup: false,
+            font: None,
+            css: None,
+            text_scale: None,
+            secret_delivery: SecretDelivery::Stdin,
+            #[cfg(feature = "dbus")]
+            secret_store: None,
+            #[cfg(feature = "scripting")]
+            replay: Vec::new(),
         };
+        let mut message_given = false;
+        // Options which read stdin, only one of them gets it.
+        let mut stdin_users: Vec<String> = Vec::new();
+        // The PIN or a prompt's value would reach the terminal emulator, not the action.
+        #[cfg_attr(not(feature = "terminal-exec"), allow(unused_mut))]
+        let mut terminal_buttons = false;
+        // --delay or --in, both set the delay.
+        let mut delay_option: Option<&str> = None;
+        let mut focus_label: Option<String> = None;
+        let mut default_button: Option<String> = None;
+        let mut timeout_labels: Vec<(String, u32)> = Vec::new();
+        let mut idle_timeout: Option<(u32, Option<String>)> = None;
+        let mut focus_order: Option<String> = None;
+        #[cfg(feature = "scripting")]
+        let mut replay_file: Option<std::path::PathBuf> = None;
 
         let mut pos = 1;
         while pos < args.len() {
             let a = &args[pos];
-            if a.eq("-m") || a.eq("--message") {
+            if !option_names().any(|name| a.eq(name)) {
+                return Err(ParseError::wrong_argument(format!(
+                    "Unexpected argument: {}",
+                    a.to_string_lossy()
+                )));
+            }
+            if a.eq("-m") || a.eq("--message") || a.eq("--message-file") {
                 pos += 1;
                 let msg_opt = Configuration::get_argument(pos, &args);
                 if msg_opt.is_none() {
-                    return Err(ParseError::missing_argument(
-                        "Required argument for -m is missing.",
-                    ));
+                    return Err(ParseError::missing_argument(format!(
+                        "Required argument for {} is missing.",
+                        a.to_string_lossy()
+                    )));
+                }
+                let paragraph = if a.eq("--message-file") {
+                    let path = msg_opt.unwrap();
+                    let content = std::fs::read(path).map_err(|e| {
+                        ParseError::wrong_argument(format!(
+                            "Couldn't read message file {}: {}",
+                            path.to_string_lossy(),
+                            e
+                        ))
+                    })?;
+                    std::borrow::Cow::from(message_text(&content))
+                } else if msg_opt.unwrap() == "-" {
+                    stdin_users.push(String::from("-m -"));
+                    std::borrow::Cow::from(stdin_text("message")?)
+                } else {
+                    msg_opt.unwrap().to_string_lossy()
+                };
+                // Every further -m starts a new paragraph.
+                if message_given {
+                    config.message.push_str("\n\n");
+                    config.message.push_str(&paragraph);
+                } else {
+                    config.message = String::from(paragraph);
+                    message_given = true;
                 }
-                config.message = String::from(msg_opt.unwrap().to_string_lossy());
             } else if a.eq("-t") || a.eq("--type") {
                 pos += 1;
                 let type_opt = Configuration::get_argument(pos, &args);
@@ -194,276 +820,5063 @@ impl Configuration {
                 let msg_type = type_opt.unwrap().to_string_lossy();
                 if msg_type.eq_ignore_ascii_case("warning") {
                     config.message_type = MessageType::WARNING;
-                } else if !msg_type.eq_ignore_ascii_case("error") {
+                } else if msg_type.eq_ignore_ascii_case("info") {
+                    config.message_type = MessageType::INFO;
+                } else if msg_type.eq_ignore_ascii_case("error") {
+                    config.message_type = MessageType::ERROR;
+                } else {
                     return Err(ParseError::wrong_argument(format!(
-                        "Parameter for -t ({}) was neither warning nor error.",
+                        "Parameter for -t ({}) was neither info, warning nor error.",
                         msg_type
                     )));
                 }
             } else if a.eq("-b") || a.eq("--button") {
-                let button = Configuration::create_button(&mut pos, args, exec_in_terminal)?;
-                config.buttons.push(button);
+                #[cfg(feature = "terminal-exec")]
+                {
+                    let button = Configuration::create_button(&mut pos, args, |a| {
+                        Action::Command(Command::new(a, exec_in_terminal))
+                    })?;
+                    config.buttons.push(button);
+                    terminal_buttons = true;
+                }
+                #[cfg(not(feature = "terminal-exec"))]
+                return Err(ParseError::feature_disabled(a, "terminal-exec"));
             } else if a.eq("-B") || a.eq("--button-no-terminal") {
-                let button = Configuration::create_button(&mut pos, &args, exec_in_shell)?;
+                let button = Configuration::create_button(&mut pos, args, |a| {
+                    Action::Command(Command::new(a, exec_in_shell))
+                })?;
                 config.buttons.push(button);
-            } else if a.eq("--exit-after-action") {
-                config.exit_after_action = true;
-            } else if a.eq("-f") || a.eq("--font") {
-                pos += 1
-            // don't handle fonts...
-            } else if a.eq("-h") || a.eq("--help") {
-                return Err(ParseError::help_requested());
-            } else if a.eq("-v") || a.eq("--version") {
-                return Err(ParseError::version_requested());
-            } else {
-                return Err(ParseError::wrong_argument(format!(
-                    "Unexpected argument: {}",
-                    a.to_string_lossy()
-                )));
-            }
-            pos += 1;
-        }
-        Ok(config)
-    }
-
-    fn create_button(
-        pos: &mut usize,
-        args: &[OsString],
-        cmd_func: CommandFunction,
-    ) -> Result<Button, ParseError> {
-        *pos += 1;
-        let label_opt = Configuration::get_argument(*pos, &args);
-        if label_opt.is_none() {
-            return Err(ParseError::missing_argument("Missing label for Button."));
-        }
-        let label = label_opt.unwrap().to_string_lossy().to_string();
-        *pos += 1;
-
-        let action_opt = Configuration::get_argument(*pos, &args);
-        if action_opt.is_none() {
-            return Err(ParseError::missing_argument("Missing action for Button."));
-        }
-        let action = action_opt.unwrap().clone();
-        let icon = match Configuration::get_argument(*pos + 1, &args) {
-            Some(v) => {
-                if v.as_bytes().starts_with(b"-") {
-                    None
+            } else if a.eq("-o") || a.eq("--button-open") {
+                let button = Configuration::create_button(&mut pos, args, Action::Open)?;
+                config.buttons.push(button);
+            } else if a.eq("--button-tmux") {
+                let button = Configuration::create_button(&mut pos, args, |a| {
+                    Action::Command(Command::new(a, exec_in_tmux))
+                })?;
+                config.buttons.push(button);
+            } else if a.eq("--button-http") {
+                #[cfg(feature = "http")]
+                {
+                    let button = Configuration::create_http_button(&mut pos, args)?;
+                    config.buttons.push(button);
+                }
+                #[cfg(not(feature = "http"))]
+                return Err(ParseError::feature_disabled(a, "http"));
+            } else if a.eq("--tab") {
+                pos += 1;
+                let name_opt = Configuration::get_argument(pos, args);
+                if name_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --tab is missing.",
+                    ));
+                }
+                let name = name_opt.unwrap().to_string_lossy().to_string();
+                config.tabs.push((name, config.buttons.len()));
+            } else if a.eq("--require-typing") {
+                pos += 1;
+                let phrase_opt = Configuration::get_argument(pos, args);
+                if phrase_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --require-typing is missing.",
+                    ));
+                }
+                let button = Configuration::last_button(&mut config, "--require-typing")?;
+                button.require_typing = Some(phrase_opt.unwrap().to_string_lossy().to_string());
+            } else if a.eq("--hold-to-confirm") {
+                pos += 1;
+                let ms_opt = Configuration::get_argument(pos, args);
+                if ms_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --hold-to-confirm is missing.",
+                    ));
+                }
+                let ms_str = ms_opt.unwrap().to_string_lossy();
+                let ms = match ms_str.parse::<u32>() {
+                    Ok(v) if v > 0 => v,
+                    _ => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --hold-to-confirm ({}) is not a positive number.",
+                            ms_str
+                        )))
+                    }
+                };
+                let button = Configuration::last_button(&mut config, "--hold-to-confirm")?;
+                button.hold_to_confirm = Some(ms);
+            } else if a.eq("--code") {
+                pos += 1;
+                let code_opt = Configuration::get_argument(pos, args);
+                if code_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --code is missing.",
+                    ));
+                }
+                let code_str = code_opt.unwrap().to_string_lossy();
+                let code = match code_str.parse::<i32>() {
+                    Ok(v) if (0..=255).contains(&v) => v,
+                    _ => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --code ({}) is not an exit code from 0 to 255.",
+                            code_str
+                        )))
+                    }
+                };
+                let button = Configuration::last_button(&mut config, "--code")?;
+                button.exit_code = Some(code);
+            } else if a.eq("--tooltip") {
+                pos += 1;
+                let tooltip_opt = Configuration::get_argument(pos, args);
+                if tooltip_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --tooltip is missing.",
+                    ));
+                }
+                let tooltip = tooltip_opt.unwrap().to_string_lossy().to_string();
+                let button = Configuration::last_button(&mut config, "--tooltip")?;
+                button.tooltip = Some(tooltip);
+            } else if a.eq("--style") {
+                pos += 1;
+                let style_opt = Configuration::get_argument(pos, args);
+                if style_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --style is missing.",
+                    ));
+                }
+                let style = style_opt.unwrap().to_string_lossy();
+                let button_style = if style.eq_ignore_ascii_case("destructive") {
+                    ButtonStyle::Destructive
+                } else if style.eq_ignore_ascii_case("suggested") {
+                    ButtonStyle::Suggested
+                } else if style.eq_ignore_ascii_case("normal") {
+                    ButtonStyle::Normal
                 } else {
-                    *pos += 1;
-                    Some(v.clone())
+                    return Err(ParseError::wrong_argument(format!(
+                        "Parameter for --style ({}) was neither destructive, suggested nor normal.",
+                        style
+                    )));
+                };
+                let button = Configuration::last_button(&mut config, "--style")?;
+                button.style = button_style;
+            } else if a.eq("--confirm") {
+                pos += 1;
+                let question_opt = Configuration::get_argument(pos, args);
+                if question_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --confirm is missing.",
+                    ));
                 }
-            }
-            None => None,
-        };
-        let button = Button {
-            label,
-            icon,
-            command: Command::new(action, cmd_func),
-        };
-        Ok(button)
-    }
-
-    fn get_argument<P>(pos: usize, args: &[P]) -> Option<&P> {
-        if pos < args.len() {
-            return Some(&args[pos]);
-        }
-        None
-    }
-}
-
-fn create_gtk_window(
-    buttons: &gtk::Box,
-    default_button: &gtk::Button,
-    message: &gtk::Box,
-) -> gtk::Window {
-    let window = gtk::Window::new(gtk::WindowType::Toplevel);
-    window.set_keep_above(true);
-    window.stick();
-    window.set_urgency_hint(true);
-    window.set_title(PROGRAM_NAME);
-    let content = gtk::Box::new(gtk::Orientation::Vertical, 5);
-    content.add(message);
-    content.add(buttons);
-    window.set_border_width(10);
-    window.set_position(gtk::WindowPosition::Center);
-    window.add(&content);
-    window.set_resizable(false);
-    default_button.set_can_default(true);
-    window.set_default(default_button);
-    window.activate_focus();
-    default_button.grab_focus();
-    window
-}
-
-fn create_gtk_message(config: &Configuration) -> gtk::Box {
-    let hbox = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-    let icon = match config.message_type {
-        MessageType::ERROR => gtk::Image::new_from_icon_name("dialog-error", 6),
-        MessageType::WARNING => gtk::Image::new_from_icon_name("dialog-warning", 6),
-    };
-    let label = gtk::Label::new(config.message.as_str());
-    hbox.add(&icon);
-    hbox.add(&label);
-    hbox
-}
-
-fn create_gtk_button(caption: &str, icon: &Option<OsString>) -> gtk::Button {
-    let gtk_button = gtk::Button::new();
-    let b_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-    let label = gtk::Label::new_with_mnemonic(caption);
-    label.set_halign(gtk::Align::Center);
-    b_box.pack_start(&label, true, true, 0);
-    if let Some(v) = &icon {
-        let image = gtk::Image::new_from_icon_name(v.to_string_lossy().as_ref(), 4);
-        image.set_halign(gtk::Align::Start);
-        b_box.pack_end(&image, false, true, 0);
-    }
-    gtk_button.add(&b_box);
-    gtk_button
-}
-
-fn create_gtk_buttons(config: &Configuration) -> (gtk::Box, gtk::Button) {
-    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 5);
-
-    for button in &config.buttons {
-        let gtk_button = create_gtk_button(button.label.as_str(), &button.icon);
-        vbox.pack_start(&gtk_button, true, true, 0);
-        let button_clone = button.clone();
-        if config.exit_after_action {
-            gtk_button.connect_clicked(move |_| {
-                button_clone.command.execute();
-                gtk::main_quit();
-            });
-        } else {
-            gtk_button.connect_clicked(move |_| {
-                button_clone.command.execute();
-            });
-        }
-    }
-    let button2 = create_gtk_button("_Cancel", &Some(OsString::from("window-close")));
-    button2.connect_clicked(|_| {
-        gtk::main_quit();
-    });
-    vbox.add(&button2);
-    (vbox, button2)
-}
-
-fn show_version() {
-    println!("{} {}", PROGRAM_NAME, VERSION);
-}
-
-fn usage_short() {
-    println!("Usage: {} [-h] [-v] [-b label action [icon]]... [-B label action [icon]]... [-t warning|error] [-m message] [-f font]", PROGRAM_NAME);
-}
-
-fn usage_long() {
-    println!("Usage:");
-    println!("  {} [OPTION]...", PROGRAM_NAME);
-    println!();
-    println!("Options:");
-    println!("  -h, --help                                     Prints help information");
-    println!("  -v, --version                                  Prints version information");
-    println!("  -b, --button LABEL ACTION [ICON]               Creates a button.");
-    println!("  -B, --button-no-terminal LABEL ACTION [ICON]   Creates a button.");
-    println!("  -m, --message MSG                              Sets the window caption");
-    println!(
-        "  -t, --type warning|error                       Default: error. Defines the window icon"
+                let question = question_opt.unwrap().to_string_lossy().to_string();
+                let button = Configuration::last_button(&mut config, "--confirm")?;
+                button.confirm = Some(question);
+            } else if a.eq("--keep-open") {
+                let button = Configuration::last_button(&mut config, "--keep-open")?;
+                button.exit_after = Some(false);
+            } else if a.eq("--close") {
+                let button = Configuration::last_button(&mut config, "--close")?;
+                button.exit_after = Some(true);
+            } else if a.eq("--secondary") {
+                let label_opt = Configuration::get_argument(pos + 1, args);
+                let action_opt = Configuration::get_argument(pos + 2, args);
+                if label_opt.is_none() || action_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "--secondary needs a label and an action.",
+                    ));
+                }
+                let label = label_opt.unwrap().to_string_lossy().to_string();
+                let action = action_opt.unwrap().clone();
+                pos += 2;
+                let button = Configuration::last_button(&mut config, "--secondary")?;
+                let secondary = match &button.action {
+                    Action::Command(command) => Action::Command(Command::new(action, command.exec)),
+                    Action::Open(_) => Action::Open(action),
+                    #[cfg(feature = "http")]
+                    Action::Http(_) => {
+                        return Err(ParseError::wrong_argument(
+                            "HTTP buttons can't have a --secondary action.",
+                        ))
+                    }
+                };
+                button.secondary = Some((label, secondary));
+            } else if a.eq("--icon-position") {
+                pos += 1;
+                let position_opt = Configuration::get_argument(pos, args);
+                if position_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --icon-position is missing.",
+                    ));
+                }
+                let position = position_opt.unwrap().to_string_lossy();
+                let layout = if position.eq_ignore_ascii_case("left") {
+                    IconLayout::Left
+                } else if position.eq_ignore_ascii_case("right") {
+                    IconLayout::Right
+                } else {
+                    return Err(ParseError::wrong_argument(format!(
+                        "Parameter for --icon-position ({}) was neither left nor right.",
+                        position
+                    )));
+                };
+                let button = Configuration::last_button(&mut config, "--icon-position")?;
+                button.icon_layout = layout;
+            } else if a.eq("--icon-only") {
+                let button = Configuration::last_button(&mut config, "--icon-only")?;
+                if button.icon.is_none() {
+                    return Err(ParseError::wrong_argument(
+                        "--icon-only needs a button with an icon.",
+                    ));
+                }
+                button.icon_layout = IconLayout::Only;
+            } else if a.eq("--button-timeout") {
+                let label_opt = Configuration::get_argument(pos + 1, args);
+                let secs_opt = Configuration::get_argument(pos + 2, args);
+                if label_opt.is_none() || secs_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "--button-timeout needs a label and the seconds.",
+                    ));
+                }
+                let secs_str = secs_opt.unwrap().to_string_lossy();
+                let secs = match secs_str.parse::<u32>() {
+                    Ok(v) if v > 0 => v,
+                    _ => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --button-timeout ({}) is not a positive number.",
+                            secs_str
+                        )))
+                    }
+                };
+                timeout_labels.push((label_opt.unwrap().to_string_lossy().to_string(), secs));
+                pos += 2;
+            } else if a.eq("--timeout") {
+                pos += 1;
+                let secs_opt = Configuration::get_argument(pos, args);
+                if secs_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --timeout is missing.",
+                    ));
+                }
+                let secs_str = secs_opt.unwrap().to_string_lossy();
+                let secs = match secs_str.parse::<u32>() {
+                    Ok(v) if v > 0 => v,
+                    _ => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --timeout ({}) is not a positive number.",
+                            secs_str
+                        )))
+                    }
+                };
+                // The button is optional, without one the dialog is cancelled.
+                let button = match Configuration::get_argument(pos + 1, args) {
+                    Some(v) if !v.to_string_lossy().starts_with('-') => {
+                        pos += 1;
+                        Some(v.to_string_lossy().to_string())
+                    }
+                    _ => None,
+                };
+                idle_timeout = Some((secs, button));
+            } else if a.eq("--pin") {
+                config.pin = true;
+                if let Some(v) = Configuration::get_argument(pos + 1, args) {
+                    if let Ok(length) = v.to_string_lossy().parse::<u32>() {
+                        if length == 0 {
+                            return Err(ParseError::wrong_argument(
+                                "The PIN length of --pin has to be at least 1.",
+                            ));
+                        }
+                        config.pin_length = Some(length);
+                        pos += 1;
+                    }
+                }
+            } else if a.eq("--password") {
+                Configuration::set_prompt(&mut config, prompt::Prompt::Password)?;
+            } else if a.eq("--checklist") {
+                let items =
+                    Configuration::get_items(&mut pos, args, "--checklist", &mut stdin_users)?;
+                Configuration::set_prompt(&mut config, prompt::Prompt::Checklist(items))?;
+            } else if a.eq("--radiolist") {
+                let items =
+                    Configuration::get_items(&mut pos, args, "--radiolist", &mut stdin_users)?;
+                Configuration::set_prompt(&mut config, prompt::Prompt::Radiolist(items))?;
+            } else if a.eq("--combo") {
+                let items = Configuration::get_items(&mut pos, args, "--combo", &mut stdin_users)?;
+                let combo = prompt::Prompt::Combo {
+                    items,
+                    editable: false,
+                };
+                Configuration::set_prompt(&mut config, combo)?;
+            } else if a.eq("--scale") {
+                let scale = Configuration::get_scale_arguments(&mut pos, args)?;
+                Configuration::set_prompt(&mut config, scale)?;
+            } else if a.eq("--editable") {
+                match &mut config.prompt {
+                    Some(prompt::Prompt::Combo { editable, .. }) => *editable = true,
+                    _ => {
+                        return Err(ParseError::wrong_argument(
+                            "--editable has to follow --combo.",
+                        ))
+                    }
+                }
+            } else if a.eq("--secret-via") {
+                pos += 1;
+                let via_opt = Configuration::get_argument(pos, args);
+                if via_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --secret-via is missing.",
+                    ));
+                }
+                let via = via_opt.unwrap().to_string_lossy();
+                config.secret_delivery = match via.as_ref() {
+                    "stdin" => SecretDelivery::Stdin,
+                    #[cfg(unix)]
+                    "fd" => SecretDelivery::Fd,
+                    v if v.starts_with("env:") && v.len() > 4 => {
+                        SecretDelivery::Env(String::from(&v[4..]))
+                    }
+                    _ => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --secret-via ({}) was neither stdin, env:NAME nor fd.",
+                            via
+                        )))
+                    }
+                };
+            } else if a.eq("--store-secret") {
+                #[cfg(feature = "dbus")]
+                {
+                    pos += 1;
+                    let store_opt = Configuration::get_argument(pos, args);
+                    if store_opt.is_none() {
+                        return Err(ParseError::missing_argument(
+                            "Required argument for --store-secret is missing.",
+                        ));
+                    }
+                    let store = secret::SecretStore::parse(&store_opt.unwrap().to_string_lossy())
+                        .map_err(ParseError::wrong_argument)?;
+                    config.secret_store = Some(store);
+                }
+                #[cfg(not(feature = "dbus"))]
+                return Err(ParseError::feature_disabled(a, "dbus"));
+            } else if a.eq("--qr") {
+                pos += 1;
+                let qr_opt = Configuration::get_argument(pos, args);
+                if qr_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --qr is missing.",
+                    ));
+                }
+                let code = qr::encode(&qr_opt.unwrap().to_string_lossy())
+                    .map_err(ParseError::wrong_argument)?;
+                config.qr_code = Some(code);
+            } else if a.eq("--show-elapsed") {
+                config.show_elapsed = true;
+            } else if a.eq("--image") {
+                pos += 1;
+                let image_opt = Configuration::get_argument(pos, args);
+                if image_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --image is missing.",
+                    ));
+                }
+                config.image = Some(std::path::PathBuf::from(image_opt.unwrap()));
+            } else if a.eq("--footer") {
+                pos += 1;
+                let footer_opt = Configuration::get_argument(pos, args);
+                if footer_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --footer is missing.",
+                    ));
+                }
+                config.footer = Some(footer_opt.unwrap().to_string_lossy().to_string());
+            } else if a.eq("--title") {
+                pos += 1;
+                let title_opt = Configuration::get_argument(pos, args);
+                if title_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --title is missing.",
+                    ));
+                }
+                config.title = Some(title_opt.unwrap().to_string_lossy().to_string());
+            } else if a.eq("--class") {
+                pos += 1;
+                let class_opt = Configuration::get_argument(pos, args);
+                if class_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --class is missing.",
+                    ));
+                }
+                let class = class_opt.unwrap().to_string_lossy().to_string();
+                if class.is_empty() || class.contains(char::is_whitespace) {
+                    return Err(ParseError::wrong_argument(format!(
+                        "Parameter for --class ({}) has to be a single word.",
+                        class
+                    )));
+                }
+                config.class = Some(class);
+            } else if a.eq("--padding") {
+                pos += 1;
+                config.padding = Configuration::get_number_argument(pos, args, "--padding")?;
+            } else if a.eq("--spacing") {
+                pos += 1;
+                let spacing = Configuration::get_number_argument(pos, args, "--spacing")?;
+                config.spacing = std::convert::TryFrom::try_from(spacing).map_err(|_| {
+                    ParseError::wrong_argument(format!(
+                        "Parameter for --spacing ({}) is too large.",
+                        spacing
+                    ))
+                })?;
+            } else if a.eq("--icon-size") {
+                pos += 1;
+                config.icon_size = Configuration::get_icon_size_argument(pos, args, "--icon-size")?;
+            } else if a.eq("--button-icon-size") {
+                pos += 1;
+                config.button_icon_size =
+                    Configuration::get_icon_size_argument(pos, args, "--button-icon-size")?;
+            } else if a.eq("--border-width") {
+                pos += 1;
+                config.border_width =
+                    Configuration::get_number_argument(pos, args, "--border-width")?;
+            } else if a.eq("--width") {
+                pos += 1;
+                config.width = Some(Configuration::get_number_argument(pos, args, "--width")?);
+            } else if a.eq("--height") {
+                pos += 1;
+                config.height = Some(Configuration::get_number_argument(pos, args, "--height")?);
+            } else if a.eq("--position") {
+                pos += 1;
+                let position_opt = Configuration::get_argument(pos, args);
+                if position_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --position is missing.",
+                    ));
+                }
+                let value = position_opt.unwrap().to_string_lossy();
+                config.position = match position::parse(&value) {
+                    Some(p) => p,
+                    None => {
+                        return Err(ParseError::wrong_argument(format!(
+                        "Parameter for --position ({}) is neither an edge like top-left nor X,Y.",
+                        value
+                    )))
+                    }
+                };
+            } else if a.eq("--offset") {
+                pos += 1;
+                let offset_opt = Configuration::get_argument(pos, args);
+                if offset_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --offset is missing.",
+                    ));
+                }
+                let value = offset_opt.unwrap().to_string_lossy();
+                config.offset = match position::parse_point(&value) {
+                    Some(p) => p,
+                    None => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --offset ({}) is not X,Y in pixels.",
+                            value
+                        )))
+                    }
+                };
+            } else if a.eq("--button-min-width") {
+                pos += 1;
+                config.button_min_width = Some(Configuration::get_number_argument(
+                    pos,
+                    args,
+                    "--button-min-width",
+                )?);
+            } else if a.eq("--focus") {
+                pos += 1;
+                let focus_opt = Configuration::get_argument(pos, args);
+                if focus_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --focus is missing.",
+                    ));
+                }
+                focus_label = Some(focus_opt.unwrap().to_string_lossy().to_string());
+            } else if a.eq("--default") {
+                pos += 1;
+                let default_opt = Configuration::get_argument(pos, args);
+                if default_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --default is missing.",
+                    ));
+                }
+                default_button = Some(default_opt.unwrap().to_string_lossy().to_string());
+            } else if a.eq("--focus-order") {
+                pos += 1;
+                let order_opt = Configuration::get_argument(pos, args);
+                if order_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --focus-order is missing.",
+                    ));
+                }
+                focus_order = Some(order_opt.unwrap().to_string_lossy().to_string());
+            } else if a.eq("--screenshot") {
+                pos += 1;
+                let path_opt = Configuration::get_argument(pos, args);
+                if path_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --screenshot is missing.",
+                    ));
+                }
+                config.screenshot = Some(std::path::PathBuf::from(path_opt.unwrap()));
+            } else if a.eq("--dump-ui") {
+                config.dump_ui = true;
+            } else if a.eq("--dump-config") {
+                config.dump_config = true;
+            } else if a.eq("--diagnose") {
+                config.diagnose = true;
+            } else if a.eq("--polkit-agent") {
+                #[cfg(all(feature = "dbus", unix))]
+                {
+                    config.polkit_agent = true;
+                }
+                #[cfg(not(all(feature = "dbus", unix)))]
+                return Err(ParseError::feature_disabled(a, "dbus"));
+            } else if a.eq("--instance") {
+                #[cfg(all(feature = "dbus", unix))]
+                {
+                    pos += 1;
+                    let instance_opt = Configuration::get_argument(pos, args);
+                    if instance_opt.is_none() {
+                        return Err(ParseError::missing_argument(
+                            "Required argument for --instance is missing.",
+                        ));
+                    }
+                    config.instance = instance_opt.unwrap().to_string_lossy().to_string();
+                }
+                #[cfg(not(all(feature = "dbus", unix)))]
+                return Err(ParseError::feature_disabled(a, "dbus"));
+            } else if a.eq("--gtk-backend") {
+                pos += 1;
+                let backend_opt = Configuration::get_argument(pos, args);
+                if backend_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --gtk-backend is missing.",
+                    ));
+                }
+                let backend = backend_opt.unwrap().to_string_lossy();
+                if backend.eq_ignore_ascii_case("broadway") {
+                    config.gtk_backend = Some(GtkBackend::Broadway);
+                } else if backend.eq_ignore_ascii_case("offscreen") {
+                    config.gtk_backend = Some(GtkBackend::Offscreen);
+                } else {
+                    return Err(ParseError::wrong_argument(format!(
+                        "Parameter for --gtk-backend ({}) was neither broadway nor offscreen.",
+                        backend
+                    )));
+                }
+            } else if a.eq("--backend") {
+                pos += 1;
+                let backend_opt = Configuration::get_argument(pos, args);
+                if backend_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --backend is missing.",
+                    ));
+                }
+                let backend = backend_opt.unwrap().to_string_lossy();
+                if backend.eq_ignore_ascii_case("x11") {
+                    config.backend = Some(DisplayBackend::X11);
+                } else if backend.eq_ignore_ascii_case("wayland") {
+                    config.backend = Some(DisplayBackend::Wayland);
+                } else {
+                    return Err(ParseError::wrong_argument(format!(
+                        "Parameter for --backend ({}) was neither x11 nor wayland.",
+                        backend
+                    )));
+                }
+            } else if a.eq("--terminal") {
+                #[cfg(feature = "terminal-exec")]
+                {
+                    pos += 1;
+                    let terminal_opt = Configuration::get_argument(pos, args);
+                    if terminal_opt.is_none() {
+                        return Err(ParseError::missing_argument(
+                            "Required argument for --terminal is missing.",
+                        ));
+                    }
+                    config.terminal = Some(terminal_opt.unwrap().clone());
+                }
+                #[cfg(not(feature = "terminal-exec"))]
+                return Err(ParseError::feature_disabled(a, "terminal-exec"));
+            } else if a.eq("--terminal-cmd") {
+                #[cfg(feature = "terminal-exec")]
+                {
+                    pos += 1;
+                    let template_opt = Configuration::get_argument(pos, args);
+                    if template_opt.is_none() {
+                        return Err(ParseError::missing_argument(
+                            "Required argument for --terminal-cmd is missing.",
+                        ));
+                    }
+                    let template = template_opt.unwrap().to_string_lossy().to_string();
+                    if template.trim().is_empty() {
+                        return Err(ParseError::wrong_argument(
+                            "The template of --terminal-cmd is empty.",
+                        ));
+                    }
+                    config.terminal_template = Some(template);
+                }
+                #[cfg(not(feature = "terminal-exec"))]
+                return Err(ParseError::feature_disabled(a, "terminal-exec"));
+            } else if a.eq("--terminal-mode") {
+                #[cfg(feature = "terminal-exec")]
+                {
+                    pos += 1;
+                    let mode_opt = Configuration::get_argument(pos, args);
+                    if mode_opt.is_none() {
+                        return Err(ParseError::missing_argument(
+                            "Required argument for --terminal-mode is missing.",
+                        ));
+                    }
+                    let mode = mode_opt.unwrap().to_string_lossy();
+                    if mode.eq_ignore_ascii_case("auto") {
+                        config.terminal_mode = terminal::TerminalMode::Auto;
+                    } else if mode.eq_ignore_ascii_case("spawn") {
+                        config.terminal_mode = terminal::TerminalMode::Spawn;
+                    } else if mode.eq_ignore_ascii_case("remote") {
+                        config.terminal_mode = terminal::TerminalMode::Remote;
+                    } else {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --terminal-mode ({}) was neither auto, spawn nor remote.",
+                            mode
+                        )));
+                    }
+                }
+                #[cfg(not(feature = "terminal-exec"))]
+                return Err(ParseError::feature_disabled(a, "terminal-exec"));
+            } else if a.eq("--dark") {
+                config.prefer_dark = Some(true);
+            } else if a.eq("--light") {
+                config.prefer_dark = Some(false);
+            } else if a.eq("--gtk-theme") || a.eq("--icon-theme") {
+                pos += 1;
+                let theme_opt = Configuration::get_argument(pos, args);
+                if theme_opt.is_none() {
+                    return Err(ParseError::missing_argument(format!(
+                        "Required argument for {} is missing.",
+                        a.to_string_lossy()
+                    )));
+                }
+                let theme = Some(theme_opt.unwrap().to_string_lossy().to_string());
+                if a.eq("--gtk-theme") {
+                    config.gtk_theme = theme;
+                } else {
+                    config.icon_theme = theme;
+                }
+            } else if a.eq("--markup") {
+                config.markup = true;
+            } else if a.eq("--align") {
+                pos += 1;
+                let align_opt = Configuration::get_argument(pos, args);
+                if align_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --align is missing.",
+                    ));
+                }
+                let align = align_opt.unwrap().to_string_lossy();
+                if align.eq_ignore_ascii_case("left") {
+                    config.align = Some(gtk::Justification::Left);
+                } else if align.eq_ignore_ascii_case("center") {
+                    config.align = Some(gtk::Justification::Center);
+                } else if align.eq_ignore_ascii_case("right") {
+                    config.align = Some(gtk::Justification::Right);
+                } else {
+                    return Err(ParseError::wrong_argument(format!(
+                        "Parameter for --align ({}) was neither left, center nor right.",
+                        align
+                    )));
+                }
+            } else if a.eq("--text-scale") {
+                pos += 1;
+                let scale_opt = Configuration::get_argument(pos, args);
+                if scale_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --text-scale is missing.",
+                    ));
+                }
+                let scale = scale_opt.unwrap().to_string_lossy();
+                match scale.parse::<f64>() {
+                    Ok(factor) if factor > 0.0 && factor.is_finite() => {
+                        config.text_scale = Some(factor)
+                    }
+                    _ => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --text-scale ({}) is no positive number.",
+                            scale
+                        )))
+                    }
+                }
+            } else if a.eq("--replay") {
+                #[cfg(feature = "scripting")]
+                {
+                    pos += 1;
+                    let path_opt = Configuration::get_argument(pos, args);
+                    if path_opt.is_none() {
+                        return Err(ParseError::missing_argument(
+                            "Required argument for --replay is missing.",
+                        ));
+                    }
+                    replay_file = Some(std::path::PathBuf::from(path_opt.unwrap()));
+                }
+                #[cfg(not(feature = "scripting"))]
+                return Err(ParseError::feature_disabled(a, "scripting"));
+            } else if a.eq("-q") || a.eq("--quiet") {
+                set_quiet();
+            } else if a.eq("--serve") {
+                pos += 1;
+                let serve_opt = Configuration::get_argument(pos, args);
+                if serve_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --serve is missing.",
+                    ));
+                }
+                config.serve = Some(serve_opt.unwrap().to_string_lossy().to_string());
+            } else if a.eq("--wizard") {
+                pos += 1;
+                let path_opt = Configuration::get_argument(pos, args);
+                if path_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --wizard is missing.",
+                    ));
+                }
+                config.wizard = Some(std::path::PathBuf::from(path_opt.unwrap()));
+            } else if a.eq("--survey") {
+                pos += 1;
+                let path_opt = Configuration::get_argument(pos, args);
+                if path_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --survey is missing.",
+                    ));
+                }
+                if path_opt.unwrap() == "-" {
+                    stdin_users.push(String::from("--survey -"));
+                }
+                config.survey = Some(std::path::PathBuf::from(path_opt.unwrap()));
+            } else if a.eq("--css") {
+                pos += 1;
+                let path_opt = Configuration::get_argument(pos, args);
+                if path_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --css is missing.",
+                    ));
+                }
+                let path = std::path::PathBuf::from(path_opt.unwrap());
+                if !path.is_file() {
+                    return Err(ParseError::wrong_argument(format!(
+                        "The style sheet {} doesn't exist.",
+                        path.to_string_lossy()
+                    )));
+                }
+                config.css = Some(path);
+            } else if a.eq("--no-config") {
+                // Handled before parsing by spec::with_default_configs
+            } else if a.eq("--hot-reload") {
+                config.hot_reload = true;
+            } else if a.eq("--chain") {
+                pos += 1;
+                let path_opt = Configuration::get_argument(pos, args);
+                if path_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --chain is missing.",
+                    ));
+                }
+                config.chain = Some(std::path::PathBuf::from(path_opt.unwrap()));
+            } else if a.eq("--dedup") {
+                pos += 1;
+                let key_opt = Configuration::get_argument(pos, args);
+                if key_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --dedup is missing.",
+                    ));
+                }
+                #[cfg(unix)]
+                {
+                    config.dedup = Some(key_opt.unwrap().to_string_lossy().to_string());
+                }
+                #[cfg(not(unix))]
+                return Err(ParseError::wrong_argument(
+                    "--dedup is only supported on unix.",
+                ));
+            } else if a.eq("--delay") {
+                pos += 1;
+                let secs_opt = Configuration::get_argument(pos, args);
+                if secs_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --delay is missing.",
+                    ));
+                }
+                if delay_option.is_some_and(|o| o != "--delay") {
+                    return Err(ParseError::wrong_argument(
+                        "Only one of --delay, --in and --at can be given.",
+                    ));
+                }
+                delay_option = Some("--delay");
+                let secs_str = secs_opt.unwrap().to_string_lossy();
+                match secs_str.parse::<u32>() {
+                    Ok(v) => config.delay = Some(v),
+                    Err(_) => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --delay ({}) is not a number.",
+                            secs_str
+                        )))
+                    }
+                }
+            } else if a.eq("--in") {
+                pos += 1;
+                let duration_opt = Configuration::get_argument(pos, args);
+                if duration_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --in is missing.",
+                    ));
+                }
+                if delay_option.is_some_and(|o| o != "--in") {
+                    return Err(ParseError::wrong_argument(
+                        "Only one of --delay, --in and --at can be given.",
+                    ));
+                }
+                delay_option = Some("--in");
+                let duration_str = duration_opt.unwrap().to_string_lossy();
+                match parse_duration(&duration_str) {
+                    Some(v) => config.delay = Some(v),
+                    None => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --in ({}) is no duration like 90s, 15m or 1h30m.",
+                            duration_str
+                        )))
+                    }
+                }
+            } else if a.eq("--at") {
+                pos += 1;
+                let time_opt = Configuration::get_argument(pos, args);
+                if time_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --at is missing.",
+                    ));
+                }
+                let time_str = time_opt.unwrap().to_string_lossy();
+                #[cfg(unix)]
+                match parse_time_of_day(&time_str) {
+                    Some(v) => config.at = Some(v),
+                    None => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --at ({}) is no time like 17:30.",
+                            time_str
+                        )))
+                    }
+                }
+                #[cfg(not(unix))]
+                return Err(ParseError::wrong_argument(
+                    "--at is only supported on unix.",
+                ));
+            } else if a.eq("--cancel-if") {
+                pos += 1;
+                let cmd_opt = Configuration::get_argument(pos, args);
+                if cmd_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --cancel-if is missing.",
+                    ));
+                }
+                config.cancel_if = Some(Command::new(cmd_opt.unwrap().clone(), exec_in_shell));
+            } else if a.eq("--close-when") {
+                pos += 1;
+                let cmd_opt = Configuration::get_argument(pos, args);
+                if cmd_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --close-when is missing.",
+                    ));
+                }
+                config.close_when = Some(Command::new(cmd_opt.unwrap().clone(), exec_in_shell));
+            } else if a.eq("--poll-interval") {
+                pos += 1;
+                let secs = Configuration::get_number_argument(pos, args, "--poll-interval")?;
+                if secs == 0 {
+                    return Err(ParseError::wrong_argument(
+                        "Parameter for --poll-interval has to be at least 1.",
+                    ));
+                }
+                config.poll_interval = Some(secs);
+            } else if a.eq("--if-failed") || a.eq("--if-succeeds") {
+                pos += 1;
+                let cmd_opt = Configuration::get_argument(pos, args);
+                if cmd_opt.is_none() {
+                    return Err(ParseError::missing_argument(format!(
+                        "Required argument for {} is missing.",
+                        a.to_string_lossy()
+                    )));
+                }
+                if config.show_if.is_some() {
+                    return Err(ParseError::wrong_argument(
+                        "Only one of --if-failed and --if-succeeds can be given.",
+                    ));
+                }
+                let command = Command::new(cmd_opt.unwrap().clone(), exec_in_shell);
+                config.show_if = Some((command, a.eq("--if-succeeds")));
+            } else if a.eq("--pipe-to") {
+                pos += 1;
+                let pipe_opt = Configuration::get_argument(pos, args);
+                if pipe_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --pipe-to is missing.",
+                    ));
+                }
+                config.pipe_to = Some(Command::new(pipe_opt.unwrap().clone(), exec_in_shell));
+            } else if a.eq("--print") {
+                pos += 1;
+                let mode_opt = Configuration::get_argument(pos, args);
+                if mode_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --print is missing.",
+                    ));
+                }
+                let mode = mode_opt.unwrap().to_string_lossy();
+                config.print = if mode.eq_ignore_ascii_case("label") {
+                    Some(PrintMode::Label)
+                } else if mode.eq_ignore_ascii_case("index") {
+                    Some(PrintMode::Index)
+                } else if mode.eq_ignore_ascii_case("value") {
+                    Some(PrintMode::Value)
+                } else {
+                    return Err(ParseError::wrong_argument(format!(
+                        "Parameter for --print ({}) was neither label, index nor value.",
+                        mode
+                    )));
+                };
+            } else if a.eq("--bar") {
+                config.bar = true;
+            } else if a.eq("--preview-types") {
+                config.preview_types = true;
+            } else if a.eq("--strict") {
+                config.strict = true;
+            } else if a.eq("--notify-result") {
+                #[cfg(feature = "notifications")]
+                {
+                    config.notify_result = true;
+                }
+                #[cfg(not(feature = "notifications"))]
+                return Err(ParseError::feature_disabled(a, "notifications"));
+            } else if a.eq("--exit-after-action") {
+                config.exit_after_action = true;
+            } else if a.eq("--cancel-label") {
+                pos += 1;
+                let label_opt = Configuration::get_argument(pos, args);
+                if label_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --cancel-label is missing.",
+                    ));
+                }
+                config.cancel_label = label_opt.unwrap().to_string_lossy().to_string();
+            } else if a.eq("--cancel-action") {
+                pos += 1;
+                let action_opt = Configuration::get_argument(pos, args);
+                if action_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --cancel-action is missing.",
+                    ));
+                }
+                config.cancel_action =
+                    Some(Command::new(action_opt.unwrap().clone(), exec_in_shell));
+            } else if a.eq("--layout") {
+                pos += 1;
+                let layout_opt = Configuration::get_argument(pos, args);
+                if layout_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --layout is missing.",
+                    ));
+                }
+                let layout = layout_opt.unwrap().to_string_lossy();
+                config.layout = if layout.eq_ignore_ascii_case("vertical") {
+                    ButtonLayout::Vertical
+                } else if layout.eq_ignore_ascii_case("horizontal") {
+                    ButtonLayout::Horizontal
+                } else {
+                    return Err(ParseError::wrong_argument(format!(
+                        "Parameter for --layout ({}) was neither horizontal nor vertical.",
+                        layout
+                    )));
+                };
+            } else if a.eq("--columns") {
+                pos += 1;
+                let columns = Configuration::get_number_argument(pos, args, "--columns")?;
+                if columns == 0 {
+                    return Err(ParseError::wrong_argument(
+                        "Parameter for --columns has to be at least 1.",
+                    ));
+                }
+                config.columns = Some(columns);
+            } else if a.eq("--no-cancel") {
+                config.no_cancel = true;
+            } else if a.eq("--no-keep-above") {
+                config.no_keep_above = true;
+            } else if a.eq("--no-sticky") {
+                config.no_sticky = true;
+            } else if a.eq("--no-urgent") {
+                config.no_urgent = true;
+            } else if a.eq("--headerbar") {
+                config.headerbar = true;
+            } else if a.eq("--attach") {
+                pos += 1;
+                let xid_opt = Configuration::get_argument(pos, args);
+                if xid_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --attach is missing.",
+                    ));
+                }
+                let value = xid_opt.unwrap().to_string_lossy();
+                config.attach = match attach::parse_xid(&value) {
+                    Some(xid) => Some(xid),
+                    None => {
+                        return Err(ParseError::wrong_argument(format!(
+                            "Parameter for --attach ({}) is not an X11 window id.",
+                            value
+                        )))
+                    }
+                };
+            } else if a.eq("--show-status") {
+                config.show_status = true;
+            } else if a.eq("--queue") {
+                config.queue = true;
+            } else if a.eq("--audit-log") {
+                pos += 1;
+                let path_opt = Configuration::get_argument(pos, args);
+                if path_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --audit-log is missing.",
+                    ));
+                }
+                let path = std::path::PathBuf::from(path_opt.unwrap());
+                config.audit_log = Some(AuditLog::new(path));
+            } else if a.eq("--print-pid") {
+                pos += 1;
+                let path_opt = Configuration::get_argument(pos, args);
+                if path_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --print-pid is missing.",
+                    ));
+                }
+                config.print_pid = Some(std::path::PathBuf::from(path_opt.unwrap()));
+            } else if a.eq("-f") || a.eq("--font") {
+                pos += 1;
+                let font_opt = Configuration::get_argument(pos, args);
+                if font_opt.is_none() {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for -f is missing.",
+                    ));
+                }
+                let font = font_opt.unwrap().to_string_lossy();
+                // i3 configs prefix Pango fonts, X core font names have no equivalent.
+                let font = font.trim_start_matches("pango:").trim();
+                if font.starts_with('-') {
+                    warning!("X core fonts aren't supported, {} is ignored.", font);
+                } else {
+                    config.font = Some(String::from(font));
+                }
+            } else if a.eq("-h") || a.eq("--help") {
+                return Err(ParseError::help_requested());
+            } else if a.eq("-v") || a.eq("--version") {
+                return Err(ParseError::version_requested());
+            } else {
+                return Err(ParseError::wrong_argument(format!(
+                    "Unhandled option: {}",
+                    a.to_string_lossy()
+                )));
+            }
+            pos += 1;
+        }
+        if stdin_users.len() > 1 {
+            return Err(ParseError::wrong_argument(format!(
+                "Only one of {} can read stdin.",
+                stdin_users.join(" and ")
+            )));
+        }
+        if terminal_buttons && (config.pin || config.prompt.is_some()) {
+            return Err(ParseError::wrong_argument(
+                "--pin and prompts can't be combined with -b, the terminal would get the value instead of the action.",
+            ));
+        }
+        if config.pin && config.prompt.is_some() {
+            return Err(ParseError::wrong_argument(
+                "--pin can't be combined with a prompt like --password.",
+            ));
+        }
+        if config.print == Some(PrintMode::Value) && !config.pin && config.prompt.is_none() {
+            return Err(ParseError::wrong_argument(
+                "--print value needs --pin or a prompt like --password.",
+            ));
+        }
+        if config.prompt.is_some() && config.buttons.is_empty() {
+            config.buttons.push(Button {
+                label: String::from("_OK"),
+                icon: None,
+                action: Action::Command(Command::new(OsString::from("true"), exec_in_shell)),
+                require_typing: None,
+                hold_to_confirm: None,
+                icon_layout: IconLayout::Right,
+                exit_code: None,
+                exit_after: None,
+                tooltip: None,
+                style: ButtonStyle::Normal,
+                confirm: None,
+                secondary: None,
+            });
+            // The next step of a chain gets the value instead.
+            if config.chain.is_none() {
+                config.print.get_or_insert(PrintMode::Value);
+            }
+            // Enter in the prompt presses OK.
+            config.default_button.get_or_insert(FocusTarget::Button(0));
+        }
+        // Buttons may be given after the focus options, so these are resolved last.
+        if let Some(label) = focus_label {
+            config.focus = Some(config.find_focus_target(&label)?);
+        }
+        if let Some(item) = default_button {
+            config.default_button = Some(config.find_button_target(&item)?);
+        }
+        for (label, secs) in timeout_labels {
+            let target = config.find_focus_target(&label)?;
+            config.button_timeouts.push((target, secs));
+        }
+        if let Some((secs, button)) = idle_timeout {
+            let target = match button {
+                Some(b) => config.find_button_target(&b)?,
+                None => FocusTarget::Cancel,
+            };
+            config.idle_timeout = Some((secs, target));
+        }
+        if let Some(order) = focus_order {
+            for item in order.split(',') {
+                let target = config.find_button_target(item.trim())?;
+                config.focus_order.push(target);
+            }
+        }
+        for (i, (name, first)) in config.tabs.iter().enumerate() {
+            let end = config.tabs.get(i + 1).map_or(config.buttons.len(), |t| t.1);
+            if *first == end {
+                return Err(ParseError::wrong_argument(format!(
+                    "--tab {} has no buttons.",
+                    name
+                )));
+            }
+        }
+        if config.at.is_some() && config.delay.is_some() {
+            return Err(ParseError::wrong_argument(
+                "Only one of --delay, --in and --at can be given.",
+            ));
+        }
+        if config.cancel_if.is_some() && config.delay.is_none() && config.at.is_none() {
+            return Err(ParseError::wrong_argument(
+                "--cancel-if is only checked during --delay, --in or --at.",
+            ));
+        }
+        if config.poll_interval.is_some() && config.close_when.is_none() {
+            return Err(ParseError::wrong_argument(
+                "--poll-interval is only used with --close-when.",
+            ));
+        }
+        if config.print.is_some() && config.chain.is_some() {
+            return Err(ParseError::wrong_argument(
+                "--print can't be combined with --chain.",
+            ));
+        }
+        if config
+            .buttons
+            .iter()
+            .any(|b| b.exit_code.is_some() && !config.exits_after(b))
+        {
+            return Err(ParseError::wrong_argument(
+                "--code is only used with --exit-after-action or --close.",
+            ));
+        }
+        // Holding can't be asked for in the menu or on middle click.
+        if config
+            .buttons
+            .iter()
+            .any(|b| b.secondary.is_some() && b.hold_to_confirm.is_some())
+        {
+            return Err(ParseError::wrong_argument(
+                "--secondary can't be combined with --hold-to-confirm.",
+            ));
+        }
+        if config.terminal.is_some() && config.terminal_template.is_some() {
+            return Err(ParseError::wrong_argument(
+                "--terminal can't be combined with --terminal-cmd.",
+            ));
+        }
+        // gtk would show invalid markup as an empty label.
+        if config.markup {
+            if let Err(e) = pango::parse_markup(&config.message, '\0') {
+                return Err(ParseError::wrong_argument(format!(
+                    "The message isn't valid markup: {}",
+                    e
+                )));
+            }
+        }
+        if config.no_cancel {
+            if config.buttons.is_empty() {
+                return Err(ParseError::wrong_argument(
+                    "--no-cancel needs at least one button.",
+                ));
+            }
+            if !config.buttons.iter().any(|b| config.exits_after(b)) {
+                return Err(ParseError::wrong_argument(
+                    "--no-cancel needs a button which closes the dialog, not all can --keep-open.",
+                ));
+            }
+            if config.focus == Some(FocusTarget::Cancel)
+                || config.default_button == Some(FocusTarget::Cancel)
+                || config.focus_order.contains(&FocusTarget::Cancel)
+            {
+                return Err(ParseError::wrong_argument(
+                    "There is no cancel button to focus with --no-cancel.",
+                ));
+            }
+        }
+        if config.columns.is_some() && (config.bar || config.layout == ButtonLayout::Horizontal) {
+            return Err(ParseError::wrong_argument(
+                "--columns can't be combined with --bar or --layout horizontal.",
+            ));
+        }
+        if config.bar && (config.width.is_some() || config.height.is_some()) {
+            return Err(ParseError::wrong_argument(
+                "--width and --height can't be combined with --bar.",
+            ));
+        }
+        if config.bar && (config.position != position::Position::Center || config.offset != (0, 0))
+        {
+            return Err(ParseError::wrong_argument(
+                "--position and --offset can't be combined with --bar.",
+            ));
+        }
+        if config.attach.is_some()
+            && (config.bar
+                || config.position != position::Position::Center
+                || config.offset != (0, 0))
+        {
+            return Err(ParseError::wrong_argument(
+                "--attach can't be combined with --bar, --position or --offset.",
+            ));
+        }
+        if config.bar && config.headerbar {
+            return Err(ParseError::wrong_argument(
+                "--headerbar can't be combined with --bar.",
+            ));
+        }
+        if config.bar && config.footer.is_some() {
+            return Err(ParseError::wrong_argument(
+                "--footer can't be combined with --bar.",
+            ));
+        }
+        if config.show_status && config.exit_after_action {
+            return Err(ParseError::wrong_argument(
+                "--show-status can't be combined with --exit-after-action.",
+            ));
+        }
+        if config.queue && config.exit_after_action {
+            return Err(ParseError::wrong_argument(
+                "--queue can't be combined with --exit-after-action.",
+            ));
+        }
+        // Nobody could see or answer an offscreen dialog, it would never exit.
+        if config.gtk_backend == Some(GtkBackend::Offscreen)
+            && config.screenshot.is_none()
+            && !config.dump_ui
+        {
+            return Err(ParseError::wrong_argument(
+                "--gtk-backend offscreen needs --screenshot or --dump-ui.",
+            ));
+        }
+        if config.backend.is_some() && config.gtk_backend == Some(GtkBackend::Broadway) {
+            return Err(ParseError::wrong_argument(
+                "--backend can't be combined with --gtk-backend broadway.",
+            ));
+        }
+        #[cfg(feature = "dbus")]
+        if config.secret_store.is_some() && !config.pin {
+            return Err(ParseError::wrong_argument(
+                "--store-secret needs the PIN keypad (--pin).",
+            ));
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(path) = replay_file {
+            let content = std::fs::read_to_string(&path).map_err(|e| {
+                ParseError::wrong_argument(format!(
+                    "Couldn't read replay file {}: {}",
+                    path.to_string_lossy(),
+                    e
+                ))
+            })?;
+            config.replay = replay::parse(&content, &config)?;
+        }
+        Ok(config)
+    }
+
+    // Accepts a 1-based button number as well as everything find_focus_target does.
+    fn find_button_target(&self, item: &str) -> Result<FocusTarget, ParseError> {
+        match item.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= self.buttons.len() => Ok(FocusTarget::Button(n - 1)),
+            Ok(n) => Err(ParseError::wrong_argument(format!(
+                "There is no button number {}.",
+                n
+            ))),
+            Err(_) => self.find_focus_target(item),
+        }
+    }
+
+    // Matches a button label (with or without mnemonic underscores) or "cancel".
+    fn find_focus_target(&self, label: &str) -> Result<FocusTarget, ParseError> {
+        let position = self
+            .buttons
+            .iter()
+            .position(|b| b.label == label || b.label.replace('_', "") == label);
+        match position {
+            Some(i) => Ok(FocusTarget::Button(i)),
+            None if label.eq_ignore_ascii_case("cancel")
+                || label == self.cancel_label
+                || label == self.cancel_label.replace('_', "") =>
+            {
+                Ok(FocusTarget::Cancel)
+            }
+            None => Err(ParseError::wrong_argument(format!(
+                "There is no button labeled {}.",
+                label
+            ))),
+        }
+    }
+
+    fn create_button(
+        pos: &mut usize,
+        args: &[OsString],
+        make_action: fn(OsString) -> Action,
+    ) -> Result<Button, ParseError> {
+        *pos += 1;
+        let label_opt = Configuration::get_argument(*pos, &args);
+        if label_opt.is_none() {
+            return Err(ParseError::missing_argument("Missing label for Button."));
+        }
+        let label = label_opt.unwrap().to_string_lossy().to_string();
+        *pos += 1;
+
+        let action_opt = Configuration::get_argument(*pos, &args);
+        if action_opt.is_none() {
+            return Err(ParseError::missing_argument("Missing action for Button."));
+        }
+        let action = action_opt.unwrap().clone();
+        let icon = match Configuration::get_argument(*pos + 1, &args) {
+            Some(v) => {
+                if v.to_string_lossy().starts_with('-') {
+                    None
+                } else {
+                    *pos += 1;
+                    Some(v.clone())
+                }
+            }
+            None => None,
+        };
+        let button = Button {
+            label,
+            icon,
+            action: make_action(action),
+            require_typing: None,
+            hold_to_confirm: None,
+            icon_layout: IconLayout::Right,
+            exit_code: None,
+            exit_after: None,
+            tooltip: None,
+            style: ButtonStyle::Normal,
+            confirm: None,
+            secondary: None,
+        };
+        Ok(button)
+    }
+
+    #[cfg(feature = "http")]
+    fn create_http_button(pos: &mut usize, args: &[OsString]) -> Result<Button, ParseError> {
+        let mut values = Vec::new();
+        for name in &["label", "method", "URL"] {
+            *pos += 1;
+            match Configuration::get_argument(*pos, args) {
+                Some(v) => values.push(v.to_string_lossy().to_string()),
+                None => {
+                    return Err(ParseError::missing_argument(format!(
+                        "Missing {} for --button-http.",
+                        name
+                    )))
+                }
+            }
+        }
+        let body = match Configuration::get_argument(*pos + 1, args) {
+            Some(v) if !is_option(&v.to_string_lossy()) => {
+                *pos += 1;
+                Some(v.to_string_lossy().to_string())
+            }
+            _ => None,
+        };
+        let request =
+            http::Request::new(&values[1], &values[2], body).map_err(ParseError::wrong_argument)?;
+        Ok(Button {
+            label: values.remove(0),
+            icon: None,
+            action: Action::Http(request),
+            require_typing: None,
+            hold_to_confirm: None,
+            icon_layout: IconLayout::Right,
+            exit_code: None,
+            exit_after: None,
+            tooltip: None,
+            style: ButtonStyle::Normal,
+            confirm: None,
+            secondary: None,
+        })
+    }
+
+    // Whether pressing the button closes the dialog. Without Cancel it is the only way out.
+    fn exits_after(&self, button: &Button) -> bool {
+        button
+            .exit_after
+            .unwrap_or(self.exit_after_action || self.no_cancel)
+    }
+
+    /* The arguments up to the next option, or the lines of stdin for a
+     * single -, which is noted in stdin_users.
+     */
+    fn get_items(
+        pos: &mut usize,
+        args: &[OsString],
+        option: &str,
+        stdin_users: &mut Vec<String>,
+    ) -> Result<Vec<String>, ParseError> {
+        let mut items = Vec::new();
+        if Configuration::get_argument(*pos + 1, args).is_some_and(|a| a == "-") {
+            *pos += 1;
+            stdin_users.push(format!("{} -", option));
+            items.extend(stdin_text("items")?.lines().map(String::from));
+            if items.is_empty() {
+                return Err(ParseError::wrong_argument(format!(
+                    "{} got no items from stdin.",
+                    option
+                )));
+            }
+            return Ok(items);
+        }
+        while let Some(item) = Configuration::get_argument(*pos + 1, args) {
+            let item = item.to_string_lossy();
+            if item.starts_with('-') {
+                break;
+            }
+            items.push(item.to_string());
+            *pos += 1;
+        }
+        if items.is_empty() {
+            return Err(ParseError::missing_argument(format!(
+                "{} needs items, or - to read them from stdin.",
+                option
+            )));
+        }
+        Ok(items)
+    }
+
+    fn set_prompt(config: &mut Configuration, prompt: prompt::Prompt) -> Result<(), ParseError> {
+        if config.prompt.is_some() {
+            return Err(ParseError::wrong_argument(
+                "Only one prompt like --password or --scale can be given.",
+            ));
+        }
+        config.prompt = Some(prompt);
+        Ok(())
+    }
+
+    // Button modifiers like --require-typing always apply to the preceding button.
+    fn last_button<'a>(
+        config: &'a mut Configuration,
+        option: &str,
+    ) -> Result<&'a mut Button, ParseError> {
+        match config.buttons.last_mut() {
+            Some(b) => Ok(b),
+            None => Err(ParseError::wrong_argument(format!(
+                "{} has to follow a button.",
+                option
+            ))),
+        }
+    }
+
+    /* MIN MAX [STEP] [INITIAL] of --scale. The value is printed with as many
+     * decimals as the most precise of them has, 0.5 gives one.
+     */
+    fn get_scale_arguments(
+        pos: &mut usize,
+        args: &[OsString],
+    ) -> Result<prompt::Prompt, ParseError> {
+        let mut numbers = Vec::new();
+        let mut digits = 0;
+        while numbers.len() < 4 {
+            let arg = match Configuration::get_argument(*pos + 1, args) {
+                Some(v) => v.to_string_lossy(),
+                None => break,
+            };
+            let number = match arg.parse::<f64>() {
+                Ok(number) if number.is_finite() => number,
+                // A negative number looks like an option, anything else ends the list.
+                _ if numbers.len() >= 2 => break,
+                _ => {
+                    return Err(ParseError::wrong_argument(format!(
+                        "Parameter for --scale ({}) is not a number.",
+                        arg
+                    )))
+                }
+            };
+            digits = digits.max(arg.split('.').nth(1).map_or(0, |d| d.len() as u32));
+            numbers.push(number);
+            *pos += 1;
+        }
+        if numbers.len() < 2 {
+            return Err(ParseError::missing_argument(
+                "--scale needs a minimum and a maximum.",
+            ));
+        }
+        let (min, max) = (numbers[0], numbers[1]);
+        let step = numbers.get(2).copied().unwrap_or(1.0);
+        let value = numbers.get(3).copied().unwrap_or(min);
+        if min >= max {
+            return Err(ParseError::wrong_argument(
+                "The minimum of --scale has to be below the maximum.",
+            ));
+        }
+        if step <= 0.0 {
+            return Err(ParseError::wrong_argument(
+                "The step of --scale has to be positive.",
+            ));
+        }
+        if value < min || value > max {
+            return Err(ParseError::wrong_argument(
+                "The initial value of --scale has to be between the minimum and the maximum.",
+            ));
+        }
+        Ok(prompt::Prompt::Scale {
+            min,
+            max,
+            step,
+            value,
+            digits,
+        })
+    }
+
+    fn get_number_argument(pos: usize, args: &[OsString], option: &str) -> Result<u32, ParseError> {
+        let arg = match Configuration::get_argument(pos, args) {
+            Some(v) => v.to_string_lossy(),
+            None => {
+                return Err(ParseError::missing_argument(format!(
+                    "Required argument for {} is missing.",
+                    option
+                )))
+            }
+        };
+        arg.parse::<u32>().map_err(|_| {
+            ParseError::wrong_argument(format!(
+                "Parameter for {} ({}) is not a number.",
+                option, arg
+            ))
+        })
+    }
+
+    fn get_icon_size_argument(
+        pos: usize,
+        args: &[OsString],
+        option: &str,
+    ) -> Result<IconSize, ParseError> {
+        let arg = match Configuration::get_argument(pos, args) {
+            Some(v) => v.to_string_lossy(),
+            None => {
+                return Err(ParseError::missing_argument(format!(
+                    "Required argument for {} is missing.",
+                    option
+                )))
+            }
+        };
+        IconSize::parse(&arg).ok_or_else(|| {
+            ParseError::wrong_argument(format!(
+                "Parameter for {} ({}) is neither a GTK icon size nor a number of pixels.",
+                option, arg
+            ))
+        })
+    }
+
+    fn get_argument<P>(pos: usize, args: &[P]) -> Option<&P> {
+        if pos < args.len() {
+            return Some(&args[pos]);
+        }
+        None
+    }
+}
+
+fn create_gtk_window(
+    config: &Configuration,
+    buttons: &GtkButtons,
+    message: &gtk::Box,
+) -> gtk::Window {
+    let offscreen = config.gtk_backend == Some(GtkBackend::Offscreen);
+    let window: gtk::Window = if offscreen || config.screenshot.is_some() {
+        gtk::OffscreenWindow::new().upcast()
+    } else {
+        gtk::Window::new(gtk::WindowType::Toplevel)
+    };
+    window.set_keep_above(!config.no_keep_above);
+    if !config.no_sticky {
+        window.stick();
+    }
+    window.set_urgency_hint(!config.no_urgent);
+    let title = config.title.as_deref().unwrap_or(PROGRAM_NAME);
+    window.set_title(title);
+    if config.headerbar {
+        let header = gtk::HeaderBar::new();
+        header.set_title(title);
+        header.set_show_close_button(!config.no_cancel);
+        window.set_titlebar(&header);
+    }
+    // Both parts of WM_CLASS on X11, Wayland uses the program name instead.
+    if let Some(class) = &config.class {
+        window.set_wmclass(class, class);
+    }
+    // Lets style sheets tell the types apart, e.g. `window.warning #message`.
+    add_style_class(&window, config.message_type.name());
+    let content = gtk::Box::new(gtk::Orientation::Vertical, config.spacing);
+    if config.bar {
+        // One row with the buttons on the right, the accent below it.
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, config.spacing);
+        buttons
+            .container
+            .set_orientation(gtk::Orientation::Horizontal);
+        row.pack_start(message, true, true, 0);
+        row.pack_end(&buttons.container, false, false, 0);
+        content.add(&row);
+        content.add(&create_gtk_accent(&config.message_type));
+    } else {
+        content.add(&create_gtk_accent(&config.message_type));
+        content.add(message);
+        content.add(&buttons.container);
+        if let Some(text) = &config.footer {
+            content.add(&create_gtk_footer(text));
+        }
+    }
+    window.set_border_width(config.border_width);
+    window.add(&content);
+    if config.bar && !offscreen && config.screenshot.is_none() {
+        bar::dock(&window);
+    } else {
+        match config.attach {
+            Some(xid) => attach::attach(&window, xid),
+            None => position::place(&window, config.position, config.offset),
+        }
+        window.set_resizable(false);
+        // Minimums, wider buttons or more lines still grow the window.
+        window.set_size_request(
+            config.width.map_or(-1, |w| w as i32),
+            config.height.map_or(-1, |h| h as i32),
+        );
+    }
+    load_default_css(&window);
+    if let Some(font) = &config.font {
+        load_css(
+            &window,
+            &font_css(font),
+            "font",
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+    }
+    if let Some(path) = &config.css {
+        match std::fs::read_to_string(path) {
+            // Above the theme and the defaults, like the user's gtk.css.
+            Ok(css) => load_css(
+                &window,
+                &css,
+                &path.to_string_lossy(),
+                gtk::STYLE_PROVIDER_PRIORITY_USER,
+            ),
+            Err(e) => warning!("Couldn't read {}: {}", path.to_string_lossy(), e),
+        }
+    }
+    let default = match &config.default_button {
+        Some(FocusTarget::Button(i)) => Some(&buttons.actions[*i]),
+        _ if config.no_cancel => None,
+        _ => Some(&buttons.cancel),
+    };
+    if let Some(default) = default {
+        default.set_can_default(true);
+        window.set_default(default);
+    }
+    if config.no_cancel {
+        // Closing the window would be a way around the buttons.
+        window.connect_delete_event(|_, _| Inhibit(true));
+    } else if config.headerbar {
+        // Its close button is the Cancel of GNOME dialogs.
+        let cancel = buttons.cancel.clone();
+        window.connect_delete_event(move |_, _| {
+            cancel.clicked();
+            Inhibit(true)
+        });
+    }
+    window.activate_focus();
+    buttons.focus.grab_focus();
+    window
+}
+
+/* Root-owned scripts don't see the theme of the user session, so it can be
+ * given per invocation. Has to be called after gtk::init.
+ */
+fn apply_themes(config: &Configuration) {
+    if config.gtk_theme.is_none() && config.icon_theme.is_none() && config.prefer_dark.is_none() {
+        return;
+    }
+    match gtk::Settings::get_default() {
+        Some(settings) => {
+            if let Some(theme) = &config.gtk_theme {
+                settings.set_property_gtk_theme_name(Some(theme));
+            }
+            if let Some(theme) = &config.icon_theme {
+                settings.set_property_gtk_icon_theme_name(Some(theme));
+            }
+            if let Some(dark) = config.prefer_dark {
+                settings.set_property_gtk_application_prefer_dark_theme(dark);
+            }
+        }
+        None => warning!("Couldn't apply themes, there are no gtk settings."),
+    }
+}
+
+/* The window of --wizard and --survey around their pages: centered, not
+ * resizable, closing it ends the main loop.
+ */
+fn create_gtk_step_window(config: &Configuration, content: &gtk::Box) -> gtk::Window {
+    let window = gtk::Window::new(gtk::WindowType::Toplevel);
+    window.set_title(PROGRAM_NAME);
+    window.set_border_width(config.border_width);
+    window.set_position(gtk::WindowPosition::Center);
+    window.add(content);
+    window.set_resizable(false);
+    load_default_css(&window);
+    window.connect_delete_event(|_, _| {
+        gtk::main_quit();
+        Inhibit(false)
+    });
+    window
+}
+
+fn load_default_css(window: &gtk::Window) {
+    load_css(
+        window,
+        DEFAULT_CSS,
+        "default style",
+        gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+}
+
+fn load_css(window: &gtk::Window, css: &str, what: &str, priority: u32) {
+    let provider = gtk::CssProvider::new();
+    if let Err(e) = provider.load_from_data(css.as_bytes()) {
+        warning!("Couldn't load {}: {}", what, e);
+        return;
+    }
+    if let Some(screen) = window.get_screen() {
+        gtk::StyleContext::add_provider_for_screen(&screen, &provider, priority);
+    }
+}
+
+/* -f takes a Pango font description like "DejaVu Sans Bold 10", which is
+ * turned into CSS for the whole dialog.
+ */
+fn font_css(font: &str) -> String {
+    let description = pango::FontDescription::from_string(font);
+    let mut css = String::from("* {");
+    if let Some(family) = description.get_family().filter(|f| !f.is_empty()) {
+        css.push_str(&format!(" font-family: \"{}\";", family.replace('"', "")));
+    }
+    if description.get_size() > 0 {
+        let unit = if description.get_size_is_absolute() {
+            "px"
+        } else {
+            "pt"
+        };
+        let size = f64::from(description.get_size()) / f64::from(pango::SCALE);
+        css.push_str(&format!(" font-size: {}{};", size, unit));
+    }
+    let weight = match description.get_weight() {
+        pango::Weight::Thin => 100,
+        pango::Weight::Ultralight => 200,
+        pango::Weight::Light | pango::Weight::Semilight => 300,
+        pango::Weight::Medium => 500,
+        pango::Weight::Semibold => 600,
+        pango::Weight::Bold => 700,
+        pango::Weight::Ultrabold => 800,
+        pango::Weight::Heavy | pango::Weight::Ultraheavy => 900,
+        _ => 400,
+    };
+    if weight != 400 {
+        css.push_str(&format!(" font-weight: {};", weight));
+    }
+    match description.get_style() {
+        pango::Style::Italic => css.push_str(" font-style: italic;"),
+        pango::Style::Oblique => css.push_str(" font-style: oblique;"),
+        _ => {}
+    }
+    css.push_str(" }");
+    css
+}
+
+// A colored strip at the top of the window, styled by the message type class.
+fn create_gtk_accent(message_type: &MessageType) -> gtk::Separator {
+    let accent = gtk::Separator::new(gtk::Orientation::Horizontal);
+    add_style_class(&accent, "accent");
+    add_style_class(&accent, message_type.name());
+    accent
+}
+
+fn add_style_class<W: WidgetExt>(widget: &W, class: &str) {
+    if let Some(context) = widget.get_style_context() {
+        context.add_class(class);
+    }
+}
+
+fn create_gtk_footer(text: &str) -> gtk::Label {
+    let label = gtk::Label::new(None);
+    label.set_markup(&format!(
+        "<small>{}</small>",
+        glib::markup_escape_text(text)
+    ));
+    WidgetExt::set_name(&label, "footer");
+    add_style_class(&label, "dim-label");
+    label.set_halign(gtk::Align::Start);
+    label
+}
+
+fn create_gtk_message(config: &Configuration) -> gtk::Box {
+    let hbox = gtk::Box::new(gtk::Orientation::Horizontal, config.spacing);
+    let icon_name = match config.message_type {
+        MessageType::ERROR => "dialog-error",
+        MessageType::WARNING => "dialog-warning",
+        MessageType::INFO => "dialog-information",
+    };
+    let icon = create_gtk_icon(OsStr::new(icon_name), config.icon_size);
+    WidgetExt::set_name(&icon, "message-icon");
+    let label = gtk::Label::new(None);
+    WidgetExt::set_name(&label, "message");
+    if config.markup {
+        label.set_markup(&config.message);
+    } else {
+        label.set_text(&config.message);
+    }
+    if let Some(justification) = config.align {
+        // The label has to fill the row, otherwise there is nothing to align in.
+        label.set_hexpand(true);
+        label.set_justify(justification);
+        label.set_halign(match justification {
+            gtk::Justification::Right => gtk::Align::End,
+            gtk::Justification::Center => gtk::Align::Center,
+            _ => gtk::Align::Start,
+        });
+    }
+    if let Some(factor) = config.text_scale {
+        let attributes = pango::AttrList::new();
+        if let Some(scale) = pango::Attribute::new_scale(factor) {
+            attributes.insert(scale);
+        }
+        label.set_attributes(&attributes);
+    }
+    if config.width.is_some() {
+        // Asks for as little width as possible, then wraps in what --width gives it.
+        label.set_line_wrap(true);
+        label.set_line_wrap_mode(pango::WrapMode::WordChar);
+        label.set_max_width_chars(1);
+    }
+    hbox.add(&icon);
+    if is_long_message(&config.message) {
+        // Logs and the like scroll instead of growing the window off screen.
+        label.set_selectable(true);
+        label.set_line_wrap(true);
+        label.set_valign(gtk::Align::Start);
+        let scrolled = gtk::ScrolledWindow::new(None, None);
+        scrolled.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+        scrolled.set_min_content_width(LONG_MESSAGE_WIDTH);
+        scrolled.set_min_content_height(LONG_MESSAGE_HEIGHT);
+        scrolled.add(&label);
+        hbox.pack_start(&scrolled, true, true, 0);
+    } else {
+        hbox.add(&label);
+    }
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, config.spacing);
+    vbox.add(&hbox);
+    if let Some(path) = &config.image {
+        // PixbufAnimation also loads still images, as single frame animations.
+        match gdk_pixbuf::PixbufAnimation::new_from_file(path) {
+            Ok(animation) => vbox.add(&gtk::Image::new_from_animation(&animation)),
+            Err(e) => warning!("Couldn't load image {}: {}", path.to_string_lossy(), e),
+        }
+    }
+    if let Some(code) = &config.qr_code {
+        vbox.add(&gtk::Image::new_from_pixbuf(&qr::to_pixbuf(code)));
+    }
+    if config.show_elapsed {
+        vbox.add(&create_gtk_elapsed_label());
+    }
+    vbox
+}
+
+const LONG_MESSAGE_LINES: usize = 20;
+const LONG_MESSAGE_WIDTH: i32 = 600;
+const LONG_MESSAGE_HEIGHT: i32 = 300;
+
+fn is_long_message(message: &str) -> bool {
+    message.lines().count() > LONG_MESSAGE_LINES || message.len() > 80 * LONG_MESSAGE_LINES
+}
+
+fn create_gtk_elapsed_label() -> gtk::Label {
+    let shown_at = std::time::Instant::now();
+    let label = gtk::Label::new(format_elapsed(0).as_str());
+    label.set_halign(gtk::Align::End);
+    let label_clone = label.clone();
+    gtk::timeout_add_seconds(1, move || {
+        label_clone.set_text(&format_elapsed(shown_at.elapsed().as_secs()));
+        gtk::Continue(true)
+    });
+    label
+}
+
+/* Signs that someone is reading the dialog: the pointer is over the window,
+ * or it has the keyboard focus and keys were pressed since it got it.
+ */
+#[derive(Default)]
+struct Activity {
+    hovered: std::cell::Cell<bool>,
+    typed: std::cell::Cell<bool>,
+}
+
+impl Activity {
+    fn watch(window: &gtk::Window) -> std::rc::Rc<Self> {
+        let activity = std::rc::Rc::new(Self::default());
+        let a = activity.clone();
+        window.connect_enter_notify_event(move |_, _| {
+            a.hovered.set(true);
+            Inhibit(false)
+        });
+        // Moving onto a child window leaves the toplevel as well.
+        let a = activity.clone();
+        window.connect_leave_notify_event(move |w, event| {
+            let (x, y) = event.get_position();
+            let inside = x >= 0.0
+                && y >= 0.0
+                && x < f64::from(w.get_allocated_width())
+                && y < f64::from(w.get_allocated_height());
+            a.hovered.set(inside);
+            Inhibit(false)
+        });
+        let a = activity.clone();
+        window.connect_key_press_event(move |_, _| {
+            a.typed.set(true);
+            Inhibit(false)
+        });
+        let a = activity.clone();
+        window.connect_focus_out_event(move |_, _| {
+            a.typed.set(false);
+            Inhibit(false)
+        });
+        activity
+    }
+
+    fn is_active(&self, window: &gtk::Window) -> bool {
+        self.hovered.get() || (self.typed.get() && window.is_active())
+    }
+
+    /* For widgets which are only put into the window after they were created:
+     * the returned check starts watching the window once the widget is in one.
+     */
+    fn watch_later(widget: gtk::Widget) -> impl Fn() -> bool {
+        let watched: std::cell::RefCell<Option<(gtk::Window, std::rc::Rc<Activity>)>> =
+            std::cell::RefCell::new(None);
+        move || {
+            if watched.borrow().is_none() {
+                let window = widget
+                    .get_toplevel()
+                    .and_then(|w| w.downcast::<gtk::Window>().ok());
+                if let Some(window) = window {
+                    let activity = Activity::watch(&window);
+                    *watched.borrow_mut() = Some((window, activity));
+                }
+            }
+            let watched = watched.borrow();
+            watched
+                .as_ref()
+                .is_some_and(|(window, activity)| activity.is_active(window))
+        }
+    }
+}
+
+// Shrinks from full to empty while the time until an automatic press runs out.
+fn create_gtk_timeout_bar() -> gtk::ProgressBar {
+    let bar = gtk::ProgressBar::new();
+    bar.set_fraction(1.0);
+    add_style_class(&bar, "timeout-bar");
+    bar
+}
+
+/* Counts down to the automatic press of a --button-timeout button. Pressing
+ * any button stops it, it pauses while the user is busy with the dialog.
+ */
+fn create_gtk_countdown(
+    label: &str,
+    secs: u32,
+    fire: std::rc::Rc<dyn Fn()>,
+    chosen: std::rc::Rc<std::cell::Cell<bool>>,
+) -> gtk::Box {
+    let name = strip_mnemonics(label);
+    let countdown = gtk::Label::new(format!("{} in {}s", name, secs).as_str());
+    let bar = create_gtk_timeout_bar();
+    let countdown_box = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    countdown_box.pack_start(&countdown, false, false, 0);
+    countdown_box.pack_start(&bar, false, true, 0);
+    let remaining = std::cell::Cell::new(secs);
+    let countdown_clone = countdown_box.clone();
+    let busy = Activity::watch_later(countdown_box.clone().upcast());
+    gtk::timeout_add_seconds(1, move || {
+        if chosen.get() {
+            countdown_clone.hide();
+            return gtk::Continue(false);
+        }
+        if busy() {
+            countdown.set_text(&format!("{} in {}s, paused", name, remaining.get()));
+            return gtk::Continue(true);
+        }
+        remaining.set(remaining.get() - 1);
+        if remaining.get() == 0 {
+            countdown_clone.hide();
+            fire();
+            return gtk::Continue(false);
+        }
+        countdown.set_text(&format!("{} in {}s", name, remaining.get()));
+        bar.set_fraction(f64::from(remaining.get()) / f64::from(secs));
+        gtk::Continue(true)
+    });
+    countdown_box
+}
+
+/* Presses the --timeout button once nobody interacted with the dialog for secs,
+ * the dialog closes afterwards. Hovering or typing starts the period again,
+ * pressing any button stops it. The returned bar shows the time left.
+ */
+fn start_idle_timeout(
+    secs: u32,
+    fire: std::rc::Rc<dyn Fn()>,
+    chosen: std::rc::Rc<std::cell::Cell<bool>>,
+) -> gtk::ProgressBar {
+    let bar = create_gtk_timeout_bar();
+    let widget = bar.clone();
+    let idle = std::cell::Cell::new(0);
+    let busy = Activity::watch_later(bar.clone().upcast());
+    gtk::timeout_add_seconds(1, move || {
+        if chosen.get() {
+            widget.hide();
+            return gtk::Continue(false);
+        }
+        if busy() {
+            idle.set(0);
+            widget.set_fraction(1.0);
+            return gtk::Continue(true);
+        }
+        idle.set(idle.get() + 1);
+        if idle.get() >= secs {
+            fire();
+            return gtk::Continue(false);
+        }
+        widget.set_fraction(f64::from(secs - idle.get()) / f64::from(secs));
+        gtk::Continue(true)
+    });
+    bar
+}
+
+fn format_elapsed(secs: u64) -> String {
+    if secs < 60 {
+        String::from("shown just now")
+    } else if secs < 3600 {
+        format!("shown {}m ago", secs / 60)
+    } else if secs < 86400 {
+        format!("shown {}h {}m ago", secs / 3600, (secs % 3600) / 60)
+    } else {
+        format!("shown {}d {}h ago", secs / 86400, (secs % 86400) / 3600)
+    }
+}
+
+// An icon is a name from the theme, unless it looks like the path of an image.
+fn is_icon_file(icon: &str) -> bool {
+    let lower = icon.to_lowercase();
+    icon.contains('/') || lower.ends_with(".png") || lower.ends_with(".svg")
+}
+
+fn create_gtk_icon(icon: &OsStr, size: IconSize) -> gtk::Image {
+    let name = icon.to_string_lossy();
+    if is_icon_file(&name) {
+        let pixels = size.pixels();
+        match gdk_pixbuf::Pixbuf::new_from_file_at_size(icon, pixels, pixels) {
+            Ok(pixbuf) => return gtk::Image::new_from_pixbuf(&pixbuf),
+            Err(e) => warning!("Couldn't load icon {}: {}", name, e),
+        }
+    }
+    match size {
+        IconSize::Named(size) => gtk::Image::new_from_icon_name(name.as_ref(), size),
+        IconSize::Pixels(pixels) => {
+            let image = gtk::Image::new_from_icon_name(name.as_ref(), 4);
+            image.set_pixel_size(pixels);
+            image
+        }
+    }
+}
+
+fn create_gtk_button(
+    caption: &str,
+    icon: &Option<OsString>,
+    icon_size: IconSize,
+    spacing: i32,
+) -> gtk::Button {
+    let gtk_button = gtk::Button::new();
+    let b_box = gtk::Box::new(gtk::Orientation::Horizontal, spacing);
+    let label = gtk::Label::new_with_mnemonic(caption);
+    label.set_halign(gtk::Align::Center);
+    b_box.pack_start(&label, true, true, 0);
+    if let Some(v) = &icon {
+        let image = create_gtk_icon(v, icon_size);
+        image.set_halign(gtk::Align::Start);
+        b_box.pack_end(&image, false, true, 0);
+    }
+    gtk_button.add(&b_box);
+    gtk_button
+}
+
+fn create_gtk_action_button(button: &Button, icon_size: IconSize, spacing: i32) -> gtk::Button {
+    let icon = match (&button.icon, button.icon_layout) {
+        (Some(icon), IconLayout::Left) | (Some(icon), IconLayout::Only) => icon,
+        _ => return create_gtk_button(button.label.as_str(), &button.icon, icon_size, spacing),
+    };
+    let gtk_button = gtk::Button::new();
+    let image = create_gtk_icon(icon, icon_size);
+    if button.icon_layout == IconLayout::Only {
+        gtk_button.add(&image);
+        gtk_button.set_tooltip_text(Some(strip_mnemonics(&button.label).as_str()));
+        return gtk_button;
+    }
+    let b_box = gtk::Box::new(gtk::Orientation::Horizontal, spacing);
+    let label = gtk::Label::new_with_mnemonic(button.label.as_str());
+    label.set_halign(gtk::Align::Center);
+    image.set_halign(gtk::Align::End);
+    b_box.pack_start(&image, false, true, 0);
+    b_box.pack_start(&label, true, true, 0);
+    gtk_button.add(&b_box);
+    gtk_button
+}
+
+// Asks the question of --confirm in a modal dialog on top of the window.
+fn confirmed(gtk_button: &gtk::Button, question: &str) -> bool {
+    use glib::translate::ToGlib;
+    let parent = gtk_button
+        .get_toplevel()
+        .and_then(|w| w.downcast::<gtk::Window>().ok());
+    let dialog = gtk::MessageDialog::new(
+        parent.as_ref(),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        gtk::MessageType::Question,
+        gtk::ButtonsType::YesNo,
+        question,
+    );
+    let response = dialog.run();
+    dialog.destroy();
+    response == gtk::ResponseType::Yes.to_glib()
+}
+
+// The button stays insensitive until the entry contains exactly the phrase.
+fn create_gtk_confirm_entry(gtk_button: &gtk::Button, phrase: &str) -> gtk::Entry {
+    let entry = gtk::Entry::new();
+    entry.set_placeholder_text(format!("Type \"{}\" to confirm", phrase).as_str());
+    gtk_button.set_sensitive(false);
+    let gtk_button = gtk_button.clone();
+    let phrase = phrase.to_string();
+    entry.connect_changed(move |e| {
+        gtk_button.set_sensitive(e.get_text().as_deref() == Some(phrase.as_str()));
+    });
+    entry
+}
+
+/* The action only fires after the button has been held down for `ms` milliseconds.
+ * A progress bar below the button fills up while it is held and resets on release.
+ */
+fn create_gtk_hold_button<F: Fn() + 'static>(
+    gtk_button: &gtk::Button,
+    ms: u32,
+    activate: F,
+) -> gtk::Box {
+    let hold_box = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    let progress = gtk::ProgressBar::new();
+    hold_box.pack_start(gtk_button, true, true, 0);
+    hold_box.pack_start(&progress, false, true, 0);
+
+    let activate = std::rc::Rc::new(activate);
+    let pressed_since = std::rc::Rc::new(std::cell::Cell::new(None));
+    let generation = std::rc::Rc::new(std::cell::Cell::new(0u32));
+    let start = {
+        let pressed_since = pressed_since.clone();
+        std::rc::Rc::new(move || {
+            pressed_since.set(Some(std::time::Instant::now()));
+            generation.set(generation.get().wrapping_add(1));
+            let current = generation.get();
+            let generation = generation.clone();
+            let pressed_since = pressed_since.clone();
+            let progress = progress.clone();
+            let activate = activate.clone();
+            gtk::timeout_add(16, move || {
+                let start = match pressed_since.get() {
+                    Some(start) if generation.get() == current => start,
+                    _ => {
+                        progress.set_fraction(0.0);
+                        return gtk::Continue(false);
+                    }
+                };
+                let fraction = start.elapsed().as_millis() as f64 / f64::from(ms);
+                if fraction >= 1.0 {
+                    pressed_since.set(None);
+                    progress.set_fraction(0.0);
+                    activate();
+                    return gtk::Continue(false);
+                }
+                progress.set_fraction(fraction);
+                gtk::Continue(true)
+            });
+        })
+    };
+    {
+        let start = start.clone();
+        gtk_button.connect_button_press_event(move |_, event| {
+            if event.get_button() == 1 {
+                start();
+            }
+            Inhibit(false)
+        });
+    }
+    {
+        let pressed_since = pressed_since.clone();
+        gtk_button.connect_button_release_event(move |_, event| {
+            if event.get_button() == 1 {
+                pressed_since.set(None);
+            }
+            Inhibit(false)
+        });
+    }
+    // Enter and Space are held the same way, their key repeats don't restart the hold.
+    let key_down = std::rc::Rc::new(std::cell::Cell::new(false));
+    {
+        let key_down = key_down.clone();
+        gtk_button.connect_key_press_event(move |_, event| {
+            if !is_activate_key(event.get_keyval()) {
+                return Inhibit(false);
+            }
+            if !key_down.replace(true) {
+                start();
+            }
+            Inhibit(true)
+        });
+    }
+    gtk_button.connect_key_release_event(move |_, event| {
+        if !is_activate_key(event.get_keyval()) {
+            return Inhibit(false);
+        }
+        key_down.set(false);
+        pressed_since.set(None);
+        Inhibit(true)
+    });
+    hold_box
+}
+
+// Keys which click a focused button.
+fn is_activate_key(key: gdk::enums::key::Key) -> bool {
+    use gdk::enums::key;
+    [
+        key::Return,
+        key::KP_Enter,
+        key::ISO_Enter,
+        key::space,
+        key::KP_Space,
+    ]
+    .contains(&key)
+}
+
+/* Middle click runs the secondary action right away, right click (or the menu
+ * key) offers it in a context menu.
+ */
+fn connect_secondary_action<F: Fn() + 'static>(gtk_button: &gtk::Button, label: &str, activate: F) {
+    let activate = std::rc::Rc::new(activate);
+    let menu = gtk::Menu::new();
+    let item = gtk::MenuItem::new_with_mnemonic(label);
+    {
+        let activate = activate.clone();
+        item.connect_activate(move |_| activate());
+    }
+    menu.append(&item);
+    menu.show_all();
+    {
+        let menu = menu.clone();
+        gtk_button.connect_popup_menu(move |_| {
+            menu.popup_easy(0, gtk::get_current_event_time());
+            true
+        });
+    }
+    gtk_button.connect_button_press_event(move |_, event| match event.get_button() {
+        2 => {
+            activate();
+            Inhibit(true)
+        }
+        3 => {
+            menu.popup_easy(event.get_button(), event.get_time());
+            Inhibit(true)
+        }
+        _ => Inhibit(false),
+    });
+}
+
+/* Masked display plus a numeric keypad. Without a fixed length the action buttons
+ * are always sensitive, otherwise only once exactly `length` digits were entered.
+ */
+fn create_gtk_pin_pad(
+    length: Option<u32>,
+    action_buttons: &[gtk::Button],
+) -> (gtk::Box, gtk::Entry) {
+    let pin_box = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    let entry = gtk::Entry::new();
+    entry.set_visibility(false);
+    entry.set_input_purpose(gtk::InputPurpose::Pin);
+    entry.set_alignment(0.5);
+    if let Some(l) = length {
+        entry.set_max_length(l as i32);
+        for b in action_buttons {
+            b.set_sensitive(false);
+        }
+    }
+    let action_buttons = action_buttons.to_vec();
+    entry.connect_changed(move |e| {
+        let text = e.get_text().unwrap_or_default();
+        let digits: String = text.chars().filter(char::is_ascii_digit).collect();
+        if digits != text {
+            e.set_text(&digits);
+            return;
+        }
+        if let Some(l) = length {
+            for b in &action_buttons {
+                b.set_sensitive(digits.chars().count() == l as usize);
+            }
+        }
+    });
+    pin_box.pack_start(&entry, false, true, 0);
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(5);
+    grid.set_column_homogeneous(true);
+    let keys = [
+        "1", "2", "3", "4", "5", "6", "7", "8", "9", "C", "0", "\u{232b}",
+    ];
+    for (i, key) in keys.iter().enumerate() {
+        let key_button = gtk::Button::new_with_label(key);
+        key_button.set_can_focus(false);
+        let entry = entry.clone();
+        let key = key.to_string();
+        key_button.connect_clicked(move |_| {
+            let mut text = entry.get_text().unwrap_or_default();
+            match key.as_str() {
+                "C" => text.clear(),
+                "\u{232b}" => {
+                    text.pop();
+                }
+                digit => text.push_str(digit),
+            }
+            entry.set_text(&text);
+            entry.set_position(-1);
+        });
+        grid.attach(&key_button, (i % 3) as i32, (i / 3) as i32, 1, 1);
+    }
+    pin_box.pack_start(&grid, true, true, 0);
+    (pin_box, entry)
+}
+
+/* One line per spawned action for --print-pid: PID, process group (only on
+ * unix) and label. "-" writes to stdout.
+ */
+fn record_pid(target: &std::path::Path, label: &str, child: &std::process::Child) {
+    let pid = child.id();
+    #[cfg(unix)]
+    let line = format!(
+        "{} {} {}\n",
+        pid,
+        unsafe { libc::getpgid(pid as libc::pid_t) },
+        strip_mnemonics(label)
+    );
+    #[cfg(not(unix))]
+    let line = format!("{} {}\n", pid, strip_mnemonics(label));
+    let result = if target == std::path::Path::new("-") {
+        let mut stdout = std::io::stdout();
+        stdout
+            .write_all(line.as_bytes())
+            .and_then(|_| stdout.flush())
+    } else {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(target)
+            .and_then(|mut f| f.write_all(line.as_bytes()))
+    };
+    if let Err(e) = result {
+        warning!("Couldn't write PID to {}: {}", target.to_string_lossy(), e);
+    }
+}
+
+// Passes stderr of an action through and returns its last lines.
+fn tee_stderr(mut pipe: std::process::ChildStderr) -> String {
+    use std::io::Read;
+    let mut output = Vec::new();
+    let mut buffer = [0u8; 4096];
+    while let Ok(n) = pipe.read(&mut buffer) {
+        if n == 0 {
+            break;
+        }
+        let _ = std::io::stderr().write_all(&buffer[..n]);
+        output.extend_from_slice(&buffer[..n]);
+    }
+    let output = String::from_utf8_lossy(&output);
+    let lines: Vec<&str> = output.trim_end().lines().collect();
+    lines[lines.len().saturating_sub(20)..].join("\n")
+}
+
+type ActionResult = (std::io::Result<std::process::ExitStatus>, String);
+
+/* The glyph next to a button for --show-status: running (or queued), then ✓ or ✗ with the
+ * end of stderr (or the exit code) as tooltip.
+ */
+fn show_action_status(
+    glyph: &gtk::Label,
+    results: std::sync::mpsc::Receiver<ActionResult>,
+    queued: bool,
+) {
+    glyph.set_text("…");
+    glyph.set_tooltip_text(Some(if queued { "Queued" } else { "Running" }));
+    if let Some(context) = glyph.get_style_context() {
+        context.remove_class("success");
+        context.remove_class("failure");
+    }
+    let glyph = glyph.clone();
+    gtk::timeout_add(100, move || match results.try_recv() {
+        Ok((status, errors)) => {
+            let success = status.as_ref().is_ok_and(|s| s.success());
+            let summary = match &status {
+                Ok(s) if s.success() => String::from("Finished successfully"),
+                Ok(s) => match s.code() {
+                    Some(code) => format!("Failed with exit code {}", code),
+                    None => String::from("Killed by a signal"),
+                },
+                Err(e) => format!("Couldn't be watched: {}", e),
+            };
+            let tooltip = if errors.is_empty() {
+                summary
+            } else {
+                format!("{}\n\n{}", summary, errors)
+            };
+            set_action_status(&glyph, success, &tooltip);
+            gtk::Continue(false)
+        }
+        Err(std::sync::mpsc::TryRecvError::Empty) => gtk::Continue(true),
+        Err(std::sync::mpsc::TryRecvError::Disconnected) => gtk::Continue(false),
+    });
+}
+
+fn set_action_status(glyph: &gtk::Label, success: bool, tooltip: &str) {
+    glyph.set_text(if success { "✓" } else { "✗" });
+    glyph.set_tooltip_text(Some(tooltip));
+    add_style_class(glyph, if success { "success" } else { "failure" });
+}
+
+/* Shared by all buttons, everything run_button needs besides the button and
+ * the entered PIN.
+ */
+#[derive(Clone)]
+struct ActionContext {
+    audit_log: Option<AuditLog>,
+    delivery: SecretDelivery,
+    notify_result: bool,
+    print_pid: Option<std::path::PathBuf>,
+    queue: Option<queue::ActionQueue>,
+}
+
+impl ActionContext {
+    fn new(config: &Configuration) -> Self {
+        let queue = if config.queue {
+            let (queue, worker) = queue::ActionQueue::new();
+            // The queue is closed with the window, the worker runs what is left before the process exits.
+            keep_watcher(worker);
+            Some(queue)
+        } else {
+            None
+        };
+        Self {
+            audit_log: config.audit_log.clone(),
+            delivery: config.secret_delivery.clone(),
+            notify_result: config.notify_result,
+            print_pid: config.print_pid.clone(),
+            queue,
+        }
+    }
+}
+
+fn start_action(
+    label: &str,
+    command: &Command,
+    input: Option<&[u8]>,
+    delivery: &SecretDelivery,
+    print_pid: &Option<std::path::PathBuf>,
+    audit_log: &Option<AuditLog>,
+    capture_stderr: bool,
+) -> std::process::Child {
+    let child = command.execute_with_input(input, delivery, capture_stderr);
+    if let Some(target) = print_pid {
+        record_pid(target, label, &child);
+    }
+    if let Some(log) = audit_log {
+        log.button_pressed(label, &command.command.to_string_lossy());
+    }
+    child
+}
+
+// Waits for the action and reports its result, runs outside of the main thread.
+#[cfg_attr(not(feature = "notifications"), allow(unused_variables))]
+fn finish_action(
+    mut child: std::process::Child,
+    label: &str,
+    audit_log: &Option<AuditLog>,
+    notify_result: bool,
+    results: std::sync::mpsc::Sender<ActionResult>,
+) {
+    #[cfg(feature = "notifications")]
+    let started = std::time::Instant::now();
+    let errors = child.stderr.take().map(tee_stderr).unwrap_or_default();
+    let status = child.wait();
+    if let Some(log) = audit_log {
+        log.command_finished(label, &status);
+    }
+    #[cfg(feature = "notifications")]
+    {
+        if notify_result {
+            notify::action_finished(label, &status, started.elapsed());
+        }
+    }
+    let _ = results.send((status, errors));
+}
+
+/* The --queue worker and threads which wait for actions to record their
+ * result in the audit log or a notification. After the window is gone the
+ * process stays until they are done, so nothing queued or recorded gets lost.
+ */
+static WATCHERS: std::sync::Mutex<Vec<std::thread::JoinHandle<()>>> =
+    std::sync::Mutex::new(Vec::new());
+
+fn keep_watcher(watcher: std::thread::JoinHandle<()>) {
+    WATCHERS.lock().unwrap().push(watcher);
+}
+
+// Called once the main loop ended.
+fn wait_for_actions() {
+    let watchers = std::mem::take(&mut *WATCHERS.lock().unwrap());
+    for watcher in watchers {
+        let _ = watcher.join();
+    }
+}
+
+fn run_button(
+    label: &str,
+    command: &Command,
+    input: Option<&[u8]>,
+    context: &ActionContext,
+    status: Option<&gtk::Label>,
+) {
+    let (results, received) = std::sync::mpsc::channel();
+    if let Some(glyph) = status {
+        show_action_status(glyph, received, context.queue.is_some());
+    }
+    let capture_stderr = status.is_some();
+    let audit_log = context.audit_log.clone();
+    let notify_result = context.notify_result;
+    if let Some(queue) = &context.queue {
+        let name = String::from(label);
+        let command = command.clone();
+        let input = input.map(<[u8]>::to_vec);
+        let delivery = context.delivery.clone();
+        let print_pid = context.print_pid.clone();
+        let job = move || {
+            let child = start_action(
+                &name,
+                &command,
+                input.as_deref(),
+                &delivery,
+                &print_pid,
+                &audit_log,
+                capture_stderr,
+            );
+            finish_action(child, &name, &audit_log, notify_result, results);
+        };
+        queue.push(label, Box::new(job));
+        return;
+    }
+    let child = start_action(
+        label,
+        command,
+        input,
+        &context.delivery,
+        &context.print_pid,
+        &audit_log,
+        capture_stderr,
+    );
+    // Every action is waited for, only recorded results keep the process alive.
+    let label = String::from(label);
+    let recorded = audit_log.is_some() || notify_result;
+    let watcher = std::thread::spawn(move || {
+        finish_action(child, &label, &audit_log, notify_result, results);
+    });
+    if recorded {
+        keep_watcher(watcher);
+    }
+}
+
+// "_Save__as" is shown as "Save_as" with S as mnemonic.
+fn split_mnemonic(label: &str) -> (String, Option<char>) {
+    let mut text = String::new();
+    let mut mnemonic = None;
+    let mut chars = label.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '_' => {
+                let next = chars.next();
+                if mnemonic.is_none() && next != Some('_') {
+                    mnemonic = next;
+                }
+                text.extend(next);
+            }
+            c => text.push(c),
+        }
+    }
+    (text, mnemonic)
+}
+
+fn strip_mnemonics(label: &str) -> String {
+    split_mnemonic(label).0
+}
+
+// The character after the first single underscore, lowercased like gtk compares it.
+fn mnemonic(label: &str) -> Option<char> {
+    split_mnemonic(label)
+        .1
+        .and_then(|c| c.to_lowercase().next())
+}
+
+/* What --pipe-to writes: the label without mnemonic underscores and, with
+ * the PIN keypad, the entered PIN on a second line.
+ */
+fn choice_text(label: &str, pin: Option<&str>) -> String {
+    let mut text = strip_mnemonics(label);
+    text.push('\n');
+    if let Some(pin) = pin {
+        text.push_str(pin);
+        text.push('\n');
+    }
+    text
+}
+
+/* With --print the pressed button is written to stdout instead of running its
+ * action and the dialog closes. The returned exit code is 1 until a button
+ * other than cancel was pressed.
+ */
+fn print_choice(config: &mut Configuration) -> std::rc::Rc<std::cell::Cell<i32>> {
+    let exit_code = std::rc::Rc::new(std::cell::Cell::new(1));
+    let mode = match config.print {
+        Some(m) => m,
+        None => return exit_code,
+    };
+    let labels: Vec<String> = config.buttons.iter().map(|b| b.label.clone()).collect();
+    let code = exit_code.clone();
+    config.on_choice = Some(std::rc::Rc::new(
+        move |index: Option<usize>, pin: Option<String>| {
+            if let Some(i) = index {
+                match mode {
+                    PrintMode::Label => print!("{}", choice_text(&labels[i], pin.as_deref())),
+                    PrintMode::Index => print!("{}", choice_text(&i.to_string(), pin.as_deref())),
+                    // Nothing checked prints nothing, not an empty item.
+                    PrintMode::Value => {
+                        let value = pin.unwrap_or_default();
+                        if !value.is_empty() {
+                            println!("{}", value);
+                        }
+                    }
+                }
+                code.set(0);
+            }
+            gtk::main_quit();
+        },
+    ));
+    exit_code
+}
+
+#[derive(Clone)]
+pub struct GtkButtons {
+    container: gtk::Box,
+    actions: Vec<gtk::Button>,
+    // Also the default button, unless --default says otherwise
+    cancel: gtk::Button,
+    // Widget which gets the initial focus
+    focus: gtk::Widget,
+}
+
+fn create_gtk_buttons(config: &Configuration) -> GtkButtons {
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, config.spacing);
+    WidgetExt::set_name(&vbox, "buttons");
+    let gtk_buttons: Vec<gtk::Button> = config
+        .buttons
+        .iter()
+        .map(|b| create_gtk_action_button(b, config.button_icon_size, config.spacing))
+        .collect();
+    for (button, b) in config.buttons.iter().zip(&gtk_buttons) {
+        add_style_class(b, "action-button");
+        match button.style {
+            ButtonStyle::Destructive => add_style_class(b, "destructive-action"),
+            ButtonStyle::Suggested => add_style_class(b, "suggested-action"),
+            ButtonStyle::Normal => {}
+        }
+        // Replaces the label shown by --icon-only as well.
+        if let Some(tooltip) = &button.tooltip {
+            b.set_tooltip_text(Some(tooltip.as_str()));
+        }
+    }
+    if let Some(width) = config.button_min_width {
+        for b in &gtk_buttons {
+            b.set_size_request(width as i32, -1);
+        }
+    }
+    let mut focus_chain: Vec<gtk::Widget> = Vec::new();
+    let mut button_rows: Vec<Vec<gtk::Widget>> = Vec::new();
+    // Shows the response status of HTTP buttons.
+    #[cfg(feature = "http")]
+    let http_status = gtk::Label::new(None);
+    let mut prompt_focus = None;
+    // What --store-secret found in the keyring or stored there last.
+    #[cfg(feature = "dbus")]
+    let stored_secret: std::rc::Rc<std::cell::RefCell<Option<String>>> = Default::default();
+    // The PIN or the value of the prompt
+    let input: Option<std::rc::Rc<dyn Fn() -> String>> = if config.pin {
+        let (pin_pad, entry) = create_gtk_pin_pad(config.pin_length, &gtk_buttons);
+        #[cfg(feature = "dbus")]
+        {
+            if let Some(store) = &config.secret_store {
+                let entry = entry.clone();
+                let stored_secret = stored_secret.clone();
+                store.lookup_in_background(move |secret| {
+                    // Typing started before the keyring answered wins.
+                    if entry.get_text().unwrap_or_default().is_empty() {
+                        entry.set_text(&secret);
+                    }
+                    *stored_secret.borrow_mut() = Some(secret);
+                });
+            }
+        }
+        vbox.pack_start(&pin_pad, true, true, 0);
+        focus_chain.push(pin_pad.upcast());
+        Some(std::rc::Rc::new(move || {
+            entry.get_text().unwrap_or_default()
+        }))
+    } else if let Some(prompt) = &config.prompt {
+        let gtk_prompt = prompt::create_gtk_prompt(prompt, config.spacing);
+        vbox.pack_start(&gtk_prompt.widget, true, true, 0);
+        focus_chain.push(gtk_prompt.widget);
+        prompt_focus = Some(gtk_prompt.focus);
+        Some(gtk_prompt.value)
+    } else if let Some(value) = config.chained_input.clone() {
+        Some(std::rc::Rc::new(move || value.clone()))
+    } else {
+        None
+    };
+
+    // The bar is a single row, whatever --layout says.
+    let orientation = match config.layout {
+        ButtonLayout::Vertical if !config.bar => gtk::Orientation::Vertical,
+        _ => gtk::Orientation::Horizontal,
+    };
+    let button_box = create_gtk_button_box(orientation, config.spacing);
+    vbox.pack_start(&button_box, true, true, 0);
+    // With --columns every box gets a grid, filled row by row.
+    let mut grid = config
+        .columns
+        .map(|_| (create_gtk_button_grid(&button_box, config.spacing), 0));
+
+    let context = ActionContext::new(config);
+    // Set by every button, it stops the countdowns of --button-timeout.
+    let chosen = std::rc::Rc::new(std::cell::Cell::new(false));
+    let mut activators: Vec<std::rc::Rc<dyn Fn()>> = Vec::new();
+    // Buttons following a --tab go into its page, the ones before stay above.
+    let notebook = gtk::Notebook::new();
+    let mut tab_pages: Vec<gtk::Box> = Vec::new();
+    for (index, (button, gtk_button)) in config
+        .buttons
+        .iter()
+        .zip(gtk_buttons.iter().cloned())
+        .enumerate()
+    {
+        if let Some((name, _)) = config.tabs.iter().find(|(_, first)| *first == index) {
+            if tab_pages.is_empty() {
+                vbox.pack_start(&notebook, true, true, 0);
+            }
+            let page = create_gtk_button_box(orientation, config.spacing);
+            page.set_border_width(config.spacing as u32);
+            notebook.append_page(&page, Some(&gtk::Label::new_with_mnemonic(name.as_str())));
+            if grid.is_some() {
+                grid = Some((create_gtk_button_grid(&page, config.spacing), 0));
+            }
+            tab_pages.push(page);
+        }
+        // In a grid the entry of --require-typing shares the cell of its button.
+        let container = match &grid {
+            Some(_) => gtk::Box::new(gtk::Orientation::Vertical, config.spacing),
+            None => tab_pages.last().unwrap_or(&button_box).clone(),
+        };
+        let mut row: Vec<gtk::Widget> = Vec::new();
+        if let Some(phrase) = &button.require_typing {
+            let entry = create_gtk_confirm_entry(&gtk_button, phrase);
+            container.pack_start(&entry, true, true, 0);
+            row.push(entry.upcast());
+        }
+        let button_clone = button.clone();
+        let action_context = context.clone();
+        let exit_after_action = config.exits_after(button);
+        let input = input.clone();
+        #[cfg(feature = "dbus")]
+        let secret_store = config.secret_store.clone();
+        #[cfg(feature = "dbus")]
+        let stored_secret = stored_secret.clone();
+        #[cfg(feature = "http")]
+        let http_status = http_status.clone();
+        let on_choice = config.on_choice.clone();
+        let pipe_to = config.pipe_to.clone();
+        let status = if config.show_status {
+            let glyph = gtk::Label::new(None);
+            glyph.set_width_chars(2);
+            add_style_class(&glyph, "action-status");
+            Some(glyph)
+        } else {
+            None
+        };
+        let status_clone = status.clone();
+        let chosen_clone = chosen.clone();
+        let activate = move || {
+            chosen_clone.set(true);
+            if exit_after_action {
+                let code = button_clone.exit_code.unwrap_or(0);
+                ACTION_EXIT_CODE.store(code, std::sync::atomic::Ordering::Relaxed);
+            }
+            #[cfg(all(feature = "dbus", unix))]
+            signal::button_activated(&button_clone.label, Some(index));
+            if let Some(handler) = &on_choice {
+                handler(Some(index), input.as_ref().map(|value| value()));
+                return;
+            }
+            if let Some(command) = &pipe_to {
+                let pin = input.as_ref().map(|value| value());
+                let text = choice_text(&button_clone.label, pin.as_deref());
+                let mut child = command.execute_with_input(
+                    Some(text.as_bytes()),
+                    &SecretDelivery::Stdin,
+                    false,
+                );
+                std::thread::spawn(move || child.wait());
+            }
+            let command = match &button_clone.action {
+                Action::Command(command) => command,
+                Action::Open(target) => {
+                    open_target(
+                        &button_clone.label,
+                        target,
+                        &action_context.audit_log,
+                        status_clone.as_ref(),
+                    );
+                    if exit_after_action {
+                        gtk::main_quit();
+                    }
+                    return;
+                }
+                #[cfg(feature = "http")]
+                Action::Http(request) => {
+                    let pin = input.as_ref().map(|value| value());
+                    http::send(
+                        request,
+                        &button_clone.label,
+                        pin.as_deref(),
+                        &http_status,
+                        &action_context.audit_log,
+                        exit_after_action,
+                    );
+                    return;
+                }
+            };
+            #[cfg(feature = "dbus")]
+            {
+                if let (Some(store), Some(value)) = (&secret_store, &input) {
+                    let value = value();
+                    if stored_secret.borrow().as_ref() != Some(&value) {
+                        keep_watcher(store.store_in_background(value.clone()));
+                        *stored_secret.borrow_mut() = Some(value);
+                    }
+                }
+            }
+            // On stdin the PIN is terminated by a newline for `read`.
+            let terminator = if action_context.delivery == SecretDelivery::Stdin {
+                "\n"
+            } else {
+                ""
+            };
+            let text = input
+                .as_ref()
+                .map(|value| format!("{}{}", value(), terminator));
+            run_button(
+                &button_clone.label,
+                command,
+                text.as_ref().map(|t| t.as_bytes()),
+                &action_context,
+                status_clone.as_ref(),
+            );
+            if exit_after_action {
+                gtk::main_quit();
+            }
+        };
+        let activate: std::rc::Rc<dyn Fn()> = std::rc::Rc::new(activate);
+        activators.push(activate.clone());
+        // A dialog reporting its choice (--chain, --serve, ...) has nothing to run.
+        let secondary = button
+            .secondary
+            .clone()
+            .filter(|_| config.on_choice.is_none());
+        if let Some((label, action)) = secondary {
+            let context = context.clone();
+            let status = status.clone();
+            let exit_after_action = config.exits_after(button);
+            let chosen = chosen.clone();
+            let name = label.clone();
+            let exit_code = button.exit_code.unwrap_or(0);
+            let question = button.confirm.clone();
+            let locked = gtk_button.clone();
+            connect_secondary_action(&gtk_button, &label, move || {
+                // Still locked by --require-typing or an incomplete PIN.
+                if !locked.is_sensitive() {
+                    return;
+                }
+                // It runs with the button's --confirm question as well.
+                if let Some(question) = &question {
+                    if !confirmed(&locked, question) {
+                        return;
+                    }
+                }
+                chosen.set(true);
+                match &action {
+                    Action::Command(command) => {
+                        run_button(&name, command, None, &context, status.as_ref())
+                    }
+                    Action::Open(target) => {
+                        open_target(&name, target, &context.audit_log, status.as_ref())
+                    }
+                    // Rejected by the parser
+                    #[cfg(feature = "http")]
+                    Action::Http(_) => {}
+                }
+                if exit_after_action {
+                    ACTION_EXIT_CODE.store(exit_code, std::sync::atomic::Ordering::Relaxed);
+                    gtk::main_quit();
+                }
+            });
+        }
+        // Only a click asks, the timeouts run the button right away.
+        let activate: std::rc::Rc<dyn Fn()> = match button.confirm.clone() {
+            Some(question) => {
+                let gtk_button = gtk_button.clone();
+                std::rc::Rc::new(move || {
+                    if confirmed(&gtk_button, &question) {
+                        activate()
+                    }
+                })
+            }
+            None => activate,
+        };
+        let action: gtk::Widget = if let Some(ms) = button.hold_to_confirm {
+            create_gtk_hold_button(&gtk_button, ms, move || activate()).upcast()
+        } else {
+            gtk_button.connect_clicked(move |_| activate());
+            gtk_button.upcast()
+        };
+        let action = match &status {
+            Some(glyph) => {
+                let hbox = gtk::Box::new(gtk::Orientation::Horizontal, config.spacing);
+                hbox.pack_start(&action, true, true, 0);
+                hbox.pack_start(glyph, false, false, 0);
+                hbox.upcast()
+            }
+            None => action,
+        };
+        container.pack_start(&action, true, true, config.padding);
+        if let (Some((grid, placed)), Some(columns)) = (&mut grid, config.columns) {
+            let columns = columns as i32;
+            grid.attach(&container, *placed % columns, *placed / columns, 1, 1);
+            *placed += 1;
+        }
+        row.push(action);
+        button_rows.push(row);
+    }
+    let button2 = create_gtk_button(
+        config.cancel_label.as_str(),
+        &Some(OsString::from("window-close")),
+        config.button_icon_size,
+        config.spacing,
     );
-    println!("  --exit-after-action                            Program exits after a button press");
+    add_style_class(&button2, "cancel-button");
+    let audit_log = config.audit_log.clone();
+    let on_choice = config.on_choice.clone();
+    let chosen_clone = chosen.clone();
+    let cancel_label = config.cancel_label.clone();
+    let cancel_action = config.cancel_action.clone();
+    button2.connect_clicked(move |_| {
+        chosen_clone.set(true);
+        if let Some(log) = &audit_log {
+            log.button_pressed(&cancel_label, "");
+        }
+        #[cfg(all(feature = "dbus", unix))]
+        signal::button_activated(&cancel_label, None);
+        // Not waited for, it goes on after the dialog is gone.
+        if let Some(command) = &cancel_action {
+            let mut child = command.execute_with_input(None, &SecretDelivery::Stdin, false);
+            std::thread::spawn(move || child.wait());
+        }
+        match &on_choice {
+            Some(handler) => handler(None, None),
+            None => gtk::main_quit(),
+        }
+    });
+    if let Some(width) = config.button_min_width {
+        button2.set_size_request(width as i32, -1);
+    }
+    // Without it in the window the timeouts can still cancel.
+    if !config.no_cancel {
+        // Below the tabs, it leaves the whole dialog.
+        let container = if tab_pages.is_empty() {
+            &button_box
+        } else {
+            &vbox
+        };
+        container.pack_start(&button2, true, true, config.padding);
+    }
+    for (target, secs) in &config.button_timeouts {
+        let (fire, label): (std::rc::Rc<dyn Fn()>, &str) = match target {
+            FocusTarget::Button(i) => (activators[*i].clone(), &config.buttons[*i].label),
+            FocusTarget::Cancel => {
+                let cancel = button2.clone();
+                (
+                    std::rc::Rc::new(move || cancel.clicked()),
+                    config.cancel_label.as_str(),
+                )
+            }
+        };
+        let countdown = create_gtk_countdown(label, *secs, fire, chosen.clone());
+        vbox.pack_start(&countdown, false, false, 0);
+    }
+    if let Some((secs, target)) = &config.idle_timeout {
+        let fire: std::rc::Rc<dyn Fn()> = match target {
+            FocusTarget::Button(i) => {
+                let activate = activators[*i].clone();
+                // Nobody is there to close the dialog afterwards.
+                let quit = config.on_choice.is_none() && !config.exits_after(&config.buttons[*i]);
+                let code = config.buttons[*i].exit_code.unwrap_or(0);
+                std::rc::Rc::new(move || {
+                    activate();
+                    if quit {
+                        ACTION_EXIT_CODE.store(code, std::sync::atomic::Ordering::Relaxed);
+                        gtk::main_quit();
+                    }
+                })
+            }
+            FocusTarget::Cancel => {
+                let cancel = button2.clone();
+                std::rc::Rc::new(move || cancel.clicked())
+            }
+        };
+        let bar = start_idle_timeout(*secs, fire, chosen.clone());
+        vbox.pack_start(&bar, false, false, 0);
+    }
+    #[cfg(feature = "http")]
+    {
+        http_status.set_no_show_all(true);
+        vbox.pack_start(&http_status, false, false, 0);
+    }
+    if let Some(queue) = &context.queue {
+        let indicator = queue.indicator();
+        let closing = queue.clone();
+        indicator.connect_destroy(move |_| closing.close());
+        vbox.pack_start(&indicator, false, false, 0);
+    }
+    if !config.focus_order.is_empty() {
+        for target in &config.focus_order {
+            match target {
+                FocusTarget::Button(i) => focus_chain.extend(button_rows[*i].iter().cloned()),
+                FocusTarget::Cancel => focus_chain.push(button2.clone().upcast()),
+            }
+        }
+        // Buttons left out of the order follow it, every button stays reachable.
+        for (i, row) in button_rows.iter().enumerate() {
+            if !config.focus_order.contains(&FocusTarget::Button(i)) {
+                focus_chain.extend(row.iter().cloned());
+            }
+        }
+        if !config.no_cancel && !config.focus_order.contains(&FocusTarget::Cancel) {
+            focus_chain.push(button2.clone().upcast());
+        }
+        vbox.set_focus_chain(&focus_chain);
+    }
+    let focus_target = config.focus.as_ref().or_else(|| config.focus_order.first());
+    let focus_button = match focus_target.or(config.default_button.as_ref()) {
+        Some(FocusTarget::Button(i)) => gtk_buttons[*i].clone(),
+        _ if config.no_cancel => gtk_buttons[0].clone(),
+        _ => button2.clone(),
+    };
+    // Typing goes into the prompt right away.
+    let focus = match (prompt_focus, focus_target) {
+        (Some(widget), None) => widget,
+        _ => focus_button.upcast(),
+    };
+    GtkButtons {
+        container: vbox,
+        actions: gtk_buttons,
+        cancel: button2,
+        focus,
+    }
+}
+
+fn create_gtk_button_grid(parent: &gtk::Box, spacing: i32) -> gtk::Grid {
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(spacing as u32);
+    grid.set_column_spacing(spacing as u32);
+    grid.set_column_homogeneous(true);
+    parent.pack_start(&grid, true, true, 0);
+    grid
+}
+
+// Side by side the buttons get the same width, like in other dialogs.
+fn create_gtk_button_box(orientation: gtk::Orientation, spacing: i32) -> gtk::Box {
+    let button_box = gtk::Box::new(orientation, spacing);
+    button_box.set_homogeneous(orientation == gtk::Orientation::Horizontal);
+    button_box
+}
+
+fn create_gtk_dialog(config: &Configuration) -> (gtk::Window, GtkButtons) {
+    let gtk_buttons = create_gtk_buttons(config);
+    let gtk_message = create_gtk_message(config);
+    let window = create_gtk_window(config, &gtk_buttons, &gtk_message);
+    (window, gtk_buttons)
+}
+
+/* Lets gtk lay out and draw the offscreen window without entering the main loop
+ * and saves the result as PNG.
+ */
+fn save_screenshot(window: &gtk::Window, path: &std::path::Path) -> i32 {
+    while gtk::events_pending() {
+        gtk::main_iteration();
+    }
+    let pixbuf = window
+        .clone()
+        .downcast::<gtk::OffscreenWindow>()
+        .ok()
+        .and_then(|w| w.get_pixbuf());
+    let result = match pixbuf {
+        Some(p) => p.savev(path, "png", &[]).map_err(|e| e.to_string()),
+        None => Err(String::from("Nothing was rendered.")),
+    };
+    match result {
+        Ok(_) => 0,
+        Err(e) => {
+            warning!("Couldn't save screenshot {}: {}", path.to_string_lossy(), e);
+            1
+        }
+    }
+}
+
+/* Used for --help, the man page and the parser, which accepts only the
+ * options listed here.
+ */
+const OPTION_HELP: &[(&str, &str)] = &[
+    ("-h, --help", "Prints help information"),
+    ("-v, --version", "Prints version information"),
+    ("-b, --button LABEL ACTION [ICON]", "Creates a button."),
+    (
+        "-B, --button-no-terminal LABEL ACTION [ICON]",
+        "Creates a button.",
+    ),
+    (
+        "-o, --button-open LABEL URI [ICON]",
+        "Creates a button which opens URI.",
+    ),
+    (
+        "--button-tmux LABEL ACTION [ICON]",
+        "Creates a button running ACTION in a new tmux window.",
+    ),
+    (
+        "--button-http LABEL METHOD URL [BODY]",
+        "Creates a button sending a request, {value} and {label} in BODY are replaced",
+    ),
+    (
+        "--tab NAME",
+        "Puts the following buttons into a tab called NAME",
+    ),
+    (
+        "--icon-position left|right",
+        "Puts the icon of the last button left or right of its label",
+    ),
+    (
+        "--icon-only",
+        "Shows only the icon of the last button, the label becomes its tooltip",
+    ),
+    (
+        "--tooltip TEXT",
+        "Shows TEXT when the pointer rests on the last button",
+    ),
+    (
+        "--style destructive|suggested|normal",
+        "Highlights the last button, e.g. red when it deletes something",
+    ),
+    (
+        "--confirm QUESTION",
+        "Asks QUESTION with Yes and No before the last button runs",
+    ),
+    (
+        "--keep-open",
+        "Keeps the dialog open after the last button, despite --exit-after-action",
+    ),
+    ("--close", "Closes the dialog after the last button was run"),
+    (
+        "--require-typing PHRASE",
+        "Previous button needs PHRASE typed to be enabled",
+    ),
+    (
+        "--secondary LABEL ACTION",
+        "Adds an action on middle click or in the right click menu of the last button",
+    ),
+    (
+        "--code N",
+        "Exit code (0-255) when the last button ends the program with --exit-after-action",
+    ),
+    (
+        "--hold-to-confirm MS",
+        "Previous button has to be held for MS milliseconds, with the mouse, Enter or Space",
+    ),
+    (
+        "--button-timeout LABEL SECONDS",
+        "Presses LABEL (or cancel) unless a button is pressed before, pauses while in use",
+    ),
+    (
+        "--timeout SECS [BUTTON]",
+        "Presses BUTTON (label or number, default cancel) and closes after SECS without interaction",
+    ),
+    (
+        "--pin [LENGTH]",
+        "Shows a PIN keypad, the PIN is written to the action's stdin (not with -b)",
+    ),
+    (
+        "--secret-via stdin|env:NAME|fd",
+        "Default: stdin. How actions get the PIN or prompt value, fd replaces {secret_fd}",
+    ),
+    (
+        "--store-secret SCHEMA:KEY=VALUE,...",
+        "Fills in the PIN from the keyring and stores it there when it changed",
+    ),
+    (
+        "--password",
+        "Asks for a password, OK prints it unless there are buttons to pass it to",
+    ),
+    (
+        "--checklist ITEM...|-",
+        "Shows check boxes for the items (or lines of stdin), OK prints the checked ones",
+    ),
+    (
+        "--radiolist ITEM...|-",
+        "Shows radio buttons for the items (or lines of stdin), OK prints the selected one",
+    ),
+    (
+        "--combo ITEM...|-",
+        "Shows a drop-down list of the items (or lines of stdin), OK prints the chosen one",
+    ),
+    (
+        "--editable",
+        "After --combo: a value besides the items can be typed",
+    ),
+    (
+        "--scale MIN MAX [STEP] [INITIAL]",
+        "Shows a slider, OK prints the chosen value",
+    ),
+    (
+        "--qr TEXT|@FILE",
+        "Shows TEXT (or the content of FILE) as QR code",
+    ),
+    ("--show-elapsed", "Shows how long ago the window appeared"),
+    (
+        "--image FILE",
+        "Shows an image or animation below the message",
+    ),
+    ("--footer TEXT", "Shows TEXT in a small line at the bottom"),
+    (
+        "--title TEXT",
+        "Default: options-window-gtk. Title of the window for taskbars and window rules",
+    ),
+    (
+        "--class NAME",
+        "Sets WM_CLASS on X11 and the app_id on Wayland to NAME for window rules",
+    ),
+    ("--padding PX", "Default: 0. Padding around each button"),
+    ("--spacing PX", "Default: 5. Space between widgets"),
+    (
+        "--icon-size SIZE",
+        "Default: dialog. Size of the message icon, a GTK icon size like button or pixels",
+    ),
+    (
+        "--button-icon-size SIZE",
+        "Default: button. Size of the button icons, a GTK icon size or pixels",
+    ),
+    (
+        "--border-width PX",
+        "Default: 10. Border around the window content",
+    ),
+    ("--button-min-width PX", "Minimum width of every button"),
+    (
+        "--width PX",
+        "Minimum width of the window, the message wraps instead of widening it",
+    ),
+    ("--height PX", "Minimum height of the window"),
+    (
+        "--position WHERE",
+        "Default: center. top, bottom, left, right, a corner like top-left or X,Y",
+    ),
+    (
+        "--offset X,Y",
+        "Moves the dialog away from the edge of --position by X and Y pixels",
+    ),
+    (
+        "--no-keep-above",
+        "Lets other windows cover the dialog like any other window",
+    ),
+    (
+        "--no-sticky",
+        "Shows the dialog only on the current workspace instead of all of them",
+    ),
+    (
+        "--no-urgent",
+        "Doesn't set the urgency hint, so taskbars don't flash",
+    ),
+    (
+        "--headerbar",
+        "Puts the title and a close button into a header bar like GNOME dialogs",
+    ),
+    (
+        "--attach XID",
+        "Keeps the dialog above the X11 window XID, e.g. from xdotool getactivewindow",
+    ),
+    (
+        "--focus LABEL",
+        "Button which gets the initial focus, or cancel",
+    ),
+    (
+        "--default BUTTON",
+        "Button (label or number) activated by Enter instead of cancel, focused unless --focus",
+    ),
+    (
+        "--focus-order ORDER",
+        "Tab order, e.g. 2,1,cancel (numbers or labels), unlisted buttons follow",
+    ),
+    (
+        "-m, --message MSG",
+        "Sets the window caption, repeat it for more paragraphs, - reads it from stdin",
+    ),
+    (
+        "--message-file FILE",
+        "Reads a paragraph of the caption from FILE, long text scrolls",
+    ),
+    ("--align left|center|right", "Aligns the message, GTK decides if not given"),
+    (
+        "-t, --type info|warning|error",
+        "Default: error. Defines the window icon and accent",
+    ),
+    (
+        "--css FILE",
+        "Styles the dialog with FILE, default: ~/.config/options-window-gtk/style.css",
+    ),
+    (
+        "-f, --font FONT",
+        "Pango font of the dialog, e.g. 'pango:DejaVu Sans 10'",
+    ),
+    (
+        "--text-scale FACTOR",
+        "Scales the message text, e.g. 1.5 for half again as large",
+    ),
+    (
+        "--screenshot FILE",
+        "Saves the window as PNG to FILE and exits",
+    ),
+    ("--dump-ui", "Prints the widget hierarchy and exits"),
+    (
+        "--preview-types",
+        "Shows sample dialogs of every type and layout at once",
+    ),
+    (
+        "--instance NAME",
+        "Name sent with the ButtonActivated D-Bus signal",
+    ),
+    (
+        "--dedup KEY",
+        "Exits right away while another dialog with the same KEY is shown",
+    ),
+    (
+        "--polkit-agent",
+        "Runs as PolicyKit authentication agent, asking for passwords with this dialog",
+    ),
+    (
+        "--serve tcp:[HOST:]PORT|SOCKET",
+        "Shows dialogs requested as JSON specs, tcp:PORT only on localhost. Unauthenticated",
+    ),
+    (
+        "--diagnose",
+        "Prints which terminal, display etc. would be used and exits",
+    ),
+    (
+        "--dump-config",
+        "Prints an annotated spec with all defaults and exits",
+    ),
+    (
+        "--gtk-backend broadway|offscreen",
+        "Renders through broadway, or offscreen for --screenshot and --dump-ui",
+    ),
+    (
+        "--backend x11|wayland",
+        "Forces the display backend, e.g. x11 to run under XWayland",
+    ),
+    (
+        "--markup",
+        "The message is Pango markup, e.g. <b>bold</b> or <tt>monospace</tt>",
+    ),
+    (
+        "--bar",
+        "Docks the dialog as a strip to the top of the screen like i3-nagbar",
+    ),
+    (
+        "--gtk-theme NAME",
+        "Uses the gtk theme NAME for this window",
+    ),
+    (
+        "--icon-theme NAME",
+        "Uses the icon theme NAME for this window",
+    ),
+    (
+        "--dark, --light",
+        "Prefers the dark (or light) variant of the gtk theme",
+    ),
+    (
+        "--terminal CMD",
+        "Terminal for -b buttons. Default: $TERMINAL, then i3-sensible-terminal, then x-terminal-emulator",
+    ),
+    (
+        "--terminal-cmd TEMPLATE",
+        "Starts -b buttons with TEMPLATE, e.g. 'foot -e {}', {} is the command",
+    ),
+    (
+        "--terminal-mode auto|spawn|remote",
+        "Default: auto. remote opens -b buttons through kitty @ launch or footclient",
+    ),
+    ("--replay FILE", "Plays back the interaction steps in FILE"),
+    (
+        "-q, --quiet",
+        "Only the result is written to stdout, no warnings",
+    ),
+    (
+        "--exit-after-action",
+        "Program exits after a button press with 0 (or its --code), 1 on Cancel",
+    ),
+    (
+        "--cancel-label TEXT",
+        "Label of the Cancel button, e.g. _Ignore",
+    ),
+    (
+        "--cancel-action CMD",
+        "Runs CMD in a shell when Cancel is pressed, before the dialog closes",
+    ),
+    (
+        "--layout horizontal|vertical",
+        "Default: vertical. Puts the buttons below or next to each other",
+    ),
+    (
+        "--columns N",
+        "Arranges the buttons in a grid with N columns",
+    ),
+    (
+        "--no-cancel",
+        "Leaves out the Cancel button, the buttons close the window unless --keep-open",
+    ),
+    (
+        "--queue",
+        "Runs actions one after another instead of all at once",
+    ),
+    (
+        "--show-status",
+        "Shows the result of the last run next to each button",
+    ),
+    (
+        "--notify-result",
+        "Sends a notification with the result when an action ends",
+    ),
+    (
+        "--print-pid FILE",
+        "Appends PID, process group and label of each action to FILE, - is stdout",
+    ),
+    (
+        "--delay SECONDS",
+        "Waits SECONDS before the window is shown",
+    ),
+    (
+        "--in DURATION",
+        "Like --delay, with a duration like 90s, 15m or 1h30m",
+    ),
+    (
+        "--at HH:MM",
+        "Waits until the next time the clock shows HH:MM",
+    ),
+    (
+        "--cancel-if CMD",
+        "Exits during --delay as soon as CMD succeeds",
+    ),
+    (
+        "--close-when CMD",
+        "Runs CMD while the dialog is shown and closes it once CMD succeeds",
+    ),
+    (
+        "--poll-interval SECS",
+        "Default: 5. How often --close-when runs its command",
+    ),
+    (
+        "--if-failed CMD, --if-succeeds CMD",
+        "Only shows the dialog if CMD fails (or succeeds)",
+    ),
+    (
+        "--strict",
+        "Unknown icons, bad labels etc. are errors (exit 10-13)",
+    ),
+    (
+        "--chain FILE",
+        "Shows the spec in FILE with the pressed button as {result}",
+    ),
+    (
+        "--no-config",
+        "Skips the config files in /etc and the XDG config directories",
+    ),
+    (
+        "--hot-reload",
+        "Rebuilds the dialog when a spec loaded with -c changes",
+    ),
+    (
+        "--wizard FILE",
+        "Shows the pages in FILE with Back/Next and prints all answers as JSON",
+    ),
+    (
+        "--survey FILE",
+        "Asks the JSON list of questions in FILE (- is stdin) and prints the answers",
+    ),
+    (
+        "--print label|index|value",
+        "Prints the pressed button or only the value of --pin or a prompt, cancel exits with 1",
+    ),
+    (
+        "--pipe-to CMD",
+        "Writes the pressed button's label (and PIN) to CMD's stdin",
+    ),
+    (
+        "--audit-log FILE",
+        "Appends shown dialogs and pressed buttons to FILE",
+    ),
+    (
+        "-c, --config FILE",
+        "Reads options from a TOML, JSON or YAML dialog spec",
+    ),
+];
+
+// The names in OPTION_HELP, e.g. -b and --button for "-b, --button LABEL ACTION [ICON]".
+fn option_names() -> impl Iterator<Item = &'static str> {
+    OPTION_HELP
+        .iter()
+        .flat_map(|(option, _)| option.split(", ").map(|o| o.split(' ').next().unwrap_or(o)))
+}
+
+const COMMAND_HELP: &[(&str, &str)] = &[
+    (
+        "check [--strict] CONFIG",
+        "Validates a dialog spec without showing it",
+    ),
+    (
+        "lint CONFIG",
+        "Suggests fixes for style issues of a dialog spec",
+    ),
+    ("gen-man", "Prints the man page in roff format"),
+];
+
+fn show_version() {
+    println!("{} {}", PROGRAM_NAME, VERSION);
+}
+
+fn usage_short() {
+    println!("Usage: {} [-h] [-v] [-b label action [icon]]... [-B label action [icon]]... [-o label uri [icon]]... [--pin [length]] [-t info|warning|error] [-m message] [-f font]", PROGRAM_NAME);
+}
+
+fn usage_long() {
+    println!("Usage:");
+    println!("  {} [OPTION]...", PROGRAM_NAME);
+    for (command, _) in COMMAND_HELP {
+        println!("  {} {}", PROGRAM_NAME, command);
+    }
+    println!();
+    println!("Options:");
+    for (option, description) in OPTION_HELP {
+        println!("  {:<47}{}", option, description);
+    }
+    println!();
+    println!("Commands:");
+    for (command, description) in COMMAND_HELP {
+        println!("  {:<47}{}", command, description);
+    }
+}
+
+fn show_error(error: ParseError) {
+    println!("Error while parsing command line: {}", error);
+}
+
+fn show_help() {
+    show_version();
+    usage_long();
+}
+
+/* Runs the command to its end, for probes like --cancel-if. Only the exit
+ * status counts, stdout is left to the result of the dialog.
+ */
+fn succeeds(command: &Command) -> std::io::Result<bool> {
+    (command.exec)(command)
+        .and_then(|mut process| process.stdout(std::process::Stdio::null()).status())
+        .map(|status| status.success())
+}
+
+// Whether the --cancel-if command succeeded, one which can't be run doesn't cancel.
+fn cancel_requested(cancel_if: &Option<Command>) -> bool {
+    match cancel_if.as_ref().map(succeeds) {
+        Some(Ok(succeeded)) => succeeded,
+        Some(Err(e)) => {
+            warning!("Couldn't run --cancel-if command: {}", e);
+            false
+        }
+        None => false,
+    }
+}
+
+/* --delay, --in and --at: a GLib timer checks every second whether the wall
+ * clock reached the deadline yet, so a suspend meanwhile doesn't push the
+ * dialog back like counting down seconds would. Returns false if the
+ * --cancel-if command succeeded.
+ */
+fn wait_for(secs: u32, cancel_if: &Option<Command>) -> bool {
+    if secs == 0 {
+        return true;
+    }
+    if cancel_requested(cancel_if) {
+        return false;
+    }
+    let wait = std::time::Duration::from_secs(u64::from(secs));
+    let deadline = std::time::SystemTime::now() + wait;
+    let main_loop = glib::MainLoop::new(None, false);
+    let cancelled = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let quit = main_loop.clone();
+    let cancel_if = cancel_if.clone();
+    let cancelled_clone = cancelled.clone();
+    glib::timeout_add_seconds(1, move || {
+        if cancel_requested(&cancel_if) {
+            cancelled_clone.store(true, std::sync::atomic::Ordering::Relaxed);
+        } else if std::time::SystemTime::now() < deadline {
+            return glib::Continue(true);
+        }
+        quit.quit();
+        glib::Continue(false)
+    });
+    main_loop.run();
+    !cancelled.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/* --close-when: runs the command every interval in a thread of its own, so a
+ * slow one doesn't freeze the dialog, and quits once it succeeded.
+ */
+fn close_when(command: Command, interval: u32) {
+    let (succeeded, received) = std::sync::mpsc::channel();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(std::time::Duration::from_secs(u64::from(interval)));
+        match succeeds(&command) {
+            Ok(true) => {
+                let _ = succeeded.send(());
+                return;
+            }
+            Ok(false) => {}
+            Err(e) => warning!("Couldn't run --close-when command: {}", e),
+        }
+    });
+    gtk::timeout_add_seconds(1, move || match received.try_recv() {
+        Ok(()) => {
+            gtk::main_quit();
+            gtk::Continue(false)
+        }
+        Err(std::sync::mpsc::TryRecvError::Empty) => gtk::Continue(true),
+        Err(std::sync::mpsc::TryRecvError::Disconnected) => gtk::Continue(false),
+    });
+}
+
+// --in: plain seconds or a combination of h, m and s like 1h30m
+fn parse_duration(text: &str) -> Option<u32> {
+    if let Ok(secs) = text.parse::<u32>() {
+        return Some(secs);
+    }
+    let mut total: u32 = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let value: u32 = number.parse().ok()?;
+        total = total.checked_add(value.checked_mul(unit)?)?;
+        number.clear();
+    }
+    if number.is_empty() && !text.is_empty() {
+        Some(total)
+    } else {
+        None
+    }
+}
+
+// --at: "HH:MM" as hour and minute
+#[cfg(unix)]
+fn parse_time_of_day(text: &str) -> Option<(u32, u32)> {
+    let (hour_str, minute_str) = text.split_once(':')?;
+    let hour: u32 = hour_str.parse().ok()?;
+    let minute: u32 = minute_str.parse().ok()?;
+    if hour < 24 && minute < 60 && minute_str.len() == 2 {
+        Some((hour, minute))
+    } else {
+        None
+    }
+}
+
+// Seconds since local midnight
+#[cfg(unix)]
+fn now_of_day() -> u32 {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&now, &mut tm) };
+    (tm.tm_hour * 3600 + tm.tm_min * 60 + tm.tm_sec) as u32
+}
+
+// Until the next time the clock shows `target`, today or tomorrow.
+#[cfg(unix)]
+fn seconds_until(target: u32, now: u32) -> u32 {
+    (target + 86400 - now) % 86400
+}
+
+/* --if-failed/--if-succeeds: the window is only shown if the probe says so.
+ * A probe which can't be run doesn't hide the window.
+ */
+fn probe_wants_window(probe: &Option<(Command, bool)>) -> bool {
+    match probe {
+        Some((command, show_on_success)) => match succeeds(command) {
+            Ok(succeeded) => succeeded == *show_on_success,
+            Err(e) => {
+                warning!("Couldn't run probe command: {}", e);
+                true
+            }
+        },
+        None => true,
+    }
+}
+
+fn handle_error(err: ParseError) -> i32 {
+    let mut exit_code = 0;
+    if err.error_type == ParseErrorType::HelpRequested {
+        show_help();
+    } else if err.error_type == ParseErrorType::VersionInfoRequested {
+        show_version();
+    } else if is_quiet() {
+        eprintln!("Error while parsing command line: {}", err);
+        exit_code = 1;
+    } else {
+        show_error(err);
+        usage_short();
+        exit_code = 1;
+    }
+    exit_code
+}
+
+fn main() {
+    let mut exit_code: i32 = 0;
+    let args = std::env::args_os().collect::<Vec<OsString>>();
+    // Checked before parsing, so parse errors are affected as well.
+    if args.iter().any(|a| a == "-q" || a == "--quiet") {
+        set_quiet();
+    }
+    if args.len() > 1 && args[1] == "check" {
+        exit_code = spec::run_check(&args[2..]);
+    } else if args.len() > 1 && args[1] == "lint" {
+        exit_code = lint::run_lint(&args[2..]);
+    } else if args.len() > 1 && args[1] == "gen-man" {
+        print!("{}", man::page());
+    } else if !terminal::is_script_link(&args[0]) {
+        let args = spec::with_default_configs(&args);
+        let result = Configuration::new(&args);
+        if result.is_ok() {
+            let mut config = result.unwrap();
+            if config.dump_config {
+                print!("{}", spec::dump_config());
+            } else {
+                if config.gtk_backend == Some(GtkBackend::Broadway) {
+                    std::env::set_var("GDK_BACKEND", "broadway");
+                }
+                if let Some(backend) = config.backend {
+                    std::env::set_var("GDK_BACKEND", backend.gdk_name());
+                }
+                terminal::set_mode(config.terminal_mode);
+                terminal::set_terminal(config.terminal.clone());
+                terminal::set_template(config.terminal_template.clone());
+                #[cfg(unix)]
+                {
+                    if config.print_pid.is_some() {
+                        OWN_PROCESS_GROUP.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }
+                }
+                #[cfg(all(feature = "terminal-exec", unix))]
+                {
+                    if let Err(e) = terminal::runtime_dir() {
+                        warning!("Couldn't create runtime directory: {}", e);
+                    }
+                }
+                if config.diagnose {
+                    print!("{}", diagnose::report(&config));
+                    std::process::exit(0);
+                }
+                #[cfg(unix)]
+                let _dedup_lock = match &config.dedup {
+                    Some(key) => match dedup::lock(key) {
+                        Ok(Some(lock)) => Some(lock),
+                        Ok(None) => std::process::exit(0),
+                        Err(e) => {
+                            warning!("Couldn't check for duplicate dialogs: {}", e);
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                #[cfg(unix)]
+                {
+                    if let Some((hour, minute)) = config.at {
+                        let secs = seconds_until(hour * 3600 + minute * 60, now_of_day());
+                        if !wait_for(secs, &config.cancel_if) {
+                            std::process::exit(0);
+                        }
+                    }
+                }
+                if let Some(secs) = config.delay {
+                    if !wait_for(secs, &config.cancel_if) {
+                        std::process::exit(0);
+                    }
+                }
+                if !probe_wants_window(&config.show_if) {
+                    std::process::exit(0);
+                }
+                // Becomes the app_id of the window on Wayland.
+                if let Some(class) = &config.class {
+                    glib::set_prgname(Some(class));
+                }
+                gtk::init().expect("Couldn't start gtk.");
+                apply_themes(&config);
+                if let Some(address) = &config.serve {
+                    std::process::exit(server::run(address));
+                }
+                #[cfg(all(feature = "dbus", unix))]
+                {
+                    if config.polkit_agent {
+                        std::process::exit(polkit::run_agent(&config));
+                    }
+                }
+                if config.preview_types {
+                    std::process::exit(preview::run(&config));
+                }
+                if let Some(path) = &config.wizard {
+                    std::process::exit(wizard::run(&config, path));
+                }
+                if let Some(path) = &config.survey {
+                    std::process::exit(survey::run(&config, path));
+                }
+                if config.strict {
+                    let problems = strict::problems(&config, &gtk::IconTheme::get_default());
+                    if !problems.is_empty() {
+                        std::process::exit(strict::report(&problems));
+                    }
+                }
+                let chained = chain::follow(&mut config);
+                let print_exit_code = print_choice(&mut config);
+                #[cfg_attr(not(feature = "scripting"), allow(unused_variables))]
+                let (window, gtk_buttons) = create_gtk_dialog(&config);
+                *chained.borrow_mut() = Some(window.clone());
+                if config.dump_ui {
+                    print!("{}", dump::widget_tree(window.upcast_ref()));
+                } else if let Some(path) = &config.screenshot {
+                    window.show_all();
+                    exit_code = save_screenshot(&window, path);
+                } else {
+                    window.show_all();
+                    if config.hot_reload {
+                        reload::watch(&args, &window);
+                    }
+                    if let Some(command) = &config.close_when {
+                        close_when(command.clone(), config.poll_interval.unwrap_or(5));
+                    }
+                    #[cfg(feature = "scripting")]
+                    {
+                        if !config.replay.is_empty() {
+                            replay::run(config.replay.clone(), &window, &gtk_buttons);
+                        }
+                    }
+                    if let Some(log) = &config.audit_log {
+                        log.dialog_shown(config.message_type.name(), &config.message);
+                    }
+                    gtk::main();
+                    if config.print.is_some() {
+                        exit_code = print_exit_code.get();
+                    } else if config.buttons.iter().any(|b| config.exits_after(b)) {
+                        exit_code = ACTION_EXIT_CODE.load(std::sync::atomic::Ordering::Relaxed);
+                    }
+                    #[cfg(all(feature = "dbus", unix))]
+                    signal::emit(&config.instance);
+                    window.destroy();
+                    while gtk::events_pending() {
+                        gtk::main_iteration();
+                    }
+                    wait_for_actions();
+                }
+            }
+        } else {
+            let err = result.err().unwrap();
+            exit_code = handle_error(err);
+        }
+    } else {
+        terminal::run_link(&args[0]);
+    }
+    std::process::exit(exit_code);
 }
 
-fn show_error(error: ParseError) {
-    println!("Error while parsing command line: {}", error);
-}
+#[cfg(test)]
+mod tests {
+    use crate::{
+        choice_text, create_gtk_dialog, dump, font_css, format_elapsed, is_activate_key,
+        is_icon_file, is_long_message, is_option, message_text, position, prompt, target_uri,
+        Action, ButtonLayout, ButtonStyle, Configuration, DisplayBackend, FocusTarget, IconLayout,
+        IconSize, PrintMode, SecretDelivery,
+    };
+    use gtk::prelude::*;
+    use std::ffi::{OsStr, OsString};
+
+    fn o(s: &str) -> OsString {
+        OsString::from(s)
+    }
+    #[test]
+    #[cfg(feature = "terminal-exec")]
+    fn button_order() {
+        let args = vec![
+            o("app"),
+            o("-b"),
+            o("1.1"),
+            o("1.2"),
+            o("-B"),
+            o("2.1"),
+            o("2.2"),
+            o("-b"),
+            o("3.1"),
+            o("3.2"),
+            o("-B"),
+            o("4.1"),
+            o("4.2"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        let mut i = 1;
+        for button in config.buttons {
+            let label = format!("{}.1", i);
+            assert_eq!(label, button.label);
+            i += 1;
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "terminal-exec")]
+    fn require_typing() {
+        let args = vec![
+            o("app"),
+            o("-b"),
+            o("Wipe"),
+            o("wipe.sh"),
+            o("--require-typing"),
+            o("host1"),
+            o("-B"),
+            o("Log"),
+            o("log.sh"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(Some("host1".to_string()), config.buttons[0].require_typing);
+        assert_eq!(None, config.buttons[1].require_typing);
+        assert!(Configuration::new(&[o("app"), o("--require-typing"), o("x")]).is_err());
+    }
+
+    #[test]
+    fn pin_length_is_optional() {
+        let config = Configuration::new(&[o("app"), o("--pin"), o("4")]).unwrap();
+        assert!(config.pin);
+        assert_eq!(Some(4), config.pin_length);
+        let config = Configuration::new(&[o("app"), o("--pin"), o("-m"), o("x")]).unwrap();
+        assert!(config.pin);
+        assert_eq!(None, config.pin_length);
+        assert_eq!("x", config.message);
+        assert!(Configuration::new(&[o("app"), o("--pin"), o("0")]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "terminal-exec")]
+    fn pin_not_for_terminal_buttons() {
+        let args = vec![o("app"), o("--pin"), o("-b"), o("Unlock"), o("unlock-disk")];
+        assert!(Configuration::new(&args).is_err());
+        let args = vec![
+            o("app"),
+            o("--password"),
+            o("-b"),
+            o("Unlock"),
+            o("unlock-disk"),
+        ];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn elapsed_format() {
+        assert_eq!("shown just now", format_elapsed(59));
+        assert_eq!("shown 3m ago", format_elapsed(200));
+        assert_eq!("shown 2h 5m ago", format_elapsed(7500));
+        assert_eq!("shown 1d 1h ago", format_elapsed(90000));
+    }
+
+    #[test]
+    fn option_arguments() {
+        assert!(is_option("-m"));
+        assert!(is_option("--message"));
+        assert!(!is_option("-"));
+        assert!(!is_option("-1"));
+        assert!(!is_option("--"));
+        assert!(!is_option("volume"));
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn http_button() {
+        let args = vec![
+            o("app"),
+            o("--button-http"),
+            o("_Darker"),
+            o("POST"),
+            o("http://localhost/brightness"),
+            o("-10"),
+            o("-m"),
+            o("Brightness"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        match &config.buttons[0].action {
+            crate::Action::Http(request) => assert_eq!(Some(String::from("-10")), request.body),
+            _ => panic!("No HTTP button"),
+        }
+        assert_eq!("Brightness", config.message);
+    }
+
+    #[test]
+    fn spacing_options() {
+        let args = vec![
+            o("app"),
+            o("--spacing"),
+            o("2"),
+            o("--border-width"),
+            o("0"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(2, config.spacing);
+        assert_eq!(0, config.border_width);
+        assert_eq!(0, config.padding);
+        assert!(Configuration::new(&[o("app"), o("--padding"), o("-1")]).is_err());
+        assert!(Configuration::new(&[o("app"), o("--padding")]).is_err());
+        assert!(Configuration::new(&[o("app"), o("--spacing"), o("3000000000")]).is_err());
+    }
+
+    #[test]
+    fn message_paragraphs() {
+        let args = vec![o("app"), o("-m"), o("First"), o("--message"), o("Second")];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!("First\n\nSecond", config.message);
+        let config = Configuration::new(&[o("app"), o("-m"), o("Only")]).unwrap();
+        assert_eq!("Only", config.message);
+    }
+
+    #[test]
+    fn message_layout() {
+        let config = Configuration::new(&[o("app"), o("--align"), o("Right")]).unwrap();
+        assert_eq!(Some(gtk::Justification::Right), config.align);
+        assert!(Configuration::new(&[o("app"), o("--align"), o("top")]).is_err());
+        let config = Configuration::new(&[o("app"), o("--text-scale"), o("2.5")]).unwrap();
+        assert_eq!(Some(2.5), config.text_scale);
+        assert!(Configuration::new(&[o("app"), o("--text-scale"), o("0")]).is_err());
+    }
+
+    #[test]
+    fn display_backend() {
+        let config = Configuration::new(&[o("app"), o("--backend"), o("X11")]).unwrap();
+        assert_eq!(Some(DisplayBackend::X11), config.backend);
+        assert!(Configuration::new(&[o("app"), o("--backend"), o("mir")]).is_err());
+        let args = vec![
+            o("app"),
+            o("--backend"),
+            o("wayland"),
+            o("--gtk-backend"),
+            o("broadway"),
+        ];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "terminal-exec")]
+    fn terminal_mode() {
+        let config = Configuration::new(&[o("app"), o("--terminal-mode"), o("remote")]).unwrap();
+        assert_eq!(crate::terminal::TerminalMode::Remote, config.terminal_mode);
+        assert!(Configuration::new(&[o("app"), o("--terminal-mode"), o("tab")]).is_err());
+        let config = Configuration::new(&[o("app"), o("--terminal"), o("foot")]).unwrap();
+        assert_eq!(Some(o("foot")), config.terminal);
+        let args = vec![o("app"), o("--terminal-cmd"), o("foot -e {}")];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(Some(String::from("foot -e {}")), config.terminal_template);
+        assert!(Configuration::new(&[o("app"), o("--terminal-cmd"), o(" ")]).is_err());
+    }
+
+    #[test]
+    fn theme_overrides() {
+        let args = vec![
+            o("app"),
+            o("--gtk-theme"),
+            o("Adwaita-dark"),
+            o("--icon-theme"),
+            o("Papirus"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(Some(String::from("Adwaita-dark")), config.gtk_theme);
+        assert_eq!(Some(String::from("Papirus")), config.icon_theme);
+        assert!(Configuration::new(&[o("app"), o("--icon-theme")]).is_err());
+        let config = Configuration::new(&[o("app"), o("--dark"), o("--light")]).unwrap();
+        assert_eq!(Some(false), config.prefer_dark);
+    }
+
+    #[test]
+    fn secret_delivery() {
+        let config = Configuration::new(&[o("app"), o("--secret-via"), o("env:PIN")]).unwrap();
+        assert_eq!(
+            SecretDelivery::Env(String::from("PIN")),
+            config.secret_delivery
+        );
+        assert!(Configuration::new(&[o("app"), o("--secret-via"), o("env:")]).is_err());
+        assert!(Configuration::new(&[o("app"), o("--secret-via"), o("argv")]).is_err());
+    }
+
+    #[test]
+    fn piped_choice() {
+        assert_eq!("Reboot\n", choice_text("_Reboot", None));
+        assert_eq!("Save_as\n1234\n", choice_text("_Save__as", Some("1234")));
+        let config = Configuration::new(&[o("app"), o("--pipe-to"), o("wl-copy")]).unwrap();
+        assert!(config.pipe_to.is_some());
+    }
+
+    #[test]
+    fn mnemonics() {
+        assert_eq!(Some('s'), crate::mnemonic("_Save__as"));
+        assert_eq!(Some('a'), crate::mnemonic("Save__as _As"));
+        assert_eq!(None, crate::mnemonic("Save__as"));
+        assert_eq!("Save_as As", crate::strip_mnemonics("Save__as _As"));
+    }
+
+    #[test]
+    fn exit_codes() {
+        let args = vec![
+            o("app"),
+            o("--exit-after-action"),
+            o("-B"),
+            o("_Yes"),
+            o("true"),
+            o("--code"),
+            o("0"),
+            o("-B"),
+            o("_Maybe"),
+            o("true"),
+            o("--code"),
+            o("42"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(Some(0), config.buttons[0].exit_code);
+        assert_eq!(Some(42), config.buttons[1].exit_code);
+        let args = vec![o("app"), o("-B"), o("_Yes"), o("true"), o("--code"), o("1")];
+        assert!(Configuration::new(&args).is_err());
+        let args = vec![
+            o("app"),
+            o("--exit-after-action"),
+            o("-B"),
+            o("_Yes"),
+            o("true"),
+            o("--code"),
+            o("256"),
+        ];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn tooltips() {
+        let args = vec![
+            o("app"),
+            o("-B"),
+            o("_Sync"),
+            o("rsync -a ~/ backup:"),
+            o("--tooltip"),
+            o("Copies the home directory to the backup host"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(
+            Some("Copies the home directory to the backup host"),
+            config.buttons[0].tooltip.as_deref()
+        );
+        assert!(Configuration::new(&[o("app"), o("--tooltip"), o("Text")]).is_err());
+    }
+
+    #[test]
+    fn button_styles() {
+        let args = vec![
+            o("app"),
+            o("-B"),
+            o("_Delete all data"),
+            o("rm -rf ~/.cache/app"),
+            o("--style"),
+            o("destructive"),
+            o("-B"),
+            o("_Show log"),
+            o("less ~/.cache/app/log"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(ButtonStyle::Destructive, config.buttons[0].style);
+        assert_eq!(ButtonStyle::Normal, config.buttons[1].style);
+        let args = vec![
+            o("app"),
+            o("-B"),
+            o("_Ok"),
+            o("true"),
+            o("--style"),
+            o("red"),
+        ];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn confirm_question() {
+        let args = vec![
+            o("app"),
+            o("-B"),
+            o("_Reboot"),
+            o("systemctl reboot"),
+            o("--confirm"),
+            o("Are you sure?"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(Some("Are you sure?"), config.buttons[0].confirm.as_deref());
+        let args = vec![
+            o("app"),
+            o("-B"),
+            o("_Reboot"),
+            o("systemctl reboot"),
+            o("--confirm"),
+        ];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn secondary_action() {
+        let secondary = |gate: &[&str]| {
+            let mut args = vec![o("app"), o("-B"), o("_Reboot"), o("systemctl reboot")];
+            args.extend(gate.iter().map(|a| o(a)));
+            args.extend(vec![o("--secondary"), o("_Logs"), o("journalctl -b")]);
+            Configuration::new(&args)
+        };
+        let config = secondary(&["--confirm", "Sure?"]).unwrap();
+        assert_eq!(
+            Some(String::from("_Logs")),
+            config.buttons[0].secondary.as_ref().map(|s| s.0.clone())
+        );
+        assert!(secondary(&["--hold-to-confirm", "800"]).is_err());
+    }
+
+    #[test]
+    fn open_buttons() {
+        let args = vec![
+            o("app"),
+            o("-o"),
+            o("_Docs"),
+            o("https://example.org/docs?page=2"),
+            o("--secondary"),
+            o("_Notes"),
+            o("notes.txt"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        match &config.buttons[0].action {
+            Action::Open(target) => assert_eq!("https://example.org/docs?page=2", target),
+            _ => panic!("-o has to open its URI"),
+        }
+        match &config.buttons[0].secondary {
+            Some((_, Action::Open(target))) => assert_eq!("notes.txt", target),
+            _ => panic!("the secondary action of -o has to open as well"),
+        }
+        let uri = target_uri(OsStr::new("https://example.org/docs?page=2"));
+        assert_eq!("https://example.org/docs?page=2", uri);
+        let uri = target_uri(OsStr::new("/tmp/Q3 report.pdf"));
+        assert_eq!("file:///tmp/Q3%20report.pdf", uri);
+        let uri = target_uri(OsStr::new("notes.txt"));
+        assert!(
+            uri.starts_with("file:///") && uri.ends_with("/notes.txt"),
+            "{}",
+            uri
+        );
+    }
+
+    #[test]
+    fn hold_keys() {
+        assert!(is_activate_key(gdk::enums::key::Return));
+        assert!(is_activate_key(gdk::enums::key::space));
+        assert!(!is_activate_key(gdk::enums::key::Escape));
+        assert!(!is_activate_key(gdk::enums::key::Tab));
+    }
+
+    #[test]
+    fn icon_files() {
+        assert!(is_icon_file("/usr/share/pixmaps/backup.png"));
+        assert!(is_icon_file("icons/backup"));
+        assert!(is_icon_file("Backup.SVG"));
+        assert!(!is_icon_file("system-reboot"));
+        assert!(!is_icon_file("org.gnome.Settings"));
+    }
+
+    #[test]
+    fn icon_sizes() {
+        let args = vec![
+            o("app"),
+            o("--icon-size"),
+            o("DND"),
+            o("--button-icon-size"),
+            o("24"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(IconSize::Named(5), config.icon_size);
+        assert_eq!(IconSize::Pixels(24), config.button_icon_size);
+        assert_eq!(32, config.icon_size.pixels());
+        assert!(Configuration::new(&[o("app"), o("--icon-size"), o("huge")]).is_err());
+        assert!(Configuration::new(&[o("app"), o("--icon-size"), o("0")]).is_err());
+    }
+
+    #[test]
+    fn exit_after_override() {
+        let args = vec![
+            o("app"),
+            o("--exit-after-action"),
+            o("-B"),
+            o("_Toggle"),
+            o("true"),
+            o("--keep-open"),
+            o("-B"),
+            o("_Run"),
+            o("true"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert!(!config.exits_after(&config.buttons[0]));
+        assert!(config.exits_after(&config.buttons[1]));
+        let args = vec![
+            o("app"),
+            o("-B"),
+            o("_Yes"),
+            o("true"),
+            o("--close"),
+            o("--code"),
+            o("3"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert!(config.exits_after(&config.buttons[0]));
+        assert!(Configuration::new(&[o("app"), o("--close")]).is_err());
+    }
+
+    #[test]
+    fn message_from_output() {
+        assert_eq!(
+            "make: *** [all] Error 2",
+            message_text(b"make: *** [all] Error 2\n")
+        );
+        assert_eq!("line 1\n\nline 3", message_text(b"line 1\n\nline 3\r\n\n"));
+        assert_eq!("caf\u{fffd}", message_text(b"caf\xe9"));
+    }
 
-fn show_help() {
-    show_version();
-    usage_long();
-}
+    #[test]
+    fn message_file() {
+        let path = std::env::temp_dir().join(format!("message-{}.txt", std::process::id()));
+        std::fs::write(&path, "Parse error in line 3\n").unwrap();
+        let args = vec![
+            o("app"),
+            o("-m"),
+            o("Config broken:"),
+            o("--message-file"),
+            path.clone().into(),
+        ];
+        let result = Configuration::new(&args);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            "Config broken:\n\nParse error in line 3",
+            result.unwrap().message
+        );
+        let args = vec![o("app"), o("--message-file"), path.into()];
+        assert!(Configuration::new(&args).is_err());
+        assert!(!is_long_message("short"));
+        assert!(is_long_message(&"line\n".repeat(30)));
+    }
 
-fn run_script(cmd: &OsString) {
-    let mut script = OsString::from_vec(cmd.as_bytes()[..cmd.len() - 3].to_vec());
-    script.push("sh");
-    std::process::Command::new("/bin/sh")
-        .arg(script)
-        .spawn()
-        .expect("Couldn't spawn child process.")
-        .wait()
-        .expect("Error during childs execute.");
-}
+    #[test]
+    fn style_sheet() {
+        let path = std::env::temp_dir().join(format!("style-{}.css", std::process::id()));
+        std::fs::write(&path, "label { color: red; }").unwrap();
+        let result = Configuration::new(&[o("app"), o("--css"), path.clone().into()]);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(Some(path.clone()), result.unwrap().css);
+        assert!(Configuration::new(&[o("app"), o("--css"), path.into()]).is_err());
+    }
 
-fn handle_error(err: ParseError) -> i32 {
-    let mut exit_code = 0;
-    if err.error_type == ParseErrorType::HelpRequested {
-        show_help();
-    } else if err.error_type == ParseErrorType::VersionInfoRequested {
-        show_version();
-    } else {
-        show_error(err);
-        usage_short();
-        exit_code = 1;
+    #[test]
+    fn fonts() {
+        let args = vec![o("app"), o("-f"), o("pango:DejaVu Sans Mono 10")];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(Some(String::from("DejaVu Sans Mono 10")), config.font);
+        let args = vec![
+            o("app"),
+            o("-f"),
+            o("-misc-fixed-medium-r-normal--13-120-75-75-C-70-iso10646-1"),
+        ];
+        assert_eq!(None, Configuration::new(&args).unwrap().font);
+        assert_eq!(
+            "* { font-family: \"DejaVu Sans\"; font-size: 10pt; font-weight: 700; }",
+            font_css("DejaVu Sans Bold 10")
+        );
+        assert_eq!("* { font-style: italic; }", font_css("Italic"));
     }
-    exit_code
-}
 
-fn main() {
-    let mut exit_code: i32 = 0;
-    let args = std::env::args_os().collect::<Vec<OsString>>();
-    if !args[0].to_string_lossy().ends_with(".cmd") {
-        let result = Configuration::new(&args);
-        if result.is_ok() {
-            let config = result.unwrap();
-            gtk::init().expect("Couldn't start gtk.");
-            let (gtk_buttons, default) = create_gtk_buttons(&config);
-            let gtk_message = create_gtk_message(&config);
-            let window = create_gtk_window(&gtk_buttons, &default, &gtk_message);
-            window.show_all();
-            gtk::main();
-        } else {
-            let err = result.err().unwrap();
-            exit_code = handle_error(err);
-        }
-    } else {
-        match std::fs::remove_file(&args[0]) {
-            Ok(_) => {}
-            Err(e) => println!("Couldn't delete link {}\n{}", &args[0].to_string_lossy(), e),
+    #[test]
+    fn default_button() {
+        let args = vec![
+            o("app"),
+            o("-B"),
+            o("_Later"),
+            o("true"),
+            o("-B"),
+            o("_Reboot now"),
+            o("reboot"),
+            o("--default"),
+            o("2"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(Some(FocusTarget::Button(1)), config.default_button);
+        let args = vec![o("app"), o("--default"), o("Missing")];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn cancel_button() {
+        let args = vec![
+            o("app"),
+            o("--cancel-label"),
+            o("_Ignore"),
+            o("--cancel-action"),
+            o("logger dismissed"),
+            o("--focus"),
+            o("Ignore"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!("_Ignore", config.cancel_label);
+        assert!(config.cancel_action.is_some());
+        assert_eq!(Some(FocusTarget::Cancel), config.focus);
+        assert!(Configuration::new(&[o("app"), o("--cancel-action")]).is_err());
+    }
+
+    #[test]
+    fn button_layout() {
+        let config = Configuration::new(&[o("app"), o("--layout"), o("horizontal")]).unwrap();
+        assert_eq!(ButtonLayout::Horizontal, config.layout);
+        assert!(Configuration::new(&[o("app"), o("--layout"), o("grid")]).is_err());
+    }
+
+    #[test]
+    fn columns() {
+        let config = Configuration::new(&[o("app"), o("--columns"), o("3")]).unwrap();
+        assert_eq!(Some(3), config.columns);
+        assert!(Configuration::new(&[o("app"), o("--columns"), o("0")]).is_err());
+        assert!(Configuration::new(&[
+            o("app"),
+            o("--columns"),
+            o("2"),
+            o("--layout"),
+            o("horizontal")
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn no_cancel() {
+        let args = vec![
+            o("app"),
+            o("--no-cancel"),
+            o("-B"),
+            o("_Understood"),
+            o("true"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert!(config.no_cancel);
+        assert!(config.exits_after(&config.buttons[0]));
+        assert!(Configuration::new(&[o("app"), o("--no-cancel")]).is_err());
+        let args = vec![
+            o("app"),
+            o("--no-cancel"),
+            o("-B"),
+            o("_Understood"),
+            o("true"),
+            o("--keep-open"),
+        ];
+        assert!(Configuration::new(&args).is_err());
+        let args = vec![
+            o("app"),
+            o("--no-cancel"),
+            o("-B"),
+            o("_Understood"),
+            o("true"),
+            o("--focus"),
+            o("cancel"),
+        ];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn markup() {
+        let args = vec![o("app"), o("--markup"), o("-m"), o("<b>Disk</b> full")];
+        assert!(Configuration::new(&args).unwrap().markup);
+        let args = vec![o("app"), o("--markup"), o("-m"), o("<b>Disk full")];
+        assert!(Configuration::new(&args).is_err());
+        let args = vec![o("app"), o("-m"), o("<b>Disk full")];
+        assert!(!Configuration::new(&args).unwrap().markup);
+    }
+
+    #[test]
+    fn window_title() {
+        assert_eq!(None, Configuration::new(&[o("app")]).unwrap().title);
+        let args = vec![o("app"), o("--title"), o("Backup")];
+        assert_eq!(
+            Some("Backup"),
+            Configuration::new(&args).unwrap().title.as_deref()
+        );
+        assert!(Configuration::new(&[o("app"), o("--title")]).is_err());
+    }
+
+    #[test]
+    fn window_class() {
+        let args = vec![o("app"), o("--class"), o("my-nag")];
+        assert_eq!(
+            Some("my-nag"),
+            Configuration::new(&args).unwrap().class.as_deref()
+        );
+        assert!(Configuration::new(&[o("app"), o("--class"), o("my nag")]).is_err());
+    }
+
+    #[test]
+    fn window_size() {
+        let args = vec![o("app"), o("--width"), o("400"), o("--height"), o("200")];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!((Some(400), Some(200)), (config.width, config.height));
+        assert!(Configuration::new(&[o("app"), o("--width"), o("wide")]).is_err());
+        assert!(Configuration::new(&[o("app"), o("--bar"), o("--height"), o("30")]).is_err());
+    }
+
+    #[test]
+    fn window_position() {
+        let args = vec![
+            o("app"),
+            o("--position"),
+            o("top-right"),
+            o("--offset"),
+            o("10,40"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(
+            position::Position::Anchor(gtk::Align::End, gtk::Align::Start),
+            config.position
+        );
+        assert_eq!((10, 40), config.offset);
+        assert!(Configuration::new(&[o("app"), o("--position"), o("middle")]).is_err());
+        assert!(Configuration::new(&[o("app"), o("--offset"), o("10")]).is_err());
+    }
+
+    #[test]
+    fn window_hints() {
+        let config = Configuration::new(&[o("app"), o("--no-sticky"), o("--no-urgent")]).unwrap();
+        assert!(!config.no_keep_above);
+        assert!(config.no_sticky);
+        assert!(config.no_urgent);
+    }
+
+    #[test]
+    fn header_bar() {
+        assert!(
+            Configuration::new(&[o("app"), o("--headerbar")])
+                .unwrap()
+                .headerbar
+        );
+        assert!(Configuration::new(&[o("app"), o("--bar"), o("--headerbar")]).is_err());
+    }
+
+    #[test]
+    fn attach_to_window() {
+        let args = vec![o("app"), o("--attach"), o("0x3a00007")];
+        assert_eq!(Some(0x3a0_0007), Configuration::new(&args).unwrap().attach);
+        assert!(Configuration::new(&[o("app"), o("--attach"), o("firefox")]).is_err());
+        let args = vec![o("app"), o("--attach"), o("42"), o("--position"), o("top")];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn bar_mode() {
+        assert!(Configuration::new(&[o("app"), o("--bar")]).unwrap().bar);
+        let args = vec![o("app"), o("--bar"), o("--footer"), o("Host: build01")];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn print_mode() {
+        let config = Configuration::new(&[o("app"), o("--print"), o("index")]).unwrap();
+        assert_eq!(Some(PrintMode::Index), config.print);
+        assert!(Configuration::new(&[o("app"), o("--print"), o("json")]).is_err());
+        let args = vec![
+            o("app"),
+            o("--print"),
+            o("label"),
+            o("--chain"),
+            o("next.toml"),
+        ];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn password_prompt() {
+        let config = Configuration::new(&[o("app"), o("--password")]).unwrap();
+        assert_eq!(Some(prompt::Prompt::Password), config.prompt);
+        assert_eq!("_OK", config.buttons[0].label);
+        assert_eq!(Some(PrintMode::Value), config.print);
+        assert_eq!(Some(FocusTarget::Button(0)), config.default_button);
+        let args = vec![
+            o("app"),
+            o("--password"),
+            o("-B"),
+            o("_Unlock"),
+            o("unlock.sh"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(1, config.buttons.len());
+        assert_eq!(None, config.print);
+        assert!(Configuration::new(&[o("app"), o("--password"), o("--pin")]).is_err());
+        assert!(Configuration::new(&[o("app"), o("--print"), o("value")]).is_err());
+        let args = vec![o("app"), o("--password"), o("--chain"), o("next.toml")];
+        assert_eq!(None, Configuration::new(&args).unwrap().print);
+        let args = vec![
+            o("app"),
+            o("--password"),
+            o("--print"),
+            o("value"),
+            o("--chain"),
+            o("next.toml"),
+        ];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn checklist_prompt() {
+        let args = vec![
+            o("app"),
+            o("--checklist"),
+            o("Clean cache"),
+            o("Rotate logs"),
+            o("-m"),
+            o("Maintenance"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(
+            Some(prompt::Prompt::Checklist(vec![
+                String::from("Clean cache"),
+                String::from("Rotate logs")
+            ])),
+            config.prompt
+        );
+        assert_eq!("Maintenance", config.message);
+        assert!(Configuration::new(&[o("app"), o("--checklist")]).is_err());
+        let args = vec![o("app"), o("--checklist"), o("a"), o("--password")];
+        assert!(Configuration::new(&args).is_err());
+        let args = vec![o("app"), o("-m"), o("-"), o("--checklist"), o("-")];
+        assert!(Configuration::new(&args).is_err());
+        let args = vec![o("app"), o("-m"), o("-"), o("--survey"), o("-")];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn radiolist_prompt() {
+        let args = vec![
+            o("app"),
+            o("--radiolist"),
+            o("Speakers"),
+            o("Headphones"),
+            o("--print"),
+            o("value"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(
+            Some(prompt::Prompt::Radiolist(vec![
+                String::from("Speakers"),
+                String::from("Headphones")
+            ])),
+            config.prompt
+        );
+        assert_eq!(Some(PrintMode::Value), config.print);
+        let args = vec![o("app"), o("--radiolist"), o("a"), o("--checklist"), o("b")];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn combo_prompt() {
+        let args = vec![o("app"), o("--combo"), o("HDMI-1"), o("eDP-1")];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(
+            Some(prompt::Prompt::Combo {
+                items: vec![String::from("HDMI-1"), String::from("eDP-1")],
+                editable: false
+            }),
+            config.prompt
+        );
+        let args = vec![o("app"), o("--combo"), o("hourly"), o("--editable")];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(
+            Some(prompt::Prompt::Combo {
+                items: vec![String::from("hourly")],
+                editable: true
+            }),
+            config.prompt
+        );
+        assert!(Configuration::new(&[o("app"), o("--editable")]).is_err());
+        let args = vec![o("app"), o("--password"), o("--editable")];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn scale_prompt() {
+        let args = vec![
+            o("app"),
+            o("--scale"),
+            o("0"),
+            o("100"),
+            o("-m"),
+            o("Volume"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(
+            Some(prompt::Prompt::Scale {
+                min: 0.0,
+                max: 100.0,
+                step: 1.0,
+                value: 0.0,
+                digits: 0
+            }),
+            config.prompt
+        );
+        assert_eq!("Volume", config.message);
+        let args = vec![
+            o("app"),
+            o("--scale"),
+            o("-1"),
+            o("1"),
+            o("0.25"),
+            o("-0.5"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(
+            Some(prompt::Prompt::Scale {
+                min: -1.0,
+                max: 1.0,
+                step: 0.25,
+                value: -0.5,
+                digits: 2
+            }),
+            config.prompt
+        );
+        let wrong = [
+            vec![o("app"), o("--scale"), o("5")],
+            vec![o("app"), o("--scale"), o("low"), o("high")],
+            vec![o("app"), o("--scale"), o("10"), o("0")],
+            vec![o("app"), o("--scale"), o("0"), o("10"), o("0")],
+            vec![o("app"), o("--scale"), o("0"), o("10"), o("1"), o("11")],
+        ];
+        for args in &wrong {
+            assert!(Configuration::new(args).is_err());
         }
-        run_script(&args[0]);
     }
-    std::process::exit(exit_code);
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::Configuration;
-    use std::ffi::OsString;
+    #[test]
+    fn idle_timeout() {
+        let args = vec![
+            o("app"),
+            o("-B"),
+            o("_Snooze"),
+            o("true"),
+            o("--timeout"),
+            o("300"),
+            o("Snooze"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(Some((300, FocusTarget::Button(0))), config.idle_timeout);
+        let config = Configuration::new(&[o("app"), o("--timeout"), o("60")]).unwrap();
+        assert_eq!(Some((60, FocusTarget::Cancel)), config.idle_timeout);
+        assert!(Configuration::new(&[o("app"), o("--timeout"), o("0")]).is_err());
+        assert!(Configuration::new(&[o("app"), o("--timeout"), o("5"), o("Missing")]).is_err());
+    }
 
-    fn o(s: &str) -> OsString {
-        OsString::from(s)
+    #[test]
+    fn button_timeouts() {
+        let args = vec![
+            o("app"),
+            o("--button-timeout"),
+            o("Continue"),
+            o("30"),
+            o("-B"),
+            o("_Continue"),
+            o("true"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(vec![(FocusTarget::Button(0), 30)], config.button_timeouts);
+        let args = vec![o("app"), o("--button-timeout"), o("Stop"), o("30")];
+        assert!(Configuration::new(&args).is_err());
+        let args = vec![o("app"), o("--button-timeout"), o("cancel"), o("0")];
+        assert!(Configuration::new(&args).is_err());
     }
+
     #[test]
-    fn button_order() {
+    #[cfg(unix)]
+    fn scheduled_display() {
+        use crate::{parse_duration, parse_time_of_day, seconds_until};
+        assert_eq!(Some(5400), parse_duration("1h30m"));
+        assert_eq!(Some(90), parse_duration("90"));
+        assert_eq!(None, parse_duration("15x"));
+        assert_eq!(None, parse_duration("1h30"));
+        assert_eq!(Some((17, 30)), parse_time_of_day("17:30"));
+        assert_eq!(None, parse_time_of_day("24:00"));
+        assert_eq!(60, seconds_until(17 * 3600 + 60, 17 * 3600));
+        assert_eq!(86400 - 60, seconds_until(17 * 3600, 17 * 3600 + 60));
+        let args = vec![o("app"), o("--at"), o("17:30"), o("--in"), o("5m")];
+        assert!(Configuration::new(&args).is_err());
+        let args = vec![o("app"), o("--in"), o("5m"), o("--delay"), o("30")];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn delayed_display() {
+        let args = vec![o("app"), o("--delay"), o("30"), o("--cancel-if"), o("true")];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(Some(30), config.delay);
+        assert!(config.cancel_if.is_some());
+        assert!(Configuration::new(&[o("app"), o("--cancel-if"), o("true")]).is_err());
+        assert!(Configuration::new(&[o("app"), o("--delay"), o("soon")]).is_err());
+    }
+
+    #[test]
+    fn close_when() {
+        let args = vec![
+            o("app"),
+            o("-m"),
+            o("VPN is down"),
+            o("--close-when"),
+            o("ip link show tun0"),
+            o("--poll-interval"),
+            o("10"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert!(config.close_when.is_some());
+        assert_eq!(Some(10), config.poll_interval);
+        assert!(Configuration::new(&[o("app"), o("--poll-interval"), o("10")]).is_err());
+    }
+
+    #[test]
+    fn probes() {
+        let config = Configuration::new(&[o("app"), o("--if-failed"), o("false")]).unwrap();
+        assert_eq!(Some(false), config.show_if.as_ref().map(|(_, s)| *s));
+        let args = vec![
+            o("app"),
+            o("--if-failed"),
+            o("false"),
+            o("--if-succeeds"),
+            o("true"),
+        ];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn pid_target() {
+        let config = Configuration::new(&[o("app"), o("--print-pid"), o("-")]).unwrap();
+        assert_eq!(Some(std::path::PathBuf::from("-")), config.print_pid);
+        assert!(Configuration::new(&[o("app"), o("--print-pid")]).is_err());
+    }
+
+    #[test]
+    fn status_needs_open_dialog() {
+        let config = Configuration::new(&[o("app"), o("--show-status")]).unwrap();
+        assert!(config.show_status);
+        let args = vec![o("app"), o("--show-status"), o("--exit-after-action")];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn queue_needs_open_dialog() {
+        assert!(Configuration::new(&[o("app"), o("--queue")]).unwrap().queue);
+        let args = vec![o("app"), o("--queue"), o("--exit-after-action")];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn tabs() {
         let args = vec![
             o("app"),
-            o("-b"),
-            o("1.1"),
-            o("1.2"),
             o("-B"),
-            o("2.1"),
-            o("2.2"),
+            o("Lock"),
+            o("lock.sh"),
+            o("--tab"),
+            o("_Power"),
+            o("-B"),
+            o("Off"),
+            o("poweroff"),
+            o("--tab"),
+            o("_Audio"),
+            o("-B"),
+            o("Mute"),
+            o("mute.sh"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(
+            vec![(String::from("_Power"), 1), (String::from("_Audio"), 2)],
+            config.tabs
+        );
+        assert!(Configuration::new(&[o("app"), o("--tab"), o("Empty")]).is_err());
+    }
+
+    #[test]
+    fn icon_layout() {
+        let args = vec![
+            o("app"),
+            o("-B"),
+            o("Save"),
+            o("save.sh"),
+            o("document-save"),
+            o("--icon-only"),
+            o("-B"),
+            o("Open"),
+            o("open.sh"),
+            o("document-open"),
+            o("--icon-position"),
+            o("left"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        assert_eq!(IconLayout::Only, config.buttons[0].icon_layout);
+        assert_eq!(IconLayout::Left, config.buttons[1].icon_layout);
+        let args = vec![o("app"), o("-B"), o("Save"), o("save.sh"), o("--icon-only")];
+        assert!(Configuration::new(&args).is_err());
+    }
+
+    #[test]
+    fn focus_targets() {
+        let args = vec![
+            o("app"),
+            o("--focus"),
+            o("Reboot"),
+            o("--focus-order"),
+            o("2,cancel,Log"),
             o("-b"),
-            o("3.1"),
-            o("3.2"),
+            o("_Log"),
+            o("log.sh"),
             o("-B"),
-            o("4.1"),
-            o("4.2"),
+            o("_Reboot"),
+            o("reboot"),
         ];
         let config = Configuration::new(&args).unwrap();
-        let mut i = 1;
-        for button in config.buttons {
-            let label = format!("{}.1", i);
-            assert_eq!(label, button.label);
-            i += 1;
+        assert_eq!(Some(FocusTarget::Button(1)), config.focus);
+        assert_eq!(
+            vec![
+                FocusTarget::Button(1),
+                FocusTarget::Cancel,
+                FocusTarget::Button(0)
+            ],
+            config.focus_order
+        );
+        assert!(Configuration::new(&[o("app"), o("--focus-order"), o("1")]).is_err());
+        assert!(Configuration::new(&[o("app"), o("--focus"), o("x")]).is_err());
+    }
+
+    #[test]
+    fn dump_ui() {
+        // Needs a display, there is nothing to check without one.
+        if gtk::init().is_err() {
+            return;
         }
+        let args = vec![o("app"), o("-m"), o("Hello"), o("-B"), o("_Yes"), o("true")];
+        let config = Configuration::new(&args).unwrap();
+        let (window, _) = create_gtk_dialog(&config);
+        let tree = dump::widget_tree(window.upcast_ref());
+        assert!(tree.starts_with("GtkWindow title=\"options-window-gtk\""));
+        assert!(tree.contains("GtkImage icon=dialog-error"));
+        assert!(tree.contains("GtkLabel #message \"Hello\""));
+        assert!(tree.lines().next().unwrap().contains(" .error"));
+        assert!(tree.contains(".action-button"));
+        assert!(tree.contains(".cancel-button"));
+        let yes = tree.find("GtkLabel \"Yes\"").unwrap();
+        let cancel = tree.find("GtkLabel \"Cancel\"").unwrap();
+        assert!(yes < cancel);
+        assert!(tree
+            .lines()
+            .any(|l| l.contains("GtkButton") && l.ends_with("[default] [focus]")));
     }
 }
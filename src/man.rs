@@ -0,0 +1,78 @@
+use crate::{COMMAND_HELP, OPTION_HELP, PROGRAM_NAME, VERSION};
+
+// Escapes text so roff doesn't interpret it, '-' would otherwise become a hyphen.
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+fn section(out: &mut String, title: &str, entries: &[(&str, &str)]) {
+    out.push_str(&format!(".SH {}\n", title));
+    for (term, description) in entries {
+        out.push_str(&format!(
+            ".TP\n\\fB{}\\fR\n{}\n",
+            escape(term),
+            escape(description)
+        ));
+    }
+}
+
+/* The man page is generated from the same tables as --help, so both always
+ * describe what the parser accepts.
+ */
+pub fn page() -> String {
+    let mut out = format!(
+        ".TH {} 1 \"\" \"{} {}\"\n",
+        escape(&PROGRAM_NAME.to_uppercase()),
+        escape(PROGRAM_NAME),
+        VERSION
+    );
+    out.push_str(&format!(
+        ".SH NAME\n{} \\- GTK replacement for i3\\-nagbar\n",
+        escape(PROGRAM_NAME)
+    ));
+    out.push_str(&format!(
+        ".SH SYNOPSIS\n.B {}\n[\\fIOPTION\\fR]...\n",
+        escape(PROGRAM_NAME)
+    ));
+    for (command, _) in COMMAND_HELP {
+        out.push_str(&format!(
+            ".br\n.B {}\n{}\n",
+            escape(PROGRAM_NAME),
+            escape(command)
+        ));
+    }
+    out.push_str(
+        ".SH DESCRIPTION\nShows a window with a message and buttons which run commands. \
+         It supports the same arguments as i3\\-nagbar plus some additional.\n",
+    );
+    section(&mut out, "OPTIONS", OPTION_HELP);
+    section(&mut out, "COMMANDS", COMMAND_HELP);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{option_names, Configuration};
+    use std::ffi::OsString;
+
+    #[test]
+    fn documented_options_are_parsed() {
+        for name in option_names() {
+            let args = vec![OsString::from("app"), OsString::from(name)];
+            if let Err(e) = Configuration::new(&args) {
+                assert!(!e.to_string().starts_with("Unhandled option"), "{}", name);
+            }
+        }
+        // Options missing from the table are rejected before they reach their branch.
+        let args = vec![OsString::from("app"), OsString::from("--undocumented")];
+        assert_eq!(
+            Some(String::from("Unexpected argument: --undocumented")),
+            Configuration::new(&args).err().map(|e| e.to_string())
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{host_command, strip_mnemonics, PROGRAM_NAME};
+
+/* --notify-result sends a desktop notification (through notify-send) when an
+ * action finishes, with its result and runtime. The actions are watched by
+ * threads kept with the other watchers in main.rs, so after the window is
+ * gone the process stays until all of them are done.
+ */
+
+fn format_runtime(secs: u64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+pub fn action_finished(
+    label: &str,
+    status: &std::io::Result<std::process::ExitStatus>,
+    runtime: std::time::Duration,
+) {
+    let name = strip_mnemonics(label);
+    let (summary, icon) = match status {
+        Ok(s) if s.success() => (format!("{} finished", name), "dialog-information"),
+        Ok(s) => match s.code() {
+            Some(code) => (
+                format!("{} failed with exit code {}", name, code),
+                "dialog-error",
+            ),
+            None => (format!("{} was killed", name), "dialog-error"),
+        },
+        Err(e) => (
+            format!("{} couldn't be watched: {}", name, e),
+            "dialog-error",
+        ),
+    };
+    let result = host_command("notify-send")
+        .arg(format!("--app-name={}", PROGRAM_NAME))
+        .arg(format!("--icon={}", icon))
+        .arg(summary)
+        .arg(format!("after {}", format_runtime(runtime.as_secs())))
+        .status();
+    if let Err(e) = result {
+        warning!("Couldn't send notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_runtime;
+
+    #[test]
+    fn runtime() {
+        assert_eq!("42s", format_runtime(42));
+        assert_eq!("3m 12s", format_runtime(192));
+        assert_eq!("2h 5m", format_runtime(7500));
+    }
+}
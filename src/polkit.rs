@@ -0,0 +1,413 @@
+use crate::{
+    create_gtk_button, create_gtk_message, create_gtk_window, Configuration, GtkButtons,
+    MessageType,
+};
+use dbus::arg::{RefArg, Variant};
+use dbus::tree::Factory;
+use dbus::{BusType, Connection, Message};
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::process::Stdio;
+use std::rc::Rc;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+
+/* PolicyKit authentication agent for sessions without one (i3, sway...).
+ *
+ * D-Bus is handled in its own thread. BeginAuthentication is answered only
+ * after the user authenticated or cancelled, so the replies are kept as
+ * pending until the prompt, which runs in the gtk main loop, responds. The
+ * thread sleeps in poll() until either the bus or a prompt has something.
+ * The password is checked by polkit's setuid helper, which speaks a line
+ * based protocol on stdin/stdout.
+ */
+
+const AGENT_PATH: &str = "/org/frankE/OptionsWindowGtk/PolkitAgent";
+const HELPERS: &[&str] = &[
+    "/usr/lib/polkit-1/polkit-agent-helper-1",
+    "/usr/libexec/polkit-agent-helper-1",
+    "/usr/lib/policykit-1/polkit-agent-helper-1",
+];
+
+// (kind, details) of everybody who may authenticate, e.g. ("unix-user", {"uid": 1000})
+type Identities = Vec<(String, HashMap<String, Variant<Box<dyn RefArg>>>)>;
+
+enum Response {
+    Password(String),
+    Cancel,
+}
+
+// Hands the responses of the prompts to the D-Bus thread and wakes it up.
+#[derive(Clone)]
+struct Responder {
+    responses: Sender<(String, Response)>,
+    wake: Arc<UnixStream>,
+}
+
+impl Responder {
+    fn send(&self, cookie: String, response: Response) {
+        if self.responses.send((cookie, response)).is_ok() {
+            let _ = (&*self.wake).write_all(&[0]);
+        }
+    }
+}
+
+struct Pending {
+    user: String,
+    message: String,
+    icon: String,
+    done: Message,
+    cancelled: Message,
+}
+
+thread_local! {
+    // Only used in the gtk thread.
+    static STYLE: RefCell<Option<Configuration>> = const { RefCell::new(None) };
+    static PROMPTS: RefCell<HashMap<String, gtk::Window>> = RefCell::new(HashMap::new());
+}
+
+// Registers the agent and runs until it gets killed.
+pub fn run_agent(config: &Configuration) -> i32 {
+    STYLE.with(|s| *s.borrow_mut() = Some(config.clone()));
+    std::thread::spawn(|| {
+        if let Err(e) = serve() {
+            eprintln!("Polkit agent stopped: {}", e);
+            glib::idle_add(|| {
+                gtk::main_quit();
+                glib::Continue(false)
+            });
+        }
+    });
+    gtk::main();
+    1
+}
+
+fn serve() -> Result<(), dbus::Error> {
+    let connection = Connection::get_private(BusType::System)?;
+    let pending: Rc<RefCell<HashMap<String, Pending>>> = Rc::new(RefCell::new(HashMap::new()));
+    let (responses, rx) = channel::<(String, Response)>();
+    let (wake, woken) = UnixStream::pair().map_err(io_error)?;
+    woken.set_nonblocking(true).map_err(io_error)?;
+    let tx = Responder {
+        responses,
+        wake: Arc::new(wake),
+    };
+
+    let factory = Factory::new_fn::<()>();
+    let begin_pending = pending.clone();
+    let begin_tx = tx.clone();
+    let cancel_tx = tx.clone();
+    let tree = factory.tree(()).add(
+        factory.object_path(AGENT_PATH, ()).introspectable().add(
+            factory
+                .interface("org.freedesktop.PolicyKit1.AuthenticationAgent", ())
+                .add_m(
+                    factory
+                        .method("BeginAuthentication", (), move |m| {
+                            let mut args = m.msg.iter_init();
+                            let _action_id: String = args.read()?;
+                            let message: String = args.read()?;
+                            let icon: String = args.read()?;
+                            let _details: HashMap<String, String> = args.read()?;
+                            let cookie: String = args.read()?;
+                            let identities: Identities = args.read()?;
+                            let user = match identities
+                                .iter()
+                                .filter(|(kind, _)| kind == "unix-user")
+                                .filter_map(|(_, d)| d.get("uid").and_then(|v| v.as_u64()))
+                                .find_map(|uid| user_name(uid as u32))
+                            {
+                                Some(user) => user,
+                                None => {
+                                    return Ok(vec![m.msg.error(
+                                        &"org.freedesktop.PolicyKit1.Error.Failed".into(),
+                                        &std::ffi::CString::new("No unix user to authenticate as")
+                                            .unwrap(),
+                                    )])
+                                }
+                            };
+                            let request = Pending {
+                                user,
+                                message,
+                                icon,
+                                done: m.msg.method_return(),
+                                cancelled: m.msg.error(
+                                    &"org.freedesktop.PolicyKit1.Error.Cancelled".into(),
+                                    &std::ffi::CString::new("Authentication was cancelled")
+                                        .unwrap(),
+                                ),
+                            };
+                            show_prompt(&cookie, &request, None, &begin_tx);
+                            begin_pending.borrow_mut().insert(cookie, request);
+                            Ok(Vec::new())
+                        })
+                        .inarg::<&str, _>("action_id")
+                        .inarg::<&str, _>("message")
+                        .inarg::<&str, _>("icon_name")
+                        .inarg::<HashMap<&str, &str>, _>("details")
+                        .inarg::<&str, _>("cookie")
+                        .inarg::<Identities, _>("identities"),
+                )
+                .add_m(
+                    factory
+                        .method("CancelAuthentication", (), move |m| {
+                            let cookie: String = m.msg.read1()?;
+                            cancel_tx.send(cookie.clone(), Response::Cancel);
+                            glib::idle_add(move || {
+                                close_prompt(&cookie);
+                                glib::Continue(false)
+                            });
+                            Ok(vec![m.msg.method_return()])
+                        })
+                        .inarg::<&str, _>("cookie"),
+                ),
+        ),
+    );
+    tree.set_registered(&connection, true)?;
+    connection.add_handler(tree);
+    register(&connection)?;
+
+    loop {
+        // Handles what arrived and sends what is queued, without blocking.
+        for _ in connection.incoming(0) {}
+        while let Ok((cookie, response)) = rx.try_recv() {
+            let request = match pending.borrow_mut().remove(&cookie) {
+                Some(r) => r,
+                None => continue,
+            };
+            match response {
+                Response::Cancel => {
+                    let _ = connection.send(request.cancelled);
+                }
+                Response::Password(password) => {
+                    match authenticate(&request.user, &cookie, &password) {
+                        Ok(()) => {
+                            let _ = connection.send(request.done);
+                        }
+                        Err(e) => {
+                            show_prompt(&cookie, &request, Some(e), &tx);
+                            pending.borrow_mut().insert(cookie, request);
+                        }
+                    }
+                }
+            }
+        }
+        wait(&connection, &woken)?;
+    }
+}
+
+fn io_error(e: std::io::Error) -> dbus::Error {
+    dbus::Error::new_custom("org.freedesktop.DBus.Error.Failed", &e.to_string())
+}
+
+// Blocks until the bus can be read or written or a prompt responded.
+fn wait(connection: &Connection, woken: &UnixStream) -> Result<(), dbus::Error> {
+    let mut fds: Vec<libc::pollfd> = connection
+        .watch_fds()
+        .iter()
+        .map(|w| w.to_pollfd())
+        .collect();
+    fds.push(libc::pollfd {
+        fd: woken.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    });
+    if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+        let e = std::io::Error::last_os_error();
+        if e.kind() != std::io::ErrorKind::Interrupted {
+            return Err(io_error(e));
+        }
+    }
+    // The bytes only wake us up, the responses are in the channel.
+    let mut buffer = [0; 64];
+    while let Ok(n) = (&*woken).read(&mut buffer) {
+        if n == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// The agent is registered for the session we run in.
+fn register(connection: &Connection) -> Result<(), dbus::Error> {
+    let mut details: HashMap<&str, Variant<Box<dyn RefArg>>> = HashMap::new();
+    let kind = match std::env::var("XDG_SESSION_ID") {
+        Ok(session) => {
+            details.insert("session-id", Variant(Box::new(session)));
+            "unix-session"
+        }
+        Err(_) => {
+            // polkit looks the start time up itself if it is 0.
+            details.insert("pid", Variant(Box::new(std::process::id())));
+            details.insert("start-time", Variant(Box::new(0u64)));
+            "unix-process"
+        }
+    };
+    let locale = std::env::var("LANG").unwrap_or_else(|_| String::from("C"));
+    let message = Message::new_method_call(
+        "org.freedesktop.PolicyKit1",
+        "/org/freedesktop/PolicyKit1/Authority",
+        "org.freedesktop.PolicyKit1.Authority",
+        "RegisterAuthenticationAgent",
+    )
+    .map_err(|e| dbus::Error::new_custom("org.freedesktop.DBus.Error.Failed", &e))?
+    .append3((kind, details), locale, dbus::Path::from(AGENT_PATH));
+    connection.send_with_reply_and_block(message, 5000)?;
+    Ok(())
+}
+
+fn user_name(uid: u32) -> Option<String> {
+    let passwd = unsafe { libc::getpwuid(uid) };
+    if passwd.is_null() {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr((*passwd).pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+fn authenticate(user: &str, cookie: &str, password: &str) -> Result<(), String> {
+    let helper = HELPERS
+        .iter()
+        .find(|h| std::path::Path::new(h).exists())
+        .ok_or_else(|| String::from("polkit-agent-helper-1 not found"))?;
+    let mut child = std::process::Command::new(helper)
+        .arg(user)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let mut stdin = child.stdin.take().unwrap();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    writeln!(stdin, "{}", cookie).map_err(|e| e.to_string())?;
+    let mut error = String::from("Authentication failed");
+    let mut result = Err(String::new());
+    for line in stdout.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.starts_with("PAM_PROMPT_ECHO_OFF") {
+            writeln!(stdin, "{}", password).map_err(|e| e.to_string())?;
+        } else if let Some(prompt) = line.strip_prefix("PAM_PROMPT_ECHO_ON") {
+            // Visible input like a user name, the password must not go there.
+            error = format!("Can't answer the prompt {:?}", prompt.trim());
+            break;
+        } else if let Some(msg) = line.strip_prefix("PAM_ERROR_MSG ") {
+            error = String::from(msg);
+        } else if line == "SUCCESS" {
+            result = Ok(());
+            break;
+        } else if line == "FAILURE" {
+            break;
+        }
+    }
+    // Without input the helper gives up if it still waits for some.
+    drop(stdin);
+    let _ = child.wait();
+    result.map_err(|_| error)
+}
+
+// Called from the D-Bus thread, the prompt itself is built in the gtk thread.
+fn show_prompt(cookie: &str, request: &Pending, error: Option<String>, tx: &Responder) {
+    let cookie = String::from(cookie);
+    let message = request.message.clone();
+    let icon = request.icon.clone();
+    let user = request.user.clone();
+    let tx = tx.clone();
+    glib::idle_add(move || {
+        let window = create_prompt(&cookie, &message, &icon, &user, &error, &tx);
+        close_prompt(&cookie);
+        window.show_all();
+        PROMPTS.with(|p| p.borrow_mut().insert(cookie.clone(), window));
+        glib::Continue(false)
+    });
+}
+
+fn close_prompt(cookie: &str) {
+    if let Some(window) = PROMPTS.with(|p| p.borrow_mut().remove(cookie)) {
+        window.destroy();
+    }
+}
+
+// Uses the styling options of the command line, so it looks like every other dialog.
+fn create_prompt(
+    cookie: &str,
+    message: &str,
+    icon: &str,
+    user: &str,
+    error: &Option<String>,
+    tx: &Responder,
+) -> gtk::Window {
+    let mut config = STYLE
+        .with(|s| s.borrow().clone())
+        .expect("Agent not started.");
+    config.message = String::from(message);
+    config.message_type = if error.is_some() {
+        MessageType::ERROR
+    } else {
+        MessageType::INFO
+    };
+    config.buttons.clear();
+    let message_box = create_gtk_message(&config);
+    if !icon.is_empty() {
+        message_box.add(&gtk::Image::new_from_icon_name(icon, 6));
+    }
+    if let Some(e) = error {
+        message_box.add(&gtk::Label::new(e.as_str()));
+    }
+
+    let container = gtk::Box::new(gtk::Orientation::Vertical, config.spacing);
+    container.add(&gtk::Label::new(format!("Password for {}:", user).as_str()));
+    let entry = gtk::Entry::new();
+    entry.set_visibility(false);
+    entry.set_input_purpose(gtk::InputPurpose::Password);
+    entry.set_activates_default(true);
+    container.add(&entry);
+    let row = gtk::Box::new(gtk::Orientation::Horizontal, config.spacing);
+    let authenticate = create_gtk_button(
+        "_Authenticate",
+        &None,
+        config.button_icon_size,
+        config.spacing,
+    );
+    let cancel = create_gtk_button("_Cancel", &None, config.button_icon_size, config.spacing);
+    row.pack_start(&cancel, true, true, 0);
+    row.pack_start(&authenticate, true, true, 0);
+    container.add(&row);
+    let buttons = GtkButtons {
+        container,
+        actions: vec![authenticate.clone()],
+        cancel: cancel.clone(),
+        focus: authenticate.clone().upcast(),
+    };
+    let window = create_gtk_window(&config, &buttons, &message_box);
+    authenticate.set_can_default(true);
+    window.set_default(&authenticate);
+    entry.grab_focus();
+
+    // Answered exactly once, closing the window afterwards must not cancel.
+    let answered = Rc::new(RefCell::new(false));
+    let respond = {
+        let answered = answered.clone();
+        let cookie = String::from(cookie);
+        let tx = tx.clone();
+        move |response: Response| {
+            if !answered.replace(true) {
+                tx.send(cookie.clone(), response);
+            }
+            close_prompt(&cookie);
+        }
+    };
+    let respond_auth = respond.clone();
+    authenticate.connect_clicked(move |_| {
+        respond_auth(Response::Password(entry.get_text().unwrap_or_default()))
+    });
+    let respond_cancel = respond.clone();
+    cancel.connect_clicked(move |_| respond_cancel(Response::Cancel));
+    window.connect_delete_event(move |_, _| {
+        respond(Response::Cancel);
+        Inhibit(true)
+    });
+    window
+}
@@ -0,0 +1,142 @@
+use gdk::ScreenExt;
+use gtk::prelude::*;
+
+/* --position puts the dialog at an edge or a corner of the primary monitor,
+ * or at a point of the root window. --offset moves it away from that edge,
+ * e.g. to keep a panel visible. Wayland compositors don't let windows place
+ * themselves, there the dialog goes wherever the compositor puts it.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Position {
+    Center,
+    // Horizontal and vertical alignment, Start is left or top.
+    Anchor(gtk::Align, gtk::Align),
+    At(i32, i32),
+}
+
+pub fn parse(value: &str) -> Option<Position> {
+    use gtk::Align::{Center, End, Start};
+    let (horizontal, vertical) = match value.to_ascii_lowercase().as_str() {
+        "center" => return Some(Position::Center),
+        "top" => (Center, Start),
+        "bottom" => (Center, End),
+        "left" => (Start, Center),
+        "right" => (End, Center),
+        "top-left" => (Start, Start),
+        "top-right" => (End, Start),
+        "bottom-left" => (Start, End),
+        "bottom-right" => (End, End),
+        _ => {
+            let (x, y) = parse_point(value)?;
+            return Some(Position::At(x, y));
+        }
+    };
+    Some(Position::Anchor(horizontal, vertical))
+}
+
+// X,Y in pixels, as for --offset.
+pub fn parse_point(value: &str) -> Option<(i32, i32)> {
+    let mut parts = value.splitn(2, ',');
+    let x = parts.next()?.trim().parse().ok()?;
+    let y = parts.next()?.trim().parse().ok()?;
+    Some((x, y))
+}
+
+fn align(align: gtk::Align, start: i32, length: i32, size: i32, offset: i32) -> i32 {
+    match align {
+        gtk::Align::Start => start + offset,
+        gtk::Align::End => start + length - size - offset,
+        _ => start + (length - size) / 2 + offset,
+    }
+}
+
+// Top left corner of a window with the given size on the monitor.
+fn origin(
+    position: Position,
+    monitor: &gdk::Rectangle,
+    size: (i32, i32),
+    offset: (i32, i32),
+) -> (i32, i32) {
+    let (horizontal, vertical) = match position {
+        Position::At(x, y) => return (x + offset.0, y + offset.1),
+        Position::Center => (gtk::Align::Center, gtk::Align::Center),
+        Position::Anchor(horizontal, vertical) => (horizontal, vertical),
+    };
+    (
+        align(horizontal, monitor.x, monitor.width, size.0, offset.0),
+        align(vertical, monitor.y, monitor.height, size.1, offset.1),
+    )
+}
+
+pub fn place(window: &gtk::Window, position: Position, offset: (i32, i32)) {
+    if position == Position::Center && offset == (0, 0) {
+        window.set_position(gtk::WindowPosition::Center);
+        return;
+    }
+    // The size is only known once the content is laid out.
+    window.connect_realize(move |w| {
+        let screen = match w.get_screen() {
+            Some(s) => s,
+            None => {
+                warning!("Couldn't position the dialog, there is no screen.");
+                return;
+            }
+        };
+        let monitor = screen.get_monitor_geometry(screen.get_primary_monitor());
+        let (_, natural) = w.get_preferred_size();
+        let (x, y) = origin(position, &monitor, (natural.width, natural.height), offset);
+        w.move_(x, y);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{origin, parse, parse_point, Position};
+
+    fn monitor(x: i32, width: i32, height: i32) -> gdk::Rectangle {
+        gdk::Rectangle {
+            x,
+            y: 0,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn positions() {
+        assert_eq!(Some(Position::Center), parse("Center"));
+        assert_eq!(
+            Some(Position::Anchor(gtk::Align::End, gtk::Align::End)),
+            parse("bottom-right")
+        );
+        assert_eq!(Some(Position::At(10, -20)), parse("10,-20"));
+        assert_eq!(None, parse("middle"));
+        assert_eq!(None, parse_point("10"));
+    }
+
+    #[test]
+    fn origins() {
+        let screen = monitor(0, 1920, 1080);
+        let top_left = parse("top-left").unwrap();
+        assert_eq!((20, 30), origin(top_left, &screen, (400, 200), (20, 30)));
+        let bottom_right = parse("bottom-right").unwrap();
+        assert_eq!(
+            (1500, 870),
+            origin(bottom_right, &screen, (400, 200), (20, 10))
+        );
+        assert_eq!(
+            (760, 0),
+            origin(parse("top").unwrap(), &screen, (400, 200), (0, 0))
+        );
+        // Second monitor right of the first one
+        let second = monitor(1920, 2560, 1440);
+        assert_eq!(
+            (1920, 620),
+            origin(parse("left").unwrap(), &second, (400, 200), (0, 0))
+        );
+        assert_eq!(
+            (5, 5),
+            origin(Position::At(0, 0), &second, (400, 200), (5, 5))
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{
+    create_gtk_dialog, exec_in_shell, Action, Button, ButtonStyle, Command, Configuration,
+    IconLayout, MessageType,
+};
+use gtk::prelude::*;
+use std::ffi::OsString;
+use std::rc::Rc;
+
+/* --preview-types shows a sample dialog of every message type and layout at
+ * once, tiled over the screen. Styling options and the user's gtk.css apply
+ * as usual, so theme authors can check every variant of their stylesheet.
+ * The buttons don't run anything, Cancel of any dialog closes all of them.
+ */
+
+const LAYOUTS: &[&str] = &["buttons", "PIN keypad"];
+// Space between the tiled windows.
+const GAP: i32 = 20;
+
+fn sample_button(label: &str, icon: Option<&str>) -> Button {
+    Button {
+        label: String::from(label),
+        icon: icon.map(OsString::from),
+        action: Action::Command(Command::new(OsString::from("true"), exec_in_shell)),
+        require_typing: None,
+        hold_to_confirm: None,
+        icon_layout: IconLayout::Right,
+        exit_code: None,
+        exit_after: None,
+        tooltip: None,
+        style: ButtonStyle::Normal,
+        confirm: None,
+        secondary: None,
+    }
+}
+
+fn sample(config: &Configuration, message_type: MessageType, layout: usize) -> Configuration {
+    let mut sample = config.clone();
+    sample.message = format!(
+        "A {} dialog with {}, as shown with the current theme.",
+        message_type.name(),
+        LAYOUTS[layout]
+    );
+    sample.message_type = message_type;
+    sample.pin = layout == 1;
+    sample.audit_log = None;
+    sample.chain = None;
+    if sample.buttons.is_empty() {
+        sample.buttons = vec![
+            sample_button("_Apply", None),
+            sample_button("_Open", Some("document-open")),
+        ];
+    }
+    sample.on_choice = Some(Rc::new(|index: Option<usize>, _| {
+        if index.is_none() {
+            gtk::main_quit();
+        }
+    }));
+    sample
+}
+
+pub fn run(config: &Configuration) -> i32 {
+    let types = [MessageType::INFO, MessageType::WARNING, MessageType::ERROR];
+    let mut windows = Vec::new();
+    for (row, layout) in LAYOUTS.iter().enumerate() {
+        for (column, message_type) in types.iter().enumerate() {
+            let (window, _) = create_gtk_dialog(&sample(config, message_type.clone(), row));
+            window.set_title(&format!(
+                "{}: {}, {}",
+                crate::PROGRAM_NAME,
+                message_type.name(),
+                layout
+            ));
+            window.show_all();
+            windows.push((column as i32, row as i32, window));
+        }
+    }
+    // Every window gets a cell of the size of the largest one.
+    let (width, height) = windows
+        .iter()
+        .map(|(_, _, w)| w.get_size())
+        .fold((0, 0), |(mw, mh), (w, h)| (mw.max(w), mh.max(h)));
+    for (column, row, window) in &windows {
+        window.set_position(gtk::WindowPosition::None);
+        window.move_(GAP + column * (width + GAP), GAP + row * (height + GAP));
+    }
+    gtk::main();
+    0
+}
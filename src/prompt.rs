@@ -0,0 +1,157 @@
+use gtk::prelude::*;
+use std::rc::Rc;
+
+/* Prompts ask for a value besides the button: --password, --checklist,
+ * whose value are the checked items, one per line, --radiolist and --combo,
+ * whose value is the selected item, or --scale. The widget goes
+ * between the message and the buttons and its value reaches the actions like
+ * the PIN of --pin does. Without buttons of its own the dialog gets an OK
+ * button, which prints the value as with --print value.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Prompt {
+    Password,
+    Checklist(Vec<String>),
+    Radiolist(Vec<String>),
+    // Editable combos also take text which isn't one of the items.
+    Combo {
+        items: Vec<String>,
+        editable: bool,
+    },
+    // Digits are the decimals shown and printed.
+    Scale {
+        min: f64,
+        max: f64,
+        step: f64,
+        value: f64,
+        digits: u32,
+    },
+}
+
+pub struct GtkPrompt {
+    pub widget: gtk::Widget,
+    // Gets the initial focus, unless --focus says otherwise
+    pub focus: gtk::Widget,
+    // The current value, as printed or given to the actions
+    pub value: Rc<dyn Fn() -> String>,
+}
+
+// Long lists scroll instead of growing the window off screen.
+fn create_gtk_item_list(items: &gtk::Box, count: usize) -> gtk::Widget {
+    if count <= 10 {
+        return items.clone().upcast();
+    }
+    let scrolled = gtk::ScrolledWindow::new(None, None);
+    scrolled.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+    scrolled.set_min_content_height(250);
+    scrolled.add(items);
+    scrolled.upcast()
+}
+
+pub fn create_gtk_prompt(prompt: &Prompt, spacing: i32) -> GtkPrompt {
+    match prompt {
+        Prompt::Password => {
+            let entry = gtk::Entry::new();
+            entry.set_visibility(false);
+            entry.set_input_purpose(gtk::InputPurpose::Password);
+            // Enter presses OK, the default button.
+            entry.set_activates_default(true);
+            let text = entry.clone();
+            GtkPrompt {
+                widget: entry.clone().upcast(),
+                focus: entry.upcast(),
+                value: Rc::new(move || text.get_text().unwrap_or_default()),
+            }
+        }
+        Prompt::Checklist(items) => {
+            let list = gtk::Box::new(gtk::Orientation::Vertical, spacing);
+            let checks: Vec<(gtk::CheckButton, String)> = items
+                .iter()
+                .map(|item| (gtk::CheckButton::new_with_label(item), item.clone()))
+                .collect();
+            for (check, _) in &checks {
+                list.pack_start(check, false, false, 0);
+            }
+            GtkPrompt {
+                widget: create_gtk_item_list(&list, checks.len()),
+                focus: checks[0].0.clone().upcast(),
+                value: Rc::new(move || {
+                    checks
+                        .iter()
+                        .filter(|(check, _)| check.get_active())
+                        .map(|(_, item)| item.as_str())
+                        .collect::<Vec<&str>>()
+                        .join("\n")
+                }),
+            }
+        }
+        Prompt::Radiolist(items) => {
+            let list = gtk::Box::new(gtk::Orientation::Vertical, spacing);
+            let first = gtk::RadioButton::new_with_label(&items[0]);
+            let mut radios = vec![(first.clone(), items[0].clone())];
+            for item in &items[1..] {
+                let radio = gtk::RadioButton::new_with_label_from_widget(&first, item);
+                radios.push((radio, item.clone()));
+            }
+            for (radio, _) in &radios {
+                list.pack_start(radio, false, false, 0);
+            }
+            GtkPrompt {
+                widget: create_gtk_item_list(&list, radios.len()),
+                focus: first.upcast(),
+                value: Rc::new(move || {
+                    radios
+                        .iter()
+                        .find(|(radio, _)| radio.get_active())
+                        .map(|(_, item)| item.clone())
+                        .unwrap_or_default()
+                }),
+            }
+        }
+        Prompt::Combo { items, editable } => {
+            let combo = if *editable {
+                gtk::ComboBoxText::new_with_entry()
+            } else {
+                gtk::ComboBoxText::new()
+            };
+            for item in items {
+                combo.append_text(item);
+            }
+            combo.set_active(0);
+            let mut focus: gtk::Widget = combo.clone().upcast();
+            if let Some(entry) = combo
+                .get_child()
+                .and_then(|c| c.downcast::<gtk::Entry>().ok())
+            {
+                // Enter presses OK, the default button.
+                entry.set_activates_default(true);
+                focus = entry.upcast();
+            }
+            let chosen = combo.clone();
+            GtkPrompt {
+                widget: combo.upcast(),
+                focus,
+                value: Rc::new(move || chosen.get_active_text().unwrap_or_default()),
+            }
+        }
+        Prompt::Scale {
+            min,
+            max,
+            step,
+            value,
+            digits,
+        } => {
+            let scale = gtk::Scale::new_with_range(gtk::Orientation::Horizontal, *min, *max, *step);
+            scale.set_digits(*digits as i32);
+            scale.set_value(*value);
+            scale.set_size_request(250, -1);
+            let digits = *digits as usize;
+            let chosen = scale.clone();
+            GtkPrompt {
+                widget: scale.clone().upcast(),
+                focus: scale.upcast(),
+                value: Rc::new(move || format!("{:.*}", digits, chosen.get_value())),
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use qrcode::{Color, QrCode};
+
+const MODULE_SIZE: usize = 6;
+const QUIET_ZONE: usize = 4;
+
+/* Encodes TEXT, or the content of FILE when the argument has the form @FILE. */
+pub fn encode(arg: &str) -> Result<QrCode, String> {
+    let data = match arg.strip_prefix('@') {
+        Some(file) => std::fs::read(file).map_err(|e| format!("Couldn't read {}: {}", file, e))?,
+        None => arg.as_bytes().to_vec(),
+    };
+    QrCode::new(data).map_err(|e| format!("Couldn't create QR code: {}", e))
+}
+
+/* Renders the code as black modules on white, including the mandatory quiet zone. */
+pub fn to_pixbuf(code: &QrCode) -> gdk_pixbuf::Pixbuf {
+    let modules = code.width();
+    let colors = code.to_colors();
+    let size = (modules + 2 * QUIET_ZONE) * MODULE_SIZE;
+    let mut pixels = vec![0xffu8; size * size * 3];
+    for (i, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let x0 = (i % modules + QUIET_ZONE) * MODULE_SIZE;
+        let y0 = (i / modules + QUIET_ZONE) * MODULE_SIZE;
+        for y in y0..y0 + MODULE_SIZE {
+            let row = y * size * 3;
+            for byte in &mut pixels[row + x0 * 3..row + (x0 + MODULE_SIZE) * 3] {
+                *byte = 0;
+            }
+        }
+    }
+    gdk_pixbuf::Pixbuf::new_from_vec(
+        pixels,
+        gdk_pixbuf::Colorspace::Rgb,
+        false,
+        8,
+        size as i32,
+        size as i32,
+        (size * 3) as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, to_pixbuf, MODULE_SIZE, QUIET_ZONE};
+    use gdk_pixbuf::prelude::*;
+
+    #[test]
+    fn text_and_file() {
+        let code = encode("https://example.org").unwrap();
+        let path = std::env::temp_dir().join(format!("qr-{}.txt", std::process::id()));
+        std::fs::write(&path, "https://example.org").unwrap();
+        let from_file = encode(&format!("@{}", path.to_string_lossy())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(code.to_colors(), from_file.to_colors());
+        let error = encode("@/nonexistent/qr.txt").err().unwrap();
+        assert!(
+            error.starts_with("Couldn't read /nonexistent/qr.txt"),
+            "{}",
+            error
+        );
+        assert!(encode(&"x".repeat(8000)).is_err());
+    }
+
+    #[test]
+    fn pixbuf_size() {
+        let code = encode("backup").unwrap();
+        let pixbuf = to_pixbuf(&code);
+        let size = ((code.width() + 2 * QUIET_ZONE) * MODULE_SIZE) as i32;
+        assert_eq!(size, pixbuf.get_width());
+        assert_eq!(size, pixbuf.get_height());
+        assert!(!pixbuf.get_has_alpha());
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+
+/* --queue runs actions one after another on a worker thread instead of all at
+ * once. The worker ends once the queue is closed (or the last handle to it is
+ * gone), after it ran everything which was queued before.
+ */
+
+pub type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Clone)]
+pub struct ActionQueue {
+    // None closes the queue
+    jobs: Sender<Option<Job>>,
+    // Running and waiting jobs
+    queued: Arc<AtomicUsize>,
+}
+
+impl ActionQueue {
+    pub fn new() -> (Self, std::thread::JoinHandle<()>) {
+        let (jobs, received) = channel::<Option<Job>>();
+        let queued = Arc::new(AtomicUsize::new(0));
+        let count = queued.clone();
+        let worker = std::thread::spawn(move || {
+            while let Ok(Some(job)) = received.recv() {
+                job();
+                count.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+        (Self { jobs, queued }, worker)
+    }
+
+    pub fn push(&self, label: &str, job: Job) {
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        if self.jobs.send(Some(job)).is_err() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            warning!("The action queue stopped, {} wasn't run.", label);
+        }
+    }
+
+    pub fn close(&self) {
+        let _ = self.jobs.send(None);
+    }
+
+    // Hidden while the queue is empty.
+    pub fn indicator(&self) -> gtk::Label {
+        use gtk::{LabelExt, WidgetExt};
+        let label = gtk::Label::new(None);
+        label.set_no_show_all(true);
+        let queued = self.queued.clone();
+        let indicator = label.clone();
+        gtk::timeout_add(200, move || {
+            match indicator_text(queued.load(Ordering::SeqCst)) {
+                Some(text) => {
+                    indicator.set_text(&text);
+                    indicator.show();
+                }
+                None => indicator.hide(),
+            }
+            gtk::Continue(true)
+        });
+        label
+    }
+}
+
+fn indicator_text(queued: usize) -> Option<String> {
+    match queued {
+        0 => None,
+        1 => Some(String::from("1 action running")),
+        n => Some(format!("1 action running, {} waiting", n - 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::indicator_text;
+
+    #[test]
+    fn indicator() {
+        assert_eq!(None, indicator_text(0));
+        assert_eq!(Some(String::from("1 action running")), indicator_text(1));
+        assert_eq!(
+            Some(String::from("1 action running, 2 waiting")),
+            indicator_text(3)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{chain, create_gtk_dialog, spec, Configuration};
+use gio::prelude::*;
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/* --hot-reload rebuilds the dialog whenever one of the specs loaded with -c,
+ * or a spec they include, changes. The files are watched with gio file
+ * monitors, which are set up again after every reload as the includes might
+ * have changed. A spec which doesn't parse keeps the current dialog and the
+ * error is printed.
+ */
+
+struct Reload {
+    args: Vec<OsString>,
+    current: RefCell<Option<gtk::Window>>,
+    monitors: RefCell<Vec<gio::FileMonitor>>,
+    pending: Cell<bool>,
+}
+
+fn spec_files(args: &[OsString]) -> Vec<PathBuf> {
+    args.windows(2)
+        .skip(1)
+        .filter(|pair| pair[0] == "-c" || pair[0] == "--config")
+        .map(|pair| PathBuf::from(&pair[1]))
+        .collect()
+}
+
+fn rebuild(args: &[OsString], current: &RefCell<Option<gtk::Window>>) {
+    let mut config = match Configuration::new(args) {
+        Ok(c) => c,
+        Err(e) => {
+            warning!("Not reloaded: {}", e);
+            return;
+        }
+    };
+    let chained = chain::follow(&mut config);
+    let (window, _) = create_gtk_dialog(&config);
+    window.show_all();
+    *chained.borrow_mut() = Some(window.clone());
+    if let Some(old) = current.replace(Some(window)) {
+        old.destroy();
+    }
+}
+
+fn monitor(reload: &Rc<Reload>) {
+    let mut monitors = Vec::new();
+    for path in spec_files(&reload.args).iter().flat_map(|f| spec::files(f)) {
+        let file = gio::File::new_for_path(&path);
+        match file.monitor_file(gio::FileMonitorFlags::NONE, None) {
+            Ok(monitor) => {
+                // Each monitor keeps the reload alive, they only go with the next reload.
+                let reload = reload.clone();
+                monitor.connect_changed(move |_, _, _, event| match event {
+                    gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created => {
+                        schedule(&reload)
+                    }
+                    _ => {}
+                });
+                monitors.push(monitor);
+            }
+            Err(e) => warning!("Can't watch {}: {}", path.display(), e),
+        }
+    }
+    for old in reload.monitors.replace(monitors) {
+        old.cancel();
+    }
+}
+
+// Saving often takes several events, they are reloaded at once.
+fn schedule(reload: &Rc<Reload>) {
+    if reload.pending.replace(true) {
+        return;
+    }
+    let reload = reload.clone();
+    gtk::timeout_add(100, move || {
+        reload.pending.set(false);
+        rebuild(&reload.args, &reload.current);
+        monitor(&reload);
+        gtk::Continue(false)
+    });
+}
+
+pub fn watch(args: &[OsString], window: &gtk::Window) {
+    if spec_files(args).is_empty() {
+        warning!("--hot-reload only watches specs loaded with -c.");
+        return;
+    }
+    let reload = Rc::new(Reload {
+        args: args.to_vec(),
+        current: RefCell::new(Some(window.clone())),
+        monitors: RefCell::new(Vec::new()),
+        pending: Cell::new(false),
+    });
+    monitor(&reload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spec_files;
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    #[test]
+    fn watched_files() {
+        let args: Vec<OsString> = ["app", "-c", "a.toml", "--hot-reload", "--config", "b.json"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        assert_eq!(
+            vec![PathBuf::from("a.toml"), PathBuf::from("b.json")],
+            spec_files(&args)
+        );
+    }
+}
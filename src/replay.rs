@@ -0,0 +1,182 @@
+use crate::{Configuration, FocusTarget, GtkButtons, ParseError};
+use gtk::prelude::*;
+use std::rc::Rc;
+
+/* A replay file contains one step per line, empty lines and lines starting
+ * with '#' are ignored:
+ *
+ *   wait MS          pauses for MS milliseconds
+ *   focus TARGET     moves the focus to a button
+ *   click TARGET     clicks a button
+ *   key KEY          Return, Tab, Shift+Tab or Escape
+ *   type TEXT        inserts TEXT into the focused entry
+ *
+ * TARGET is a button number, a button label or cancel. Buttons with
+ * --hold-to-confirm can't be clicked, they have to be held.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    Wait(u32),
+    Focus(FocusTarget),
+    Click(FocusTarget),
+    Key(Key),
+    Type(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Key {
+    Return,
+    Tab,
+    ShiftTab,
+    Escape,
+}
+
+pub fn parse(content: &str, config: &Configuration) -> Result<Vec<Step>, ParseError> {
+    let mut steps = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (command, argument) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+        let step = parse_step(command, argument, config).map_err(|e| {
+            ParseError::wrong_argument(format!("Line {} of replay file: {}", number + 1, e))
+        })?;
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+fn parse_step(command: &str, argument: &str, config: &Configuration) -> Result<Step, ParseError> {
+    match command {
+        "wait" => argument
+            .parse::<u32>()
+            .map(Step::Wait)
+            .map_err(|_| ParseError::wrong_argument(format!("{} is not a number.", argument))),
+        "focus" => Ok(Step::Focus(config.find_button_target(argument)?)),
+        "click" => match config.find_button_target(argument)? {
+            FocusTarget::Button(i) if config.buttons[i].hold_to_confirm.is_some() => {
+                Err(ParseError::wrong_argument(format!(
+                    "{} has to be held, replay can't click it.",
+                    argument
+                )))
+            }
+            target => Ok(Step::Click(target)),
+        },
+        "key" => match argument.to_ascii_lowercase().as_str() {
+            "return" | "enter" => Ok(Step::Key(Key::Return)),
+            "tab" => Ok(Step::Key(Key::Tab)),
+            "shift+tab" => Ok(Step::Key(Key::ShiftTab)),
+            "escape" => Ok(Step::Key(Key::Escape)),
+            _ => Err(ParseError::wrong_argument(format!(
+                "Unknown key {}.",
+                argument
+            ))),
+        },
+        "type" => Ok(Step::Type(argument.to_string())),
+        _ => Err(ParseError::wrong_argument(format!(
+            "Unknown step {}.",
+            command
+        ))),
+    }
+}
+
+// Executes the steps one after another from the main loop.
+pub fn run(steps: Vec<Step>, window: &gtk::Window, buttons: &GtkButtons) {
+    run_from(Rc::new(steps), 0, window.clone(), buttons.clone());
+}
+
+fn run_from(steps: Rc<Vec<Step>>, index: usize, window: gtk::Window, buttons: GtkButtons) {
+    let step = match steps.get(index) {
+        Some(s) => s.clone(),
+        None => return,
+    };
+    let delay = match step {
+        Step::Wait(ms) => ms,
+        _ => {
+            execute(&step, &window, &buttons);
+            0
+        }
+    };
+    gtk::timeout_add(delay, move || {
+        run_from(steps.clone(), index + 1, window.clone(), buttons.clone());
+        gtk::Continue(false)
+    });
+}
+
+fn execute(step: &Step, window: &gtk::Window, buttons: &GtkButtons) {
+    let target_button = |target: &FocusTarget| match target {
+        FocusTarget::Button(i) => buttons.actions[*i].clone(),
+        FocusTarget::Cancel => buttons.cancel.clone(),
+    };
+    match step {
+        Step::Wait(_) => {}
+        Step::Focus(target) => target_button(target).grab_focus(),
+        Step::Click(target) => target_button(target).clicked(),
+        Step::Key(Key::Return) => {
+            window.activate_focus();
+        }
+        Step::Key(Key::Tab) => {
+            window.child_focus(gtk::DirectionType::TabForward);
+        }
+        Step::Key(Key::ShiftTab) => {
+            window.child_focus(gtk::DirectionType::TabBackward);
+        }
+        Step::Key(Key::Escape) => buttons.cancel.clicked(),
+        Step::Type(text) => {
+            let entry = window
+                .get_focus()
+                .and_then(|w| w.downcast::<gtk::Entry>().ok());
+            match entry {
+                Some(entry) => {
+                    let current = entry.get_text().unwrap_or_default();
+                    entry.set_text(&format!("{}{}", current, text));
+                    entry.set_position(-1);
+                }
+                None => warning!("Replay: the focused widget is no entry."),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Key, Step};
+    use crate::{Configuration, FocusTarget};
+    use std::ffi::OsString;
+
+    #[test]
+    fn parse_steps() {
+        let args: Vec<OsString> = vec!["app", "-B", "_Yes", "true"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        let config = Configuration::new(&args).unwrap();
+        let steps = parse(
+            "# demo\nwait 100\n\nfocus Yes\nkey Enter\nclick cancel\n",
+            &config,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                Step::Wait(100),
+                Step::Focus(FocusTarget::Button(0)),
+                Step::Key(Key::Return),
+                Step::Click(FocusTarget::Cancel),
+            ],
+            steps
+        );
+        assert!(parse("focus 2", &config).is_err());
+        assert!(parse("jump", &config).is_err());
+        let args: Vec<OsString> = vec!["app", "-B", "_Wipe", "true", "--hold-to-confirm", "800"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        let config = Configuration::new(&args).unwrap();
+        assert!(parse("click Wipe", &config).is_err());
+        assert!(parse("focus Wipe", &config).is_ok());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::host_command;
+use std::io::Write;
+use std::process::Stdio;
+use std::sync::mpsc::{channel, TryRecvError};
+
+/* Stores and looks up the entered PIN in the Secret Service keyring (e.g.
+ * gnome-keyring or KeePassXC) through `secret-tool` from libsecret.
+ * secret-tool always uses the generic schema, so the schema name is stored
+ * as an additional attribute. A locked keyring asks for its password first,
+ * so secret-tool runs on separate threads and never blocks the dialog.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecretStore {
+    schema: String,
+    attributes: Vec<(String, String)>,
+}
+
+impl SecretStore {
+    // Parses SCHEMA:KEY=VALUE[,KEY=VALUE]...
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        let (schema, attributes) = match arg.find(':') {
+            Some(i) => (&arg[..i], &arg[i + 1..]),
+            None => {
+                return Err(format!(
+                    "{} has no attributes, expected SCHEMA:KEY=VALUE.",
+                    arg
+                ))
+            }
+        };
+        if schema.is_empty() {
+            return Err(format!("{} has no schema, expected SCHEMA:KEY=VALUE.", arg));
+        }
+        let mut store = SecretStore {
+            schema: String::from(schema),
+            attributes: Vec::new(),
+        };
+        for pair in attributes.split(',') {
+            match pair.find('=') {
+                Some(i) if i > 0 => store
+                    .attributes
+                    .push((String::from(&pair[..i]), String::from(&pair[i + 1..]))),
+                _ => return Err(format!("Attribute {} is not of the form KEY=VALUE.", pair)),
+            }
+        }
+        Ok(store)
+    }
+
+    fn secret_tool(&self, operation: &str) -> std::process::Command {
+        let mut cmd = host_command("secret-tool");
+        cmd.arg(operation);
+        if operation == "store" {
+            cmd.arg(format!("--label={}: {}", crate::PROGRAM_NAME, self.schema));
+        }
+        cmd.arg("schema").arg(&self.schema);
+        for (key, value) in &self.attributes {
+            cmd.arg(key).arg(value);
+        }
+        cmd
+    }
+
+    // The stored secret, None if there is none or the keyring isn't available.
+    fn lookup(&self) -> Option<String> {
+        let output = self
+            .secret_tool("lookup")
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if output.status.success() {
+            Some(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            None
+        }
+    }
+
+    // Calls found from the main loop once the stored secret is there.
+    pub fn lookup_in_background<F: Fn(String) + 'static>(&self, found: F) {
+        let (tx, rx) = channel();
+        let store = self.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(store.lookup());
+        });
+        gtk::timeout_add(100, move || match rx.try_recv() {
+            Ok(Some(secret)) => {
+                found(secret);
+                gtk::Continue(false)
+            }
+            Err(TryRecvError::Empty) => gtk::Continue(true),
+            Ok(None) | Err(TryRecvError::Disconnected) => gtk::Continue(false),
+        });
+    }
+
+    pub fn store_in_background(&self, secret: String) -> std::thread::JoinHandle<()> {
+        let store = self.clone();
+        std::thread::spawn(move || store.store(&secret))
+    }
+
+    fn store(&self, secret: &str) {
+        let result = self
+            .secret_tool("store")
+            .stdin(Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(secret.as_bytes())?;
+                }
+                child.wait()
+            });
+        match result {
+            Ok(status) if status.success() => {}
+            Ok(status) => warning!("Couldn't store secret, secret-tool exited with {}", status),
+            Err(e) => warning!("Couldn't store secret: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SecretStore;
+
+    #[test]
+    fn parse_store() {
+        let store = SecretStore::parse("vpn:host=example.org,user=me").unwrap();
+        assert_eq!("vpn", store.schema);
+        assert_eq!(
+            vec![
+                (String::from("host"), String::from("example.org")),
+                (String::from("user"), String::from("me"))
+            ],
+            store.attributes
+        );
+        assert!(SecretStore::parse("vpn").is_err());
+        assert!(SecretStore::parse(":host=a").is_err());
+        assert!(SecretStore::parse("vpn:host").is_err());
+    }
+}
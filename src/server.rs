@@ -0,0 +1,273 @@
+use crate::{create_gtk_dialog, spec, Configuration, PROGRAM_NAME};
+use gtk::prelude::*;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::rc::Rc;
+use std::sync::mpsc::{channel, Sender};
+
+/* Dialog server for --serve. Every connection sends one dialog spec as a
+ * single line of JSON (see spec.rs) and gets one line of JSON back:
+ *
+ *   {"button": "_Reboot", "index": 0, "pin": "1234"}
+ *   {"button": null}                       cancelled
+ *   {"error": "..."}
+ *
+ * Actions are never run, requests only choose from the shown buttons. Options
+ * touching local files or commands are not accepted from requests either.
+ * There is no authentication: anyone reaching the socket can put dialogs on
+ * the screen and learn the answers, so tcp:PORT only listens on localhost.
+ */
+
+const ALLOWED_OPTIONS: &[&str] = &[
+    "message",
+    "type",
+    "align",
+    "text-scale",
+    "footer",
+    "show-elapsed",
+    "qr",
+    "pin",
+    "padding",
+    "spacing",
+    "border-width",
+    "button-min-width",
+    "focus",
+    "focus-order",
+    "button",
+];
+// Allowed options which read stdin for -, that would be the server's stdin.
+const STDIN_OPTIONS: &[&str] = &["message"];
+const ALLOWED_BUTTON_KEYS: &[&str] = &[
+    "label",
+    "action",
+    "icon",
+    "require-typing",
+    "hold-to-confirm",
+];
+
+// tcp:PORT listens on localhost only, tcp:HOST:PORT on HOST (e.g. 0.0.0.0:PORT for all interfaces).
+fn tcp_address(tcp: &str) -> String {
+    if tcp.contains(':') {
+        String::from(tcp)
+    } else {
+        format!("127.0.0.1:{}", tcp)
+    }
+}
+
+trait Stream: Read + Write + Send {}
+impl<T: Read + Write + Send> Stream for T {}
+
+enum Listener {
+    Tcp(std::net::TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+impl Listener {
+    fn bind(address: &str) -> std::io::Result<Self> {
+        if let Some(tcp) = address.strip_prefix("tcp:") {
+            return Ok(Listener::Tcp(std::net::TcpListener::bind(tcp_address(
+                tcp,
+            ))?));
+        }
+        #[cfg(unix)]
+        {
+            // A left over socket of a previous run would make bind fail.
+            if std::fs::metadata(address)
+                .map(|m| {
+                    use std::os::unix::fs::FileTypeExt;
+                    m.file_type().is_socket()
+                })
+                .unwrap_or(false)
+            {
+                std::fs::remove_file(address)?;
+            }
+            Ok(Listener::Unix(std::os::unix::net::UnixListener::bind(
+                address,
+            )?))
+        }
+        #[cfg(not(unix))]
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Only tcp:PORT is supported here",
+        ))
+    }
+
+    fn accept(&self) -> std::io::Result<Box<dyn Stream>> {
+        match self {
+            Listener::Tcp(l) => Ok(Box::new(l.accept()?.0)),
+            #[cfg(unix)]
+            Listener::Unix(l) => Ok(Box::new(l.accept()?.0)),
+        }
+    }
+}
+
+pub fn run(address: &str) -> i32 {
+    let listener = match Listener::bind(address) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Couldn't listen on {}: {}", address, e);
+            return 1;
+        }
+    };
+    std::thread::spawn(move || loop {
+        match listener.accept() {
+            Ok(stream) => {
+                std::thread::spawn(move || handle(stream));
+            }
+            Err(e) => warning!("Couldn't accept connection: {}", e),
+        }
+    });
+    gtk::main();
+    0
+}
+
+fn handle(mut stream: Box<dyn Stream>) {
+    let mut line = String::new();
+    let reply = match BufReader::new(&mut stream).read_line(&mut line) {
+        Ok(_) => match serde_json::from_str::<Value>(&line) {
+            Ok(request) => {
+                let (tx, rx) = channel();
+                glib::idle_add(move || {
+                    show(&request, tx.clone());
+                    glib::Continue(false)
+                });
+                rx.recv()
+                    .unwrap_or_else(|_| json!({"error": "Dialog was not shown."}))
+            }
+            Err(e) => json!({ "error": e.to_string() }),
+        },
+        Err(e) => json!({ "error": e.to_string() }),
+    };
+    let _ = writeln!(stream, "{}", reply);
+}
+
+fn check_request(request: &Value) -> Result<(), String> {
+    let map = request
+        .as_object()
+        .ok_or_else(|| String::from("The request has to be a JSON object."))?;
+    for (key, value) in map {
+        if !ALLOWED_OPTIONS.contains(&key.as_str()) {
+            return Err(format!("{} is not allowed in requests.", key));
+        }
+        let reads_stdin = match value {
+            Value::Array(items) => items.iter().any(|i| i.as_str() == Some("-")),
+            v => v.as_str() == Some("-"),
+        };
+        if STDIN_OPTIONS.contains(&key.as_str()) && reads_stdin {
+            return Err(format!("{} can't read stdin in requests.", key));
+        }
+        if key == "qr" && value.as_str().is_some_and(|v| v.starts_with('@')) {
+            return Err(String::from("qr can't read files in requests."));
+        }
+        if key == "button" {
+            for button in buttons(value) {
+                for button_key in button.as_object().into_iter().flat_map(|b| b.keys()) {
+                    if !ALLOWED_BUTTON_KEYS.contains(&button_key.as_str()) {
+                        return Err(format!(
+                            "Button key {} is not allowed in requests.",
+                            button_key
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// "button" may be a single table or a list of them.
+fn buttons(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(buttons) => buttons.iter().collect(),
+        button => vec![button],
+    }
+}
+
+// Buttons of requests (and wizard pages) don't need an action, it would never run anyway.
+pub fn with_actions(request: &Value) -> Value {
+    let mut request = request.clone();
+    if let Some(button) = request.get_mut("button") {
+        let buttons: Vec<&mut Value> = match button {
+            Value::Array(buttons) => buttons.iter_mut().collect(),
+            button => vec![button],
+        };
+        for button in buttons.into_iter().filter_map(Value::as_object_mut) {
+            button.entry("action").or_insert_with(|| json!(""));
+            button.insert(String::from("type"), json!("shell"));
+        }
+    }
+    request
+}
+
+fn configuration(request: &Value) -> Result<Configuration, String> {
+    check_request(request)?;
+    let mut args = vec![OsString::from(PROGRAM_NAME)];
+    args.extend(spec::to_args(&spec::with_defaults(&with_actions(request)))?);
+    Configuration::new(&args).map_err(|e| e.to_string())
+}
+
+// Runs in the gtk thread.
+fn show(request: &Value, tx: Sender<Value>) {
+    let mut config = match configuration(request) {
+        Ok(c) => c,
+        Err(e) => {
+            let _ = tx.send(json!({ "error": e }));
+            return;
+        }
+    };
+    let labels: Vec<String> = config.buttons.iter().map(|b| b.label.clone()).collect();
+    let window_slot: Rc<RefCell<Option<gtk::Window>>> = Rc::new(RefCell::new(None));
+    let slot = window_slot.clone();
+    let answered = Rc::new(RefCell::new(false));
+    config.on_choice = Some(Rc::new(move |index: Option<usize>, pin: Option<String>| {
+        if answered.replace(true) {
+            return;
+        }
+        let reply = match index {
+            Some(i) => json!({ "button": labels[i], "index": i, "pin": pin }),
+            None => json!({ "button": null }),
+        };
+        let _ = tx.send(reply);
+        if let Some(window) = slot.borrow_mut().take() {
+            window.destroy();
+        }
+    }));
+    let (window, _) = create_gtk_dialog(&config);
+    let handler = config.on_choice.clone();
+    window.connect_delete_event(move |_, _| {
+        if let Some(h) = &handler {
+            h(None, None);
+        }
+        Inhibit(false)
+    });
+    window.show_all();
+    *window_slot.borrow_mut() = Some(window);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_request, tcp_address, with_actions};
+    use serde_json::json;
+
+    #[test]
+    fn requests_are_restricted() {
+        assert!(check_request(&json!({"message": "Hi", "button": [{"label": "Yes"}]})).is_ok());
+        assert!(check_request(&json!({"audit-log": "/tmp/x"})).is_err());
+        assert!(check_request(&json!({"qr": "@/etc/passwd"})).is_err());
+        assert!(check_request(&json!({"message": "-"})).is_err());
+        assert!(check_request(&json!({"message": ["Hi", "-"]})).is_err());
+        assert!(check_request(&json!({"button": {"label": "Yes", "type": "http"}})).is_err());
+        assert!(check_request(&json!({"button": [{"label": "Yes", "type": "http"}]})).is_err());
+        let request = with_actions(&json!({"button": [{"label": "Yes", "action": "rm -rf ~"}]}));
+        assert_eq!(json!("shell"), request["button"][0]["type"]);
+    }
+
+    #[test]
+    fn tcp_defaults_to_localhost() {
+        assert_eq!("127.0.0.1:7878", tcp_address("7878"));
+        assert_eq!("0.0.0.0:7878", tcp_address("0.0.0.0:7878"));
+    }
+}
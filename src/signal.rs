@@ -0,0 +1,49 @@
+use dbus::{BusType, Connection, Message};
+use std::cell::RefCell;
+
+/* Broadcasts the result of the dialog on the session bus when it exits:
+ *
+ *   org.frankE.OptionsWindowGtk.ButtonActivated(instance, label, index)
+ *
+ * Cancel is reported with index -1, closing the window without any button
+ * with an empty label and index -1. Nobody has to listen, so a missing
+ * session bus is not worth a warning.
+ */
+
+const PATH: &str = "/org/frankE/OptionsWindowGtk";
+const INTERFACE: &str = "org.frankE.OptionsWindowGtk";
+
+thread_local! {
+    // Only used in the gtk thread.
+    static ACTIVATED: RefCell<Option<(String, i32)>> = const { RefCell::new(None) };
+}
+
+pub fn default_instance() -> String {
+    format!("{}-{}", crate::PROGRAM_NAME, std::process::id())
+}
+
+// The last pressed button wins, e.g. when --exit-after-action isn't given.
+pub fn button_activated(label: &str, index: Option<usize>) {
+    let index = index.map_or(-1, |i| i as i32);
+    ACTIVATED.with(|a| *a.borrow_mut() = Some((String::from(label), index)));
+}
+
+pub fn emit(instance: &str) {
+    let (label, index) = ACTIVATED
+        .with(|a| a.borrow_mut().take())
+        .unwrap_or_else(|| (String::new(), -1));
+    let connection = match Connection::get_private(BusType::Session) {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    let message = match Message::new_signal(PATH, INTERFACE, "ButtonActivated") {
+        Ok(m) => m.append3(instance, label, index),
+        Err(e) => {
+            warning!("Couldn't create ButtonActivated signal: {}", e);
+            return;
+        }
+    };
+    if connection.send(message).is_err() {
+        warning!("Couldn't send ButtonActivated signal.");
+    }
+}
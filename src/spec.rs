@@ -0,0 +1,1540 @@
+use crate::{Configuration, ParseError, ParseErrorType};
+use serde_json::Value;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/* A dialog spec is a TOML, JSON or YAML file describing the same things as the
+ * command line. Every top level key is the long name of an option:
+ *
+ *   message = "Disk almost full"
+ *   type = "warning"
+ *   exit-after-action = true
+ *
+ *   [[button]]
+ *   label = "_Clean up"
+ *   action = "ncdu /"
+ *   icon = "edit-clear"
+ *   type = "terminal"        # terminal (-b), shell (-B), open (-o), tmux or http
+ *   require-typing = "yes"   # further keys are button modifiers
+ *
+ * true enables a flag, false leaves it out and arrays repeat the option.
+ * include = ["other.toml"] merges other specs, see include().
+ * The spec is translated into arguments, so the regular parser validates it.
+ */
+
+pub struct SpecError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl SpecError {
+    fn new<T: Into<String>>(line: Option<usize>, message: T) -> Self {
+        SpecError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+enum Format {
+    Toml,
+    Json,
+    Yaml,
+}
+
+fn format_of(path: &Path) -> Result<Format, SpecError> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "toml" => Ok(Format::Toml),
+        "json" => Ok(Format::Json),
+        "yaml" | "yml" => Ok(Format::Yaml),
+        _ => Err(SpecError::new(
+            None,
+            "Unknown file type, expected .toml, .json, .yaml or .yml",
+        )),
+    }
+}
+
+// Returns the source text as well, it is needed to look up line numbers.
+pub fn read(path: &Path) -> Result<(String, Value), SpecError> {
+    read_nested(path, 0)
+}
+
+// Includes of includes are fine, but not endlessly.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/* include = ["buttons/common.toml"] merges other specs into this one, paths
+ * are relative to the including spec. Its own options take precedence, the
+ * included buttons follow its own ones.
+ */
+fn include(mut value: Value, source: &str, path: &Path, depth: usize) -> Result<Value, SpecError> {
+    let map = match &mut value {
+        Value::Object(map) => map,
+        _ => return Ok(value),
+    };
+    let error = |message: String| SpecError::new(locate(source, "include", 0), message);
+    let names: Vec<String> = match map.remove("include") {
+        None => return Ok(value),
+        Some(Value::String(name)) => vec![name],
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|i| i.as_str().map(String::from))
+            .collect::<Option<_>>()
+            .ok_or_else(|| error(String::from("include has to be a list of paths.")))?,
+        Some(_) => return Err(error(String::from("include has to be a list of paths."))),
+    };
+    if depth >= MAX_INCLUDE_DEPTH {
+        return Err(error(String::from(
+            "Includes are nested too deeply, does a spec include itself?",
+        )));
+    }
+    let base = path.parent().unwrap_or_else(|| Path::new("."));
+    for name in names {
+        let included_path = base.join(&name);
+        let (_, included) = read_nested(&included_path, depth + 1).map_err(|e| {
+            error(match e.line {
+                Some(line) => format!("{}:{}: {}", name, line, e.message),
+                None => format!("{}: {}", name, e.message),
+            })
+        })?;
+        let included = match included {
+            Value::Object(options) => options,
+            _ => {
+                return Err(error(format!(
+                    "{}: The spec has to be a table of options.",
+                    name
+                )))
+            }
+        };
+        for (key, item) in included {
+            if key == "button" {
+                let mut buttons = match map.remove("button") {
+                    Some(Value::Array(own)) => own,
+                    Some(own) => vec![own],
+                    None => Vec::new(),
+                };
+                match item {
+                    Value::Array(more) => buttons.extend(more),
+                    more => buttons.push(more),
+                }
+                map.insert(key, Value::Array(buttons));
+            } else {
+                map.entry(key).or_insert(item);
+            }
+        }
+    }
+    Ok(value)
+}
+
+fn read_nested(path: &Path, depth: usize) -> Result<(String, Value), SpecError> {
+    let (source, value) = parse(path)?;
+    let value = include(value, &source, path, depth)?;
+    Ok((source, value))
+}
+
+fn parse(path: &Path) -> Result<(String, Value), SpecError> {
+    let format = format_of(path)?;
+    let source = std::fs::read_to_string(path).map_err(|e| SpecError::new(None, e.to_string()))?;
+    let value = match format {
+        Format::Toml => toml::from_str::<Value>(&source)
+            .map_err(|e| SpecError::new(e.line_col().map(|(l, _)| l + 1), e.to_string())),
+        Format::Json => serde_json::from_str::<Value>(&source)
+            .map_err(|e| SpecError::new(Some(e.line()), e.to_string())),
+        Format::Yaml => serde_yaml::from_str::<Value>(&source)
+            .map_err(|e| SpecError::new(e.location().map(|l| l.line()), e.to_string())),
+    }?;
+    Ok((source, value))
+}
+
+/* The spec and all specs it includes, for --hot-reload. Broken includes are
+ * listed as well, fixing them is a change to watch for.
+ */
+pub fn files(path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_files(path, 0, &mut files);
+    files
+}
+
+fn collect_files(path: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    if files.iter().any(|f| f == path) {
+        return;
+    }
+    files.push(path.to_path_buf());
+    let names = match parse(path) {
+        Ok((_, value)) => match value.get("include") {
+            Some(Value::String(name)) => vec![name.clone()],
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(|i| i.as_str().map(String::from))
+                .collect(),
+            _ => Vec::new(),
+        },
+        Err(_) => Vec::new(),
+    };
+    if depth >= MAX_INCLUDE_DEPTH {
+        return;
+    }
+    let base = path.parent().unwrap_or_else(|| Path::new("."));
+    for name in names {
+        collect_files(&base.join(name), depth + 1, files);
+    }
+}
+
+fn option_name(key: &str) -> OsString {
+    if key.chars().count() == 1 {
+        OsString::from(format!("-{}", key))
+    } else {
+        OsString::from(format!("--{}", key))
+    }
+}
+
+fn scalar(value: &Value) -> Option<OsString> {
+    match value {
+        Value::String(s) => Some(OsString::from(s)),
+        Value::Number(n) => Some(OsString::from(n.to_string())),
+        _ => None,
+    }
+}
+
+// Prompts which take all their items (--scale its numbers) after a single option.
+const LIST_OPTIONS: &[&str] = &["checklist", "radiolist", "combo", "scale"];
+
+fn option_args(key: &str, value: &Value) -> Result<Vec<OsString>, String> {
+    let mut args = Vec::new();
+    match value {
+        Value::Bool(true) => args.push(option_name(key)),
+        Value::Bool(false) | Value::Null => {}
+        Value::Array(items) if LIST_OPTIONS.contains(&key) => {
+            args.push(option_name(key));
+            for item in items {
+                args.push(
+                    scalar(item)
+                        .ok_or_else(|| format!("{} can only list strings and numbers.", key))?,
+                );
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                args.extend(option_args(key, item)?);
+            }
+        }
+        Value::Object(_) => return Err(format!("{} can't be a table.", key)),
+        v => {
+            args.push(option_name(key));
+            args.push(scalar(v).unwrap_or_default());
+        }
+    }
+    Ok(args)
+}
+
+// secondary = { label = "_Logs", action = "journalctl -f" }
+fn secondary_args(value: &Value) -> Result<Vec<OsString>, String> {
+    let label = value.get("label").and_then(scalar);
+    let action = value.get("action").and_then(scalar);
+    match (label, action) {
+        (Some(label), Some(action)) => Ok(vec![OsString::from("--secondary"), label, action]),
+        _ => Err(String::from("secondary needs a label and an action.")),
+    }
+}
+
+fn button_args(button: &Value) -> Result<Vec<OsString>, String> {
+    let fields = match button {
+        Value::Object(fields) => fields,
+        _ => return Err(String::from("A button has to be a table.")),
+    };
+    // Option, positional keys and the optional last positional key of each type.
+    let (option, required, optional): (&str, &[&str], &str) =
+        match fields.get("type").and_then(Value::as_str) {
+            None | Some("terminal") => ("-b", &["label", "action"], "icon"),
+            Some("shell") => ("-B", &["label", "action"], "icon"),
+            Some("open") => ("-o", &["label", "action"], "icon"),
+            Some("tmux") => ("--button-tmux", &["label", "action"], "icon"),
+            Some("http") => ("--button-http", &["label", "method", "url"], "body"),
+            Some(t) => {
+                return Err(format!(
+                    "Button type {} is neither terminal, shell, open, tmux nor http.",
+                    t
+                ))
+            }
+        };
+    // A tab starts before its first button.
+    let mut args = option_args("tab", fields.get("tab").unwrap_or(&Value::Null))?;
+    args.push(OsString::from(option));
+    for key in required {
+        match fields.get(*key).and_then(scalar) {
+            Some(v) => args.push(v),
+            None => return Err(format!("Button is missing its {}.", key)),
+        }
+    }
+    if let Some(value) = fields.get(optional).and_then(scalar) {
+        args.push(value);
+    }
+    for (key, value) in fields {
+        if key == "secondary" {
+            args.extend(secondary_args(value)?);
+        } else if key != "type"
+            && key != "tab"
+            && key != optional
+            && !required.contains(&key.as_str())
+        {
+            args.extend(option_args(key, value)?);
+        }
+    }
+    Ok(args)
+}
+
+pub fn top_level(spec: &Value) -> Result<&serde_json::Map<String, Value>, String> {
+    match spec {
+        Value::Object(map) => match map.get("config") {
+            Some(_) => Err(String::from("A spec can't load another spec via config.")),
+            None => Ok(map),
+        },
+        _ => Err(String::from("The spec has to be a table of options.")),
+    }
+}
+
+pub fn buttons(spec: &serde_json::Map<String, Value>) -> Vec<&Value> {
+    match spec.get("button") {
+        Some(Value::Array(buttons)) => buttons.iter().collect(),
+        Some(button) => vec![button],
+        None => Vec::new(),
+    }
+}
+
+pub fn to_args(spec: &Value) -> Result<Vec<OsString>, String> {
+    let map = top_level(spec)?;
+    let mut args = Vec::new();
+    for (key, value) in map {
+        if key != "button" {
+            args.extend(option_args(key, value)?);
+        }
+    }
+    for button in buttons(map) {
+        args.extend(button_args(button)?);
+    }
+    Ok(args)
+}
+
+// Replaces every `-c FILE` / `--config FILE` with the options of the spec.
+pub fn expand(args: &[OsString]) -> Result<Vec<OsString>, ParseError> {
+    let mut expanded = Vec::with_capacity(args.len());
+    let mut pos = 0;
+    while pos < args.len() {
+        let a = &args[pos];
+        if pos > 0 && (a.eq("-c") || a.eq("--config")) {
+            pos += 1;
+            let path = match args.get(pos) {
+                Some(p) => Path::new(p),
+                None => {
+                    return Err(ParseError::missing_argument(
+                        "Required argument for --config is missing.",
+                    ))
+                }
+            };
+            let spec_args = read(path)
+                .map_err(|e| e.message)
+                .and_then(|(_, spec)| to_args(&spec))
+                .map_err(|e| {
+                    ParseError::wrong_argument(format!("{}: {}", path.to_string_lossy(), e))
+                })?;
+            expanded.extend(spec_args);
+        } else {
+            expanded.push(a.clone());
+        }
+        pos += 1;
+    }
+    Ok(expanded)
+}
+
+/* Config files applied to every dialog, least important first:
+ * /etc/options-window-gtk/config.toml, config.toml in $XDG_CONFIG_DIRS (the
+ * first directory is the most important) and in $XDG_CONFIG_HOME.
+ */
+fn default_config_files(
+    config_dirs: Option<OsString>,
+    config_home: Option<PathBuf>,
+) -> Vec<PathBuf> {
+    let file = |dir: &Path| dir.join(crate::PROGRAM_NAME).join("config.toml");
+    let mut files = vec![file(Path::new("/etc"))];
+    let config_dirs = config_dirs
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| OsString::from("/etc/xdg"));
+    let dirs: Vec<PathBuf> = std::env::split_paths(&config_dirs).collect();
+    files.extend(dirs.iter().rev().map(|d| file(d)));
+    files.extend(config_home.map(|d| file(&d)));
+    files
+}
+
+type Options = serde_json::Map<String, Value>;
+
+// Buttons would end up in every dialog, so only options are taken.
+fn default_options(path: &Path) -> Result<Options, String> {
+    let (_, spec) = read(path).map_err(|e| e.message)?;
+    let mut options = top_level(&spec)?.clone();
+    if options.remove("button").is_some() {
+        warning!(
+            "Buttons in {} are ignored, it is applied to every dialog.",
+            path.to_string_lossy()
+        );
+    }
+    // A file which can't be turned into options is ignored as a whole.
+    to_args(&Value::Object(options.clone()))?;
+    Ok(options)
+}
+
+/* Options which repeat, clash or replace each other. A default of one of them
+ * is dropped as soon as another one of its group is given.
+ */
+const EXCLUSIVE: &[&[&str]] = &[
+    &["message", "message-file"],
+    &["delay", "in", "at"],
+    &["if-failed", "if-succeeds"],
+    &["terminal", "terminal-cmd"],
+    &[
+        "pin",
+        "password",
+        "checklist",
+        "radiolist",
+        "combo",
+        "scale",
+    ],
+    &["bar", "width", "height", "position", "offset", "attach"],
+    &["bar", "headerbar", "footer", "columns"],
+    &["exit-after-action", "show-status", "queue"],
+];
+
+// Spec keys of the short options.
+const SHORT_OPTIONS: &[(&str, &str)] = &[
+    ("-m", "message"),
+    ("-t", "type"),
+    ("-f", "font"),
+    ("-q", "quiet"),
+];
+
+fn overridden(key: &str, given: &[String]) -> bool {
+    given.iter().any(|g| {
+        g == key
+            || EXCLUSIVE
+                .iter()
+                .any(|group| group.contains(&key) && group.contains(&g.as_str()))
+    })
+}
+
+/* The defaults without the options set by a spec or the command line, so
+ * these win even where the defaults would be added to them (-m), clash with
+ * them (--bar and --width) or couldn't be switched off (flags set to false).
+ */
+fn remaining_defaults(defaults: &Options, given: &[String]) -> Options {
+    defaults
+        .iter()
+        .filter(|(key, _)| !overridden(key, given))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+// Spec keys set on the command line, directly or by the specs loaded with -c.
+fn given_keys(args: &[OsString]) -> Vec<String> {
+    let mut keys = Vec::new();
+    for (i, arg) in args.iter().enumerate().skip(1) {
+        let arg = arg.to_string_lossy();
+        if arg == "-c" || arg == "--config" {
+            // A spec which can't be read is reported when it is expanded.
+            if let Some(Ok((_, Value::Object(spec)))) = args.get(i + 1).map(|p| read(Path::new(p)))
+            {
+                keys.extend(spec.keys().cloned());
+            }
+        } else if let Some(long) = arg.strip_prefix("--") {
+            keys.push(String::from(long));
+        } else if let Some((_, key)) = SHORT_OPTIONS.iter().find(|(short, _)| arg == *short) {
+            keys.push(String::from(*key));
+        }
+    }
+    keys
+}
+
+fn default_args(defaults: &Options, args: &[OsString]) -> Vec<OsString> {
+    let remaining = remaining_defaults(defaults, &given_keys(args));
+    to_args(&Value::Object(remaining)).unwrap_or_default()
+}
+
+// Set by with_default_configs for the specs of --chain, --wizard and --serve.
+static DEFAULTS: Mutex<Option<Options>> = Mutex::new(None);
+
+fn merge_defaults(defaults: &Options, spec: &Value) -> Value {
+    match spec {
+        Value::Object(options) => {
+            let given: Vec<String> = options.keys().cloned().collect();
+            let mut merged = remaining_defaults(defaults, &given);
+            merged.extend(options.clone());
+            Value::Object(merged)
+        }
+        _ => spec.clone(),
+    }
+}
+
+// A spec shown later on, with the defaults it doesn't replace.
+pub fn with_defaults(spec: &Value) -> Value {
+    match &*DEFAULTS.lock().unwrap() {
+        Some(defaults) => merge_defaults(defaults, spec),
+        None => spec.clone(),
+    }
+}
+
+/* Merges the options of the default config files, later files replace
+ * options of earlier ones, and puts those the command line doesn't set in
+ * front of it. The style.css next to the user config is given as --css
+ * before the command line as well. --no-config skips them.
+ */
+pub fn with_default_configs(args: &[OsString]) -> Vec<OsString> {
+    if args.is_empty() || args[1..].iter().any(|a| a == "--no-config") {
+        return args.to_vec();
+    }
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")));
+    let mut defaults = Options::new();
+    for path in default_config_files(std::env::var_os("XDG_CONFIG_DIRS"), config_home.clone()) {
+        if !path.is_file() {
+            continue;
+        }
+        match default_options(&path) {
+            Ok(options) => defaults.extend(options),
+            Err(e) => warning!("Ignoring {}: {}", path.to_string_lossy(), e),
+        }
+    }
+    let mut layered = vec![args[0].clone()];
+    layered.extend(default_args(&defaults, args));
+    *DEFAULTS.lock().unwrap() = Some(defaults);
+    if let Some(home) = config_home {
+        let style = home.join(crate::PROGRAM_NAME).join("style.css");
+        if style.is_file() {
+            layered.push(OsString::from("--css"));
+            layered.push(style.into_os_string());
+        }
+    }
+    layered.extend_from_slice(&args[1..]);
+    layered
+}
+
+fn toml_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Key, description, default and example of every option in the dump.
+fn config_options() -> Vec<(&'static str, &'static str, Option<String>, String)> {
+    let defaults = Configuration::new(&[OsString::from(crate::PROGRAM_NAME)])
+        .unwrap_or_else(|e| panic!("Defaults have to parse: {}", e));
+    let number = |n: Option<u32>| n.map(|n| n.to_string());
+    vec![
+        (
+            "message",
+            "Window caption, an array of strings gives several paragraphs",
+            Some(toml_string(&defaults.message)),
+            String::new(),
+        ),
+        (
+            "type",
+            "info, warning or error. Defines the window icon and accent",
+            Some(toml_string(defaults.message_type.name())),
+            String::new(),
+        ),
+        (
+            "cancel-label",
+            "Label of the Cancel button",
+            Some(toml_string(&defaults.cancel_label)),
+            String::new(),
+        ),
+        (
+            "cancel-action",
+            "Shell command run when Cancel is pressed",
+            None,
+            toml_string("logger 'update reminder dismissed'"),
+        ),
+        (
+            "layout",
+            "horizontal or vertical. How the buttons are arranged",
+            Some(toml_string("vertical")),
+            String::new(),
+        ),
+        (
+            "columns",
+            "Arrange the buttons in a grid with this many columns",
+            number(defaults.columns),
+            String::from("3"),
+        ),
+        (
+            "no-cancel",
+            "Leave out the Cancel button, every way out goes through a button",
+            Some(defaults.no_cancel.to_string()),
+            String::new(),
+        ),
+        (
+            "exit-after-action",
+            "Exit after a button press",
+            Some(defaults.exit_after_action.to_string()),
+            String::new(),
+        ),
+        (
+            "hot-reload",
+            "Rebuild the dialog when this spec changes, for working on its layout",
+            Some(defaults.hot_reload.to_string()),
+            String::new(),
+        ),
+        (
+            "wizard",
+            "Spec with a list of pages shown with Back and Next, the answers are printed as JSON",
+            None,
+            toml_string("setup.toml"),
+        ),
+        (
+            "survey",
+            "JSON list of questions asked one after another, the answers are printed as JSON",
+            None,
+            toml_string("standup.json"),
+        ),
+        (
+            "chain",
+            "Spec shown next, {result} and {result_index} are replaced, the actions get the PIN",
+            None,
+            toml_string("actions.toml"),
+        ),
+        (
+            "strict",
+            "Unknown icons, non-UTF-8 labels, duplicate accelerators and no buttons are errors",
+            Some(defaults.strict.to_string()),
+            String::new(),
+        ),
+        (
+            "if-failed",
+            "Command probing for a problem, the window is only shown if it fails",
+            None,
+            toml_string("systemctl is-active --quiet backup.service"),
+        ),
+        (
+            "if-succeeds",
+            "Command probing for a problem, the window is only shown if it succeeds",
+            None,
+            toml_string("test -e /var/run/reboot-required"),
+        ),
+        (
+            "dedup",
+            "Key of the dialog, it isn't shown again while one with the same key is open",
+            None,
+            toml_string("battery-low"),
+        ),
+        (
+            "delay",
+            "Seconds to wait before the window is shown",
+            None,
+            String::from("30"),
+        ),
+        (
+            "timeout",
+            "Seconds without interaction after which the dialog is cancelled",
+            None,
+            String::from("300"),
+        ),
+        (
+            "in",
+            "Wait this long before showing the dialog, like 90s, 15m or 1h30m",
+            None,
+            toml_string("15m"),
+        ),
+        (
+            "at",
+            "Wait until the next time the clock shows this time",
+            None,
+            toml_string("17:30"),
+        ),
+        (
+            "cancel-if",
+            "Command run every second during delay, the window isn't shown once it succeeds",
+            None,
+            toml_string("! pgrep -x make"),
+        ),
+        (
+            "close-when",
+            "Command run while the window is shown, it closes once the command succeeds",
+            None,
+            toml_string("ip link show tun0"),
+        ),
+        (
+            "poll-interval",
+            "Seconds between the runs of close-when, 5 if unset",
+            None,
+            String::from("10"),
+        ),
+        (
+            "queue",
+            "Run actions one after another instead of all at once, with an indicator of waiting ones",
+            Some(defaults.queue.to_string()),
+            String::new(),
+        ),
+        (
+            "show-status",
+            "Show ✓ or ✗ with the end of stderr as tooltip next to each button after it ran",
+            Some(defaults.show_status.to_string()),
+            String::new(),
+        ),
+        (
+            "notify-result",
+            "Send a desktop notification with result and runtime when an action finishes",
+            Some(defaults.notify_result.to_string()),
+            String::new(),
+        ),
+        (
+            "print-pid",
+            "Append PID, process group and label of each started action to this file, \"-\" is stdout",
+            None,
+            toml_string("/run/user/1000/actions.pid"),
+        ),
+        (
+            "print",
+            "Prints the pressed button (label or index) or the value instead of running its action",
+            None,
+            toml_string("label"),
+        ),
+        (
+            "pipe-to",
+            "Command which gets the pressed button's label (and PIN) on stdin",
+            None,
+            toml_string("wl-copy"),
+        ),
+        (
+            "quiet",
+            "Only write the result to stdout, no warnings",
+            Some(String::from("false")),
+            String::new(),
+        ),
+        (
+            "pin",
+            "Show a PIN keypad, true or the PIN length. The PIN is written to the action's stdin",
+            Some(defaults.pin.to_string()),
+            String::new(),
+        ),
+        (
+            "password",
+            "Ask for a password, OK prints it unless there are buttons to pass it to",
+            Some(String::from("false")),
+            String::new(),
+        ),
+        (
+            "checklist",
+            "Items shown as check boxes, OK prints the checked ones",
+            None,
+            String::from("[\"Clean cache\", \"Rotate logs\"]"),
+        ),
+        (
+            "radiolist",
+            "Items shown as radio buttons, OK prints the selected one",
+            None,
+            String::from("[\"Speakers\", \"Headphones\"]"),
+        ),
+        (
+            "combo",
+            "Items shown as a drop-down list, OK prints the chosen one",
+            None,
+            String::from("[\"HDMI-1\", \"eDP-1\"]"),
+        ),
+        (
+            "editable",
+            "Allow typing a value besides the combo items",
+            Some(String::from("false")),
+            String::new(),
+        ),
+        (
+            "scale",
+            "Minimum, maximum, step and initial value of a slider, OK prints the value",
+            None,
+            String::from("[0, 100, 5, 50]"),
+        ),
+        (
+            "secret-via",
+            "stdin, env:NAME or fd. How the PIN reaches the action, fd replaces {secret_fd}",
+            Some(toml_string("stdin")),
+            String::new(),
+        ),
+        (
+            "store-secret",
+            "Keyring entry SCHEMA:KEY=VALUE,... the PIN is pre-filled from and stored to",
+            None,
+            toml_string("vpn:host=example.org"),
+        ),
+        (
+            "qr",
+            "Show TEXT (or the content of @FILE) as QR code",
+            None,
+            toml_string("https://example.org"),
+        ),
+        (
+            "show-elapsed",
+            "Show how long ago the window appeared",
+            Some(defaults.show_elapsed.to_string()),
+            String::new(),
+        ),
+        (
+            "image",
+            "Image or animation shown below the message",
+            None,
+            toml_string("/path/to/image.png"),
+        ),
+        (
+            "footer",
+            "Small line at the bottom",
+            None,
+            toml_string("Sent by cron"),
+        ),
+        (
+            "title",
+            "Title of the window, seen by taskbars and window manager rules",
+            Some(toml_string("options-window-gtk")),
+            String::new(),
+        ),
+        (
+            "class",
+            "WM_CLASS on X11 and app_id on Wayland, for window manager rules",
+            None,
+            toml_string("my-nag"),
+        ),
+        (
+            "padding",
+            "Padding around each button in pixels",
+            Some(defaults.padding.to_string()),
+            String::new(),
+        ),
+        (
+            "spacing",
+            "Space between widgets in pixels",
+            Some(defaults.spacing.to_string()),
+            String::new(),
+        ),
+        (
+            "icon-size",
+            "Size of the message icon, a GTK icon size like button or dialog, or pixels",
+            Some(toml_string("dialog")),
+            String::new(),
+        ),
+        (
+            "button-icon-size",
+            "Size of the button icons, a GTK icon size or pixels",
+            Some(toml_string("button")),
+            String::from("24"),
+        ),
+        (
+            "border-width",
+            "Border around the window content in pixels",
+            Some(defaults.border_width.to_string()),
+            String::new(),
+        ),
+        (
+            "button-min-width",
+            "Minimum width of every button in pixels",
+            number(defaults.button_min_width),
+            String::from("120"),
+        ),
+        (
+            "width",
+            "Width of the window in pixels, the message wraps to fit",
+            number(defaults.width),
+            String::from("400"),
+        ),
+        (
+            "height",
+            "Minimum height of the window in pixels",
+            number(defaults.height),
+            String::from("200"),
+        ),
+        (
+            "position",
+            "center, top, bottom, left, right, a corner like top-left, or X,Y",
+            Some(toml_string("center")),
+            toml_string("bottom-right"),
+        ),
+        (
+            "offset",
+            "X,Y in pixels away from the edge of position",
+            None,
+            toml_string("20,40"),
+        ),
+        (
+            "no-keep-above",
+            "Let other windows cover the dialog",
+            Some(defaults.no_keep_above.to_string()),
+            String::new(),
+        ),
+        (
+            "no-sticky",
+            "Show the dialog only on the current workspace",
+            Some(defaults.no_sticky.to_string()),
+            String::new(),
+        ),
+        (
+            "no-urgent",
+            "Don't set the urgency hint",
+            Some(defaults.no_urgent.to_string()),
+            String::new(),
+        ),
+        (
+            "headerbar",
+            "Put the title and a close button into a header bar",
+            Some(defaults.headerbar.to_string()),
+            String::new(),
+        ),
+        (
+            "attach",
+            "X11 window id the dialog is kept above",
+            None,
+            toml_string("0x3a00007"),
+        ),
+        (
+            "focus",
+            "Button which gets the initial focus (number or label), or cancel",
+            None,
+            toml_string("cancel"),
+        ),
+        (
+            "default",
+            "Button (label or number) which Enter activates instead of cancel",
+            None,
+            toml_string("_Reboot now"),
+        ),
+        (
+            "focus-order",
+            "Tab order, numbers or labels",
+            None,
+            toml_string("2,1,cancel"),
+        ),
+        (
+            "audit-log",
+            "Append shown dialogs and pressed buttons to this file",
+            None,
+            toml_string("/path/to/audit.log"),
+        ),
+        (
+            "screenshot",
+            "Save the window as PNG and exit",
+            None,
+            toml_string("/path/to/shot.png"),
+        ),
+        (
+            "dump-ui",
+            "Print the widget hierarchy and exit",
+            Some(defaults.dump_ui.to_string()),
+            String::new(),
+        ),
+        (
+            "diagnose",
+            "Print which terminal, display, icon theme and runtime directory would be used and exit",
+            Some(defaults.diagnose.to_string()),
+            String::new(),
+        ),
+        (
+            "preview-types",
+            "Show sample dialogs of every type and layout with the styling options",
+            Some(defaults.preview_types.to_string()),
+            String::new(),
+        ),
+        (
+            "align",
+            "left, center or right. Alignment of the message, GTK decides if unset",
+            None,
+            toml_string("left"),
+        ),
+        (
+            "message-file",
+            "File with the window caption, for logs and other long text",
+            None,
+            toml_string("/var/log/backup.log"),
+        ),
+        (
+            "markup",
+            "The message is Pango markup instead of plain text",
+            Some(defaults.markup.to_string()),
+            String::new(),
+        ),
+        (
+            "css",
+            "GTK CSS file for the dialog, instead of ~/.config/options-window-gtk/style.css",
+            None,
+            toml_string("dialog.css"),
+        ),
+        (
+            "font",
+            "Pango font description for the whole dialog",
+            None,
+            toml_string("DejaVu Sans 10"),
+        ),
+        (
+            "text-scale",
+            "Multiplies the font size of the message",
+            None,
+            String::from("1.5"),
+        ),
+        (
+            "serve",
+            "Socket path or tcp:[HOST:]PORT to serve dialogs requested as JSON on",
+            None,
+            toml_string("tcp:7878"),
+        ),
+        (
+            "polkit-agent",
+            "Run as PolicyKit authentication agent",
+            Some(String::from("false")),
+            String::new(),
+        ),
+        (
+            "instance",
+            "Name sent with the ButtonActivated D-Bus signal on exit",
+            None,
+            toml_string("backup"),
+        ),
+        (
+            "gtk-backend",
+            "broadway, or offscreen for screenshot and dump-ui",
+            None,
+            toml_string("offscreen"),
+        ),
+        (
+            "backend",
+            "x11 or wayland. Forces the display backend",
+            None,
+            toml_string("x11"),
+        ),
+        (
+            "bar",
+            "Docks the dialog as a strip to the top of the screen",
+            Some(defaults.bar.to_string()),
+            String::new(),
+        ),
+        (
+            "terminal",
+            "Terminal for terminal buttons, defaults to $TERMINAL, then i3-sensible-terminal",
+            None,
+            toml_string("foot"),
+        ),
+        (
+            "terminal-cmd",
+            "Command template for terminal buttons, {} is replaced with the command",
+            None,
+            toml_string("foot -e {}"),
+        ),
+        (
+            "terminal-mode",
+            "auto, spawn or remote. remote opens commands in a running kitty or foot server",
+            Some(toml_string("auto")),
+            String::new(),
+        ),
+        (
+            "dark",
+            "Prefer the dark variant of the gtk theme, light = true prefers the light one",
+            None,
+            String::from("true"),
+        ),
+        (
+            "gtk-theme",
+            "gtk theme of this window instead of the session's",
+            None,
+            toml_string("Adwaita-dark"),
+        ),
+        (
+            "icon-theme",
+            "Icon theme of this window instead of the session's",
+            None,
+            toml_string("Adwaita"),
+        ),
+        #[cfg(feature = "scripting")]
+        (
+            "replay",
+            "Replay scripted interaction from this file",
+            None,
+            toml_string("/path/to/steps"),
+        ),
+    ]
+}
+
+/* An example spec with every option commented out. Values are the compiled-in
+ * defaults, options without a default show an example instead.
+ */
+pub fn dump_config() -> String {
+    let mut out = format!(
+        "# Dialog spec for {} {}, load it with -c FILE.\n\
+         # Every option is commented out and shows its default or an example.\n",
+        crate::PROGRAM_NAME,
+        crate::VERSION
+    );
+    for (key, description, default, example) in config_options() {
+        out.push('\n');
+        out.push_str(&format!("# {}.\n", description));
+        match default {
+            Some(value) => out.push_str(&format!("# Default: {}\n# {} = {}\n", value, key, value)),
+            None => out.push_str(&format!("# {} = {}\n", key, example)),
+        }
+    }
+    out.push_str(
+        "\n# Buttons, repeat the table for more. type is terminal, shell, open, tmux or http,\n\
+         # http buttons have method, url and body instead of action and icon,\n\
+         # further keys modify the button.\n\
+         # [[button]]\n\
+         # label = \"_Reboot\"\n\
+         # action = \"systemctl reboot\"\n\
+         # icon = \"system-reboot\"\n\
+         # icon-position = \"left\"\n\
+         # type = \"shell\"\n\
+         # require-typing = \"reboot\"\n\
+         # hold-to-confirm = 1000\n\
+         # code = 3\n\
+         # keep-open = true\n\
+         # tooltip = \"Reboots right away\"\n\
+         # style = \"destructive\"\n\
+         # confirm = \"Reboot now?\"\n\
+         # secondary = { label = \"_Logs\", action = \"journalctl -b\" }\n\
+         # tab = \"_Power\"\n",
+    );
+    out
+}
+
+/* Finds the line of the nth (0-based) definition of key. This is a textual
+ * search which works for the usual layouts of all three formats.
+ */
+pub fn locate(source: &str, key: &str, nth: usize) -> Option<usize> {
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let rest = line.trim_start_matches(|c: char| {
+                c.is_whitespace() || c == '-' || c == '{' || c == ',' || c == '['
+            });
+            let rest = rest.trim_start_matches('"');
+            match rest.strip_prefix(key) {
+                Some(after) => {
+                    let after = after.trim_start_matches(&['"', ']'][..]);
+                    let after = after.trim_start();
+                    after.starts_with('=') || after.starts_with(':') || after.is_empty()
+                }
+                None => false,
+            }
+        })
+        .nth(nth)
+        .map(|(i, _)| i + 1)
+}
+
+fn parse_error(args: &[OsString]) -> Option<String> {
+    let mut full = vec![OsString::from(crate::PROGRAM_NAME)];
+    full.extend_from_slice(args);
+    match Configuration::new(&full) {
+        Ok(_) => None,
+        Err(ref e)
+            if e.error_type == ParseErrorType::HelpRequested
+                || e.error_type == ParseErrorType::VersionInfoRequested =>
+        {
+            Some(String::from("help and version are not allowed in a spec."))
+        }
+        Err(e) => Some(e.to_string()),
+    }
+}
+
+fn is_icon_path(icon: &str) -> bool {
+    icon.contains('/')
+}
+
+pub fn check_icon(icon: &str, theme: &Option<gtk::IconTheme>) -> Option<String> {
+    use gtk::IconThemeExt;
+    if is_icon_path(icon) {
+        if !Path::new(icon).exists() {
+            return Some(format!("Icon file {} doesn't exist.", icon));
+        }
+    } else if let Some(theme) = theme {
+        if !theme.has_icon(icon) {
+            return Some(format!("Icon {} is not part of the icon theme.", icon));
+        }
+    }
+    None
+}
+
+/* Validates the spec without showing anything. Icons can only be looked up in
+ * the icon theme if gtk can be initialized, otherwise only icon files are checked.
+ */
+pub fn check(path: &Path) -> Vec<SpecError> {
+    let (source, spec) = match read(path) {
+        Ok(v) => v,
+        Err(e) => return vec![e],
+    };
+    let map = match top_level(&spec) {
+        Ok(m) => m,
+        Err(e) => return vec![SpecError::new(locate(&source, "config", 0), e)],
+    };
+    let theme = if gtk::init().is_ok() {
+        gtk::IconTheme::get_default()
+    } else {
+        None
+    };
+    let mut errors = Vec::new();
+    let mut all_button_args = Vec::new();
+    for (i, button) in buttons(map).into_iter().enumerate() {
+        let line = locate(&source, "button", i).or_else(|| locate(&source, "label", i));
+        match button_args(button) {
+            Ok(args) => {
+                if let Some(e) = parse_error(&args) {
+                    errors.push(SpecError::new(line, e));
+                }
+                all_button_args.extend(args);
+            }
+            Err(e) => errors.push(SpecError::new(line, e)),
+        }
+        if let Some(icon) = button.get("icon").and_then(Value::as_str) {
+            if let Some(e) = check_icon(icon, &theme) {
+                errors.push(SpecError::new(locate(&source, "icon", i), e));
+            }
+        }
+    }
+    for (key, value) in map {
+        if key == "button" {
+            continue;
+        }
+        let line = locate(&source, key, 0);
+        match option_args(key, value) {
+            Ok(mut args) => {
+                args.extend(all_button_args.iter().cloned());
+                if let Some(e) = parse_error(&args) {
+                    errors.push(SpecError::new(line, e));
+                }
+            }
+            Err(e) => errors.push(SpecError::new(line, e)),
+        }
+    }
+    // Some errors only show up with all options together, e.g. an unknown --focus.
+    if errors.is_empty() {
+        match to_args(&spec) {
+            Ok(args) => errors.extend(parse_error(&args).map(|e| SpecError::new(None, e))),
+            Err(e) => errors.push(SpecError::new(None, e)),
+        }
+    }
+    errors
+}
+
+// The problems --strict would reject, with the exit code of the first one.
+fn strict_problems(path: &Path) -> (Vec<SpecError>, i32) {
+    let mut args = vec![OsString::from(crate::PROGRAM_NAME)];
+    args.extend(
+        read(path)
+            .ok()
+            .and_then(|(_, spec)| to_args(&spec).ok())
+            .unwrap_or_default(),
+    );
+    let config = match Configuration::new(&args) {
+        Ok(c) => c,
+        Err(_) => return (Vec::new(), 0),
+    };
+    let theme = if gtk::init().is_ok() {
+        gtk::IconTheme::get_default()
+    } else {
+        None
+    };
+    let problems = crate::strict::problems(&config, &theme);
+    let code = problems
+        .first()
+        .map_or(0, crate::strict::Problem::exit_code);
+    let errors = problems
+        .iter()
+        .map(|p| SpecError::new(None, p.to_string()))
+        .collect();
+    (errors, code)
+}
+
+/* Entry point of `options-window-gtk check [--strict] CONFIG`, returns the exit
+ * code. With --strict it is the one --strict would exit with.
+ */
+pub fn run_check(args: &[OsString]) -> i32 {
+    let strict = args.first().is_some_and(|a| a == "--strict");
+    let args = if strict { &args[1..] } else { args };
+    if args.len() != 1 {
+        println!("Usage: {} check [--strict] CONFIG", crate::PROGRAM_NAME);
+        return 2;
+    }
+    let path = Path::new(&args[0]);
+    let mut errors = check(path);
+    let mut code = if errors.is_empty() { 0 } else { 1 };
+    if strict && errors.is_empty() {
+        let (problems, strict_code) = strict_problems(path);
+        errors = problems;
+        code = strict_code;
+    }
+    for e in &errors {
+        match e.line {
+            Some(line) => println!("{}:{}: {}", path.to_string_lossy(), line, e.message),
+            None => println!("{}: {}", path.to_string_lossy(), e.message),
+        }
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        config_options, default_args, default_config_files, dump_config, files, locate,
+        merge_defaults, read, to_args, Options,
+    };
+    use crate::Configuration;
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    #[test]
+    fn spec_to_args() {
+        let spec: serde_json::Value = toml::from_str(
+            "message = \"Hi\"\nexit-after-action = true\nshow-elapsed = false\n\
+             [[button]]\nlabel = \"Log\"\naction = \"log.sh\"\ntype = \"shell\"\n\
+             hold-to-confirm = 500\n",
+        )
+        .unwrap();
+        let expected: Vec<OsString> = vec![
+            "--exit-after-action",
+            "--message",
+            "Hi",
+            "-B",
+            "Log",
+            "log.sh",
+            "--hold-to-confirm",
+            "500",
+        ]
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+        assert_eq!(expected, to_args(&spec).unwrap());
+    }
+
+    #[test]
+    fn secondary_table() {
+        let spec: serde_json::Value = toml::from_str(
+            "[[button]]\nlabel = \"Restart\"\naction = \"restart.sh\"\n\
+             secondary = { label = \"Logs\", action = \"logs.sh\" }\n",
+        )
+        .unwrap();
+        let expected: Vec<OsString> = vec![
+            "-b",
+            "Restart",
+            "restart.sh",
+            "--secondary",
+            "Logs",
+            "logs.sh",
+        ]
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+        assert_eq!(expected, to_args(&spec).unwrap());
+    }
+
+    #[test]
+    fn tab_precedes_button() {
+        let spec: serde_json::Value =
+            toml::from_str("[[button]]\nlabel = \"Off\"\naction = \"poweroff\"\ntab = \"Power\"\n")
+                .unwrap();
+        let expected: Vec<OsString> = vec!["--tab", "Power", "-b", "Off", "poweroff"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        assert_eq!(expected, to_args(&spec).unwrap());
+    }
+
+    #[test]
+    fn list_prompts() {
+        let spec: serde_json::Value =
+            toml::from_str("radiolist = [\"Speakers\", \"Headphones\"]\n").unwrap();
+        let expected: Vec<OsString> = vec!["--radiolist", "Speakers", "Headphones"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        assert_eq!(expected, to_args(&spec).unwrap());
+        let spec: serde_json::Value =
+            toml::from_str("combo = [\"HDMI-1\", \"eDP-1\"]\neditable = true\n").unwrap();
+        let expected: Vec<OsString> = vec!["--combo", "HDMI-1", "eDP-1", "--editable"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        assert_eq!(expected, to_args(&spec).unwrap());
+        let spec: serde_json::Value = toml::from_str("scale = [0, 100, 5, 50]\n").unwrap();
+        let expected: Vec<OsString> = vec!["--scale", "0", "100", "5", "50"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        assert_eq!(expected, to_args(&spec).unwrap());
+    }
+
+    #[test]
+    fn default_config_order() {
+        let files = default_config_files(
+            Some(OsString::from("/etc/xdg/site:/etc/xdg")),
+            Some(PathBuf::from("/home/u/.config")),
+        );
+        let expected: Vec<PathBuf> = vec![
+            "/etc/options-window-gtk/config.toml",
+            "/etc/xdg/options-window-gtk/config.toml",
+            "/etc/xdg/site/options-window-gtk/config.toml",
+            "/home/u/.config/options-window-gtk/config.toml",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+        assert_eq!(expected, files);
+    }
+
+    fn defaults(toml: &str) -> Options {
+        match toml::from_str(toml).unwrap() {
+            serde_json::Value::Object(options) => options,
+            _ => unreachable!(),
+        }
+    }
+
+    fn layered(defaults: &Options, args: &[&str]) -> Result<Configuration, crate::ParseError> {
+        let args: Vec<OsString> = args.iter().map(OsString::from).collect();
+        let mut layered = vec![args[0].clone()];
+        layered.extend(default_args(defaults, &args));
+        layered.extend_from_slice(&args[1..]);
+        Configuration::new(&layered)
+    }
+
+    #[test]
+    fn command_line_wins_over_defaults() {
+        let message = defaults("message = \"Default\"\nborder-width = 20\n");
+        let config = layered(&message, &["app", "-m", "Given"]).unwrap();
+        assert_eq!("Given", config.message);
+        assert_eq!(20, config.border_width);
+        assert_eq!("Default", layered(&message, &["app"]).unwrap().message);
+
+        let width = defaults("width = 400\n");
+        assert!(layered(&width, &["app", "--bar"]).unwrap().bar);
+
+        let bar = defaults("bar = true\n");
+        assert!(layered(&bar, &["app", "--width", "400"]).is_ok());
+
+        let exit = defaults("exit-after-action = true\n");
+        let path = std::env::temp_dir().join(format!("spec-defaults-{}.toml", std::process::id()));
+        std::fs::write(&path, "exit-after-action = false\n").unwrap();
+        let config = layered(
+            &exit,
+            &["app", "-c", path.to_str().unwrap(), "-B", "a", "b"],
+        );
+        std::fs::remove_file(&path).unwrap();
+        let config = config.unwrap();
+        assert!(!config.exits_after(&config.buttons[0]));
+    }
+
+    #[cfg(feature = "terminal-exec")]
+    #[test]
+    fn terminal_default_gives_way() {
+        let terminal = defaults("terminal = \"foot\"\n");
+        assert!(layered(&terminal, &["app", "--terminal-cmd", "kitty {}"]).is_ok());
+    }
+
+    #[test]
+    fn later_specs_get_defaults() {
+        let options = defaults("message = \"Default\"\nexit-after-action = true\nwidth = 400\n");
+        let spec: serde_json::Value =
+            toml::from_str("message = \"Next\"\nexit-after-action = false\nbar = true\n").unwrap();
+        let merged = merge_defaults(&options, &spec);
+        assert_eq!("Next", merged["message"]);
+        assert_eq!(false, merged["exit-after-action"]);
+        assert!(merged.get("width").is_none());
+        let merged = merge_defaults(&options, &serde_json::json!({}));
+        assert_eq!(400, merged["width"]);
+    }
+
+    #[test]
+    fn includes() {
+        let dir = std::env::temp_dir().join(format!("spec-include-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("buttons")).unwrap();
+        std::fs::write(
+            dir.join("buttons/power.toml"),
+            "type = \"info\"\nmessage = \"Power\"\n\
+             [[button]]\nlabel = \"Off\"\naction = \"poweroff\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("main.toml"),
+            "include = [\"buttons/power.toml\"]\nmessage = \"Main\"\n\
+             [[button]]\nlabel = \"Lock\"\naction = \"lock\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("loop.toml"), "include = \"loop.toml\"\n").unwrap();
+        let merged = read(&dir.join("main.toml")).map(|(_, spec)| spec).ok();
+        let looped = read(&dir.join("loop.toml")).is_err();
+        let watched = (files(&dir.join("main.toml")), files(&dir.join("loop.toml")));
+        std::fs::remove_dir_all(&dir).unwrap();
+        let merged = merged.unwrap();
+        assert_eq!("Main", merged["message"]);
+        assert_eq!("info", merged["type"]);
+        assert_eq!("Lock", merged["button"][0]["label"]);
+        assert_eq!("Off", merged["button"][1]["label"]);
+        assert!(looped);
+        assert_eq!(
+            vec![dir.join("main.toml"), dir.join("buttons/power.toml")],
+            watched.0
+        );
+        assert_eq!(vec![dir.join("loop.toml")], watched.1);
+    }
+
+    #[test]
+    fn locate_keys() {
+        let toml = "message = \"a\"\n[[button]]\nlabel = \"x\"\n[[button]]\nlabel = \"y\"\n";
+        assert_eq!(Some(1), locate(toml, "message", 0));
+        assert_eq!(Some(4), locate(toml, "button", 1));
+        let yaml = "message: a\nbutton:\n  - label: x\n  - label: y\n";
+        assert_eq!(Some(4), locate(yaml, "label", 1));
+        let json = "{\n  \"message\": \"a\",\n  \"type\": \"info\"\n}\n";
+        assert_eq!(Some(3), locate(json, "type", 0));
+    }
+
+    #[test]
+    fn dumped_defaults_parse() {
+        let dump = dump_config();
+        let mut lines = dump.lines();
+        let mut defaults = String::new();
+        while let Some(line) = lines.next() {
+            if line.starts_with("# Default:") {
+                defaults.push_str(&lines.next().unwrap()[2..]);
+                defaults.push('\n');
+            }
+        }
+        let spec: serde_json::Value = toml::from_str(&defaults).unwrap();
+        let mut args = vec![OsString::from("app")];
+        args.extend(to_args(&spec).unwrap());
+        assert!(crate::Configuration::new(&args).is_ok());
+        // Examples of files which have to exist, and options which need another one.
+        let skipped = ["message-file", "css", "replay"];
+        let companions: &[(&str, &[&str])] = &[
+            ("cancel-if", &["--delay", "30"]),
+            ("gtk-backend", &["--dump-ui"]),
+            ("poll-interval", &["--close-when", "true"]),
+            ("store-secret", &["--pin"]),
+            ("default", &["-B", "_Reboot now", "true"]),
+            (
+                "focus-order",
+                &["-B", "_Suspend", "true", "-B", "_Reboot", "true"],
+            ),
+        ];
+        for (key, _, default, example) in config_options() {
+            if default.is_some() || skipped.contains(&key) {
+                continue;
+            }
+            let line = format!("{} = {}", key, example);
+            let spec: serde_json::Value = toml::from_str(&line).unwrap();
+            let mut args = vec![OsString::from("app")];
+            args.extend(to_args(&spec).unwrap());
+            for (_, extra) in companions.iter().filter(|(k, _)| *k == key) {
+                args.extend(extra.iter().map(OsString::from));
+            }
+            if let Err(e) = crate::Configuration::new(&args) {
+                assert!(
+                    e.to_string().contains("was built without"),
+                    "{}: {}",
+                    line,
+                    e
+                );
+            }
+        }
+    }
+}
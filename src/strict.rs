@@ -0,0 +1,131 @@
+use crate::{mnemonic, spec, Configuration};
+
+/* Soft problems of a dialog. They are tolerated unless --strict is given,
+ * then each kind exits with its own code, so generated dialogs can be
+ * validated in CI:
+ *
+ *   10  no buttons besides Cancel
+ *   11  unknown icon
+ *   12  label which isn't valid UTF-8
+ *   13  two buttons with the same accelerator
+ */
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    NoButtons,
+    UnknownIcon(String),
+    NonUtf8Label(String),
+    DuplicateAccelerator(char, String, String),
+}
+
+impl Problem {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Problem::NoButtons => 10,
+            Problem::UnknownIcon(_) => 11,
+            Problem::NonUtf8Label(_) => 12,
+            Problem::DuplicateAccelerator(..) => 13,
+        }
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Problem::NoButtons => write!(f, "The dialog has no buttons besides Cancel."),
+            Problem::UnknownIcon(e) => write!(f, "{}", e),
+            Problem::NonUtf8Label(label) => write!(f, "Label {} is not valid UTF-8.", label),
+            Problem::DuplicateAccelerator(key, first, second) => write!(
+                f,
+                "{} and {} both use the accelerator Alt+{}.",
+                first, second, key
+            ),
+        }
+    }
+}
+
+/* Icons can only be looked up if there is an icon theme, without one only
+ * icon files are checked.
+ */
+pub fn problems(config: &Configuration, theme: &Option<gtk::IconTheme>) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if config.buttons.is_empty() {
+        problems.push(Problem::NoButtons);
+    }
+    for button in &config.buttons {
+        // Labels are converted lossily while parsing.
+        if button.label.contains(char::REPLACEMENT_CHARACTER) {
+            problems.push(Problem::NonUtf8Label(button.label.clone()));
+        }
+        if let Some(icon) = &button.icon {
+            if let Some(e) = spec::check_icon(&icon.to_string_lossy(), theme) {
+                problems.push(Problem::UnknownIcon(e));
+            }
+        }
+    }
+    let mut labels: Vec<&str> = config.buttons.iter().map(|b| b.label.as_str()).collect();
+    if !config.no_cancel {
+        labels.push(config.cancel_label.as_str());
+    }
+    let mut seen: Vec<(char, &str)> = Vec::new();
+    for label in labels {
+        if let Some(key) = mnemonic(label) {
+            match seen.iter().find(|(k, _)| *k == key) {
+                Some((_, first)) => problems.push(Problem::DuplicateAccelerator(
+                    key,
+                    String::from(*first),
+                    String::from(label),
+                )),
+                None => seen.push((key, label)),
+            }
+        }
+    }
+    problems
+}
+
+// Prints every problem and returns the exit code of the first one, 0 if there is none.
+pub fn report(problems: &[Problem]) -> i32 {
+    for problem in problems {
+        eprintln!("{}", problem);
+    }
+    problems.first().map_or(0, Problem::exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{problems, Problem};
+    use crate::Configuration;
+    use std::ffi::OsString;
+
+    fn o(s: &str) -> OsString {
+        OsString::from(s)
+    }
+
+    #[test]
+    fn soft_problems() {
+        let config = Configuration::new(&[o("app")]).unwrap();
+        assert_eq!(vec![Problem::NoButtons], problems(&config, &None));
+        let args = vec![
+            o("app"),
+            o("-B"),
+            o("_Save"),
+            o("save.sh"),
+            o("-B"),
+            o("_Stop"),
+            o("stop.sh"),
+            o("/nonexistent/icon.png"),
+            o("-B"),
+            o("_Close"),
+            o("close.sh"),
+        ];
+        let config = Configuration::new(&args).unwrap();
+        let found = problems(&config, &None);
+        assert_eq!(
+            vec![11, 13, 13],
+            found.iter().map(Problem::exit_code).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Problem::DuplicateAccelerator('c', String::from("_Close"), String::from("_Cancel")),
+            found[2]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{create_gtk_step_window, handle_error, Configuration, ParseError};
+use gtk::prelude::*;
+use serde_json::{json, Map, Value};
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::rc::Rc;
+
+/* --survey FILE asks the questions of a JSON array one after another in one
+ * window and prints the answers as one JSON object, keyed by the id of each
+ * question (or its text). FILE may be - for stdin.
+ *
+ *   [{"id": "mood", "question": "How was your day?", "type": "choice",
+ *     "choices": ["Good", "Meh", "Bad"]},
+ *    {"id": "blocked", "question": "Anything blocking you?", "type": "yes-no"},
+ *    {"id": "tomorrow", "question": "Plans for tomorrow?", "type": "text"}]
+ *
+ * Choices are answered with the chosen string, yes-no with a bool and text
+ * with the entered string. Cancel prints nothing and exits with 1.
+ */
+
+enum Kind {
+    Choice(Vec<String>),
+    YesNo,
+    Text,
+}
+
+struct Question {
+    key: String,
+    text: String,
+    kind: Kind,
+}
+
+fn read(path: &Path) -> Result<Value, String> {
+    let source = if path == Path::new("-") {
+        let mut source = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut source)
+            .map(|_| source)
+            .map_err(|e| e.to_string())?
+    } else {
+        std::fs::read_to_string(path).map_err(|e| e.to_string())?
+    };
+    serde_json::from_str(&source).map_err(|e| e.to_string())
+}
+
+fn questions(value: &Value) -> Result<Vec<Question>, String> {
+    let items = match value {
+        Value::Array(items) if !items.is_empty() => items,
+        _ => return Err(String::from("A survey has to be a list of questions.")),
+    };
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let text = item
+                .get("question")
+                .and_then(Value::as_str)
+                .ok_or_else(|| format!("Question {} has no question text.", i + 1))?;
+            let kind = match item.get("type").and_then(Value::as_str) {
+                None | Some("choice") => {
+                    let choices: Vec<String> = item
+                        .get("choices")
+                        .and_then(Value::as_array)
+                        .map(|c| {
+                            c.iter()
+                                .filter_map(Value::as_str)
+                                .map(String::from)
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    if choices.is_empty() {
+                        return Err(format!("Question {} has no choices.", i + 1));
+                    }
+                    Kind::Choice(choices)
+                }
+                Some("yes-no") => Kind::YesNo,
+                Some("text") => Kind::Text,
+                Some(t) => {
+                    return Err(format!(
+                        "Type {} of question {} is neither choice, yes-no nor text.",
+                        t,
+                        i + 1
+                    ))
+                }
+            };
+            let key = item.get("id").and_then(Value::as_str).unwrap_or(text);
+            Ok(Question {
+                key: String::from(key),
+                text: String::from(text),
+                kind,
+            })
+        })
+        .collect()
+}
+
+fn create_page(question: &Question, spacing: i32, answer: Rc<dyn Fn(Value)>) -> gtk::Box {
+    let page = gtk::Box::new(gtk::Orientation::Vertical, spacing);
+    let label = gtk::Label::new(question.text.as_str());
+    label.set_line_wrap(true);
+    page.pack_start(&label, false, false, 0);
+    match &question.kind {
+        Kind::Choice(choices) => {
+            for choice in choices {
+                let button = gtk::Button::new_with_label(choice);
+                let answer = answer.clone();
+                let choice = choice.clone();
+                button.connect_clicked(move |_| answer(json!(choice)));
+                page.pack_start(&button, false, true, 0);
+            }
+        }
+        Kind::YesNo => {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, spacing);
+            row.set_homogeneous(true);
+            for (mnemonic, value) in &[("_Yes", true), ("_No", false)] {
+                let button = gtk::Button::new_with_mnemonic(mnemonic);
+                let answer = answer.clone();
+                let value = *value;
+                button.connect_clicked(move |_| answer(json!(value)));
+                row.pack_start(&button, true, true, 0);
+            }
+            page.pack_start(&row, false, true, 0);
+        }
+        Kind::Text => {
+            let entry = gtk::Entry::new();
+            let next = gtk::Button::new_with_mnemonic("_Next");
+            {
+                let answer = answer.clone();
+                entry.connect_activate(move |e| answer(json!(e.get_text().unwrap_or_default())));
+            }
+            let text = entry.clone();
+            next.connect_clicked(move |_| answer(json!(text.get_text().unwrap_or_default())));
+            page.pack_start(&entry, false, true, 0);
+            page.pack_start(&next, false, false, 0);
+        }
+    }
+    page
+}
+
+pub fn run(config: &Configuration, path: &Path) -> i32 {
+    let questions = match read(path).and_then(|v| questions(&v)) {
+        Ok(q) => q,
+        Err(e) => {
+            return handle_error(ParseError::wrong_argument(format!(
+                "{}: {}",
+                path.to_string_lossy(),
+                e
+            )))
+        }
+    };
+    let count = questions.len();
+    let keys: Vec<String> = questions.iter().map(|q| q.key.clone()).collect();
+    let stack = gtk::Stack::new();
+    stack.set_transition_type(gtk::StackTransitionType::SlideLeft);
+    let progress = gtk::Label::new(format!("Question 1 of {}", count).as_str());
+    let answers = Rc::new(RefCell::new(Map::new()));
+    let current = Rc::new(Cell::new(0));
+    let exit_code = Rc::new(Cell::new(1));
+
+    let answer: Rc<dyn Fn(Value)> = {
+        let (stack, progress, exit_code) = (stack.clone(), progress.clone(), exit_code.clone());
+        Rc::new(move |value: Value| {
+            let index = current.get();
+            // Clicks while the window closes
+            if index == count {
+                return;
+            }
+            answers.borrow_mut().insert(keys[index].clone(), value);
+            current.set(index + 1);
+            if index + 1 == count {
+                println!("{}", Value::Object(answers.borrow().clone()));
+                exit_code.set(0);
+                gtk::main_quit();
+                return;
+            }
+            stack.set_visible_child_name(&(index + 1).to_string());
+            progress.set_text(&format!("Question {} of {}", index + 2, count));
+        })
+    };
+    for (index, question) in questions.iter().enumerate() {
+        let page = create_page(question, config.spacing, answer.clone());
+        stack.add_named(&page, &index.to_string());
+    }
+
+    let cancel = gtk::Button::new_with_mnemonic("_Cancel");
+    cancel.connect_clicked(|_| gtk::main_quit());
+    let footer = gtk::Box::new(gtk::Orientation::Horizontal, config.spacing);
+    footer.pack_start(&progress, false, false, 0);
+    footer.pack_end(&cancel, false, false, 0);
+    let content = gtk::Box::new(gtk::Orientation::Vertical, config.spacing);
+    content.add(&stack);
+    content.add(&gtk::Separator::new(gtk::Orientation::Horizontal));
+    content.add(&footer);
+
+    let window = create_gtk_step_window(config, &content);
+    window.show_all();
+    gtk::main();
+    exit_code.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{questions, Kind};
+    use serde_json::json;
+
+    #[test]
+    fn question_list() {
+        let list = questions(&json!([
+            {"id": "mood", "question": "How was your day?", "choices": ["Good", "Bad"]},
+            {"question": "Blocked?", "type": "yes-no"}
+        ]))
+        .ok()
+        .unwrap();
+        assert_eq!("mood", list[0].key);
+        assert!(matches!(&list[0].kind, Kind::Choice(c) if c.len() == 2));
+        assert_eq!("Blocked?", list[1].key);
+        assert!(questions(&json!([{"question": "Why?", "type": "choice"}])).is_err());
+        assert!(questions(&json!([{"question": "Why?", "type": "slider"}])).is_err());
+        assert!(questions(&json!({"question": "Why?"})).is_err());
+    }
+}
@@ -0,0 +1,487 @@
+#[cfg(feature = "terminal-exec")]
+use crate::Command;
+#[cfg(all(feature = "terminal-exec", unix))]
+use crate::PROGRAM_NAME;
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+use crate::{host_command, running_in_flatpak, DEFAULT_TERMINAL};
+#[cfg(all(feature = "terminal-exec", unix))]
+use rand::Rng;
+use std::ffi::OsStr;
+#[cfg(all(feature = "terminal-exec", unix))]
+use std::ffi::OsString;
+#[cfg(all(feature = "terminal-exec", unix))]
+use std::io::Write;
+#[cfg(all(feature = "terminal-exec", unix))]
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+#[cfg(all(feature = "terminal-exec", unix))]
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+#[cfg(all(feature = "terminal-exec", unix))]
+use std::path::PathBuf;
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+use std::sync::atomic::{AtomicU8, Ordering};
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+use std::sync::Mutex;
+
+/* How a terminal emulator takes the command to run. Argv terminals get the
+ * program and its arguments as separate arguments after the flags, String
+ * terminals a single string which they split themselves.
+ */
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+enum Quirk {
+    Argv(&'static [&'static str]),
+    String(&'static str),
+}
+
+// How the chosen terminal takes a command, it is only started directly if it is in here.
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+const TERMINALS: &[(&str, Quirk)] = &[
+    ("alacritty", Quirk::Argv(&["-e"])),
+    ("foot", Quirk::Argv(&[])),
+    ("kitty", Quirk::Argv(&[])),
+    ("wezterm", Quirk::Argv(&["start", "--"])),
+    ("gnome-terminal", Quirk::Argv(&["--"])),
+    ("konsole", Quirk::Argv(&["-e"])),
+    ("xfce4-terminal", Quirk::Argv(&["-x"])),
+    ("mate-terminal", Quirk::Argv(&["-x"])),
+    ("terminator", Quirk::Argv(&["-x"])),
+    ("tilix", Quirk::String("-e")),
+    ("lxterminal", Quirk::String("-e")),
+    ("termite", Quirk::String("-e")),
+    ("urxvt", Quirk::Argv(&["-e"])),
+    ("st", Quirk::Argv(&["-e"])),
+    ("xterm", Quirk::Argv(&["-e"])),
+];
+
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn find_in_path(program: &OsStr) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+// Set from --terminal before the dialog is shown.
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+static TERMINAL: Mutex<Option<OsString>> = Mutex::new(None);
+
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+pub fn set_terminal(terminal: Option<OsString>) {
+    *TERMINAL.lock().unwrap() = terminal;
+}
+
+#[cfg(not(all(feature = "terminal-exec", unix, not(target_os = "macos"))))]
+pub fn set_terminal(_terminal: Option<std::ffi::OsString>) {}
+
+// Set from --terminal-cmd, it replaces the terminal choice completely.
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+static TEMPLATE: Mutex<Option<String>> = Mutex::new(None);
+
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+pub fn set_template(template: Option<String>) {
+    *TEMPLATE.lock().unwrap() = template;
+}
+
+#[cfg(not(all(feature = "terminal-exec", unix, not(target_os = "macos"))))]
+pub fn set_template(_template: Option<String>) {}
+
+/* Splits a --terminal-cmd template like `foot -e {}` at whitespace and puts
+ * the words of run in place of {}. Without {} they are appended.
+ */
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn template_args(template: &str, run: &[OsString]) -> Vec<OsString> {
+    let mut args: Vec<OsString> = Vec::new();
+    let mut replaced = false;
+    for word in template.split_whitespace() {
+        if word == "{}" {
+            args.extend(run.iter().cloned());
+            replaced = true;
+        } else {
+            args.push(OsString::from(word));
+        }
+    }
+    if !replaced {
+        args.extend(run.iter().cloned());
+    }
+    args
+}
+
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn template_command(template: &str, run: &[OsString]) -> std::process::Command {
+    let args = template_args(template, run);
+    let mut cmd = host_command(&args[0]);
+    cmd.args(&args[1..]);
+    cmd
+}
+
+// Debian's alternative for the default terminal, used without i3-sensible-terminal.
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+const ALTERNATIVE_TERMINAL: &str = "x-terminal-emulator";
+
+// --terminal, otherwise $TERMINAL like i3-sensible-terminal does.
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn chosen_terminal() -> Option<OsString> {
+    let terminal = TERMINAL.lock().unwrap().clone();
+    terminal.or_else(|| std::env::var_os("TERMINAL").filter(|t| !t.is_empty()))
+}
+
+/* The chosen terminal if we know how to hand it a command. None if no terminal
+ * is chosen, it is unknown or not installed.
+ */
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn known_terminal() -> Option<(PathBuf, &'static str, &'static Quirk)> {
+    let terminal = chosen_terminal()?;
+    let name = std::path::Path::new(&terminal).file_name()?.to_os_string();
+    let (known, quirk) = TERMINALS.iter().find(|(known, _)| name == *known)?;
+    Some((find_in_path(&terminal)?, known, quirk))
+}
+
+/* How --terminal-mode opens terminals. Remote asks an already running kitty
+ * (`kitty @ launch`, needs remote control) or foot server (`footclient`) for
+ * a new window, which is a lot faster than starting a new terminal. Auto does
+ * that only if the remote control socket is there.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(not(feature = "terminal-exec"), allow(dead_code))]
+pub enum TerminalMode {
+    Auto,
+    Spawn,
+    Remote,
+}
+
+// Set once before the dialog is shown, exec_in_terminal has no access to the configuration.
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+static MODE: AtomicU8 = AtomicU8::new(0);
+
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+pub fn set_mode(mode: TerminalMode) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+#[cfg(not(all(feature = "terminal-exec", unix, not(target_os = "macos"))))]
+pub fn set_mode(_mode: TerminalMode) {}
+
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn mode() -> TerminalMode {
+    match MODE.load(Ordering::Relaxed) {
+        1 => TerminalMode::Spawn,
+        2 => TerminalMode::Remote,
+        _ => TerminalMode::Auto,
+    }
+}
+
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn remote_available(terminal: &str) -> bool {
+    match terminal {
+        "kitty" => std::env::var_os("KITTY_LISTEN_ON").is_some(),
+        "foot" => {
+            let runtime = std::env::var_os("XDG_RUNTIME_DIR").unwrap_or_default();
+            let socket = match std::env::var("WAYLAND_DISPLAY") {
+                Ok(display) => format!("foot-{}.sock", display),
+                Err(_) => String::from("foot.sock"),
+            };
+            std::path::Path::new(&runtime).join(socket).exists()
+        }
+        _ => false,
+    }
+}
+
+// The remote control command of kitty and foot.
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn remote_command(terminal: &str, command: &OsStr) -> Option<std::process::Command> {
+    let mut cmd = match terminal {
+        "kitty" => {
+            let mut cmd = std::process::Command::new("kitty");
+            cmd.arg("@").arg("launch").arg("--type=os-window");
+            cmd
+        }
+        "foot" => std::process::Command::new("footclient"),
+        _ => return None,
+    };
+    cmd.arg("/bin/sh").arg("-c").arg(command);
+    Some(cmd)
+}
+
+/* Started with -e for commands it doesn't know how to take otherwise: the
+ * chosen terminal, else i3-sensible-terminal, which picks one itself, else
+ * x-terminal-emulator. Within a Flatpak the host's PATH can't be searched,
+ * there it is always i3-sensible-terminal.
+ */
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+pub fn fallback_terminal() -> (OsString, &'static [&'static str]) {
+    if let Some(terminal) = chosen_terminal() {
+        return (terminal, &["-e"]);
+    }
+    if !running_in_flatpak()
+        && find_in_path(OsStr::new(DEFAULT_TERMINAL)).is_none()
+        && find_in_path(OsStr::new(ALTERNATIVE_TERMINAL)).is_some()
+    {
+        return (OsString::from(ALTERNATIVE_TERMINAL), &["-e"]);
+    }
+    (OsString::from(DEFAULT_TERMINAL), &["-v", "-e"])
+}
+
+// The terminal exec_in_terminal starts, for --diagnose.
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+pub fn terminal_name() -> Option<String> {
+    if let Some(template) = &*TEMPLATE.lock().unwrap() {
+        return Some(format!("{} (--terminal-cmd)", template));
+    }
+    if let Some((path, _, _)) = known_terminal() {
+        return Some(path.to_string_lossy().into_owned());
+    }
+    find_in_path(&fallback_terminal().0)
+        .map(|path| format!("{} with a script", path.to_string_lossy()))
+}
+
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn direct_args(quirk: &Quirk, command: &OsStr) -> Vec<OsString> {
+    let mut args: Vec<OsString> = Vec::new();
+    match quirk {
+        Quirk::Argv(flags) => {
+            args.extend(flags.iter().map(OsString::from));
+            args.push(OsString::from("/bin/sh"));
+            args.push(OsString::from("-c"));
+            args.push(command.to_os_string());
+        }
+        Quirk::String(flag) => {
+            let quoted = command.to_string_lossy().replace('\'', "'\\''");
+            args.push(OsString::from(flag));
+            args.push(OsString::from(format!("/bin/sh -c '{}'", quoted)));
+        }
+    }
+    args
+}
+
+/* A chosen terminal (--terminal or $TERMINAL) listed in TERMINALS gets the
+ * command directly. For all others and without a chosen terminal the
+ * method is roughly the same as in i3-nagbar:
+ * A temporary script with the command and a link to this executable is created.
+ * Afterwards the terminal emulator (see fallback_terminal) gets called with -e <link>
+ * If this executable gets called with a '.cmd' ending it starts a shell with the
+ * script as parameter.
+ *
+ * The reason for this is that not all terminal emulators handle -e the same way.
+ * A --terminal-cmd template gets the link as well, in place of its {}.
+ *
+ * There might be some security issues with this...
+ *
+ * Within a Flatpak the script and the link would only be visible inside the
+ * sandbox, so the host terminal gets the command handed over directly instead.
+*/
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+pub fn exec_in_terminal(command: &Command) -> std::io::Result<std::process::Command> {
+    let template = TEMPLATE.lock().unwrap().clone();
+    if let Some(template) = template {
+        // The link isn't visible on the host, the shell gets the command there.
+        if running_in_flatpak() {
+            let run = [
+                OsString::from("/bin/sh"),
+                OsString::from("-c"),
+                command.command.clone(),
+            ];
+            return Ok(template_command(&template, &run));
+        }
+        let link_path = write_link(command)?;
+        return Ok(template_command(&template, &[link_path.into_os_string()]));
+    }
+    if running_in_flatpak() {
+        let (terminal, flags) = fallback_terminal();
+        let mut cmd = host_command(terminal);
+        cmd.args(flags)
+            .arg("/bin/sh")
+            .arg("-c")
+            .arg(&command.command);
+        return Ok(cmd);
+    }
+    if let Some((terminal, name, quirk)) = known_terminal() {
+        let remote = match mode() {
+            TerminalMode::Auto => remote_available(name),
+            TerminalMode::Spawn => false,
+            TerminalMode::Remote => true,
+        };
+        if remote {
+            match remote_command(name, &command.command) {
+                Some(cmd) => return Ok(cmd),
+                None => warning!("{} has no remote control, starting a new one.", name),
+            }
+        }
+        let mut cmd = std::process::Command::new(terminal);
+        cmd.args(direct_args(quirk, &command.command));
+        return Ok(cmd);
+    }
+    let link_path = write_link(command)?;
+    let (terminal, flags) = fallback_terminal();
+    let mut cmd = std::process::Command::new(terminal);
+    cmd.args(flags).arg(link_path.as_os_str());
+    Ok(cmd)
+}
+
+// The script of the command and the link to this executable which runs it.
+#[cfg(all(feature = "terminal-exec", unix, not(target_os = "macos")))]
+fn write_link(command: &Command) -> std::io::Result<PathBuf> {
+    let (script_path, rnd) = write_script(command, "sh")?;
+    let mut link_path = script_path;
+    link_path.set_file_name(format!("{}_{}.cmd", PROGRAM_NAME, rnd));
+    std::os::unix::fs::symlink(std::env::current_exe()?, &link_path)?;
+    Ok(link_path)
+}
+
+/* There is no i3-sensible-terminal on macOS. Scripts ending in '.command' are
+ * opened by Terminal.app, so no link is needed there.
+ */
+#[cfg(all(feature = "terminal-exec", target_os = "macos"))]
+pub fn exec_in_terminal(command: &Command) -> std::io::Result<std::process::Command> {
+    let (script_path, _) = write_script(command, "command")?;
+    let mut cmd = std::process::Command::new("open");
+    cmd.arg("-a").arg("Terminal").arg(script_path.as_os_str());
+    Ok(cmd)
+}
+
+// Opens a new console window which stays open after the command finished.
+#[cfg(all(feature = "terminal-exec", windows))]
+pub fn exec_in_terminal(command: &Command) -> std::io::Result<std::process::Command> {
+    let mut cmd = std::process::Command::new("cmd");
+    cmd.arg("/C")
+        .arg("start")
+        .arg("")
+        .arg("cmd")
+        .arg("/K")
+        .arg(&command.command);
+    Ok(cmd)
+}
+
+/* Writes the command into a script which deletes itself when it runs.
+ * Returns the script path and the random part of its name.
+ */
+#[cfg(all(feature = "terminal-exec", unix))]
+fn write_script(command: &Command, extension: &str) -> std::io::Result<(PathBuf, String)> {
+    let mut script_path = runtime_dir()?;
+    let rnd: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(30)
+        .collect();
+    let script_name = OsString::from(format!("{}_{}.{}", PROGRAM_NAME, rnd, extension));
+    script_path.push(script_name);
+    {
+        let mut script_file = std::fs::File::create(&script_path)?;
+        let mut p = script_file.metadata()?.permissions();
+        p.set_mode(0o700);
+        script_file
+            .set_permissions(p)
+            .expect("Couldn't set permissions for script file");
+        script_file.write_all(b"#!/bin/sh\n")?;
+        script_file.write_all(b"rm ")?;
+        script_file.write_all(script_path.as_os_str().as_bytes())?;
+        script_file.write_all(b"\n")?;
+        script_file.write_all(command.command.as_bytes())?;
+        script_file.write_all(b"\n")?;
+        script_file.flush()?;
+    }
+    Ok((script_path, rnd))
+}
+
+/* Private directory for scripts and links. It lives below $XDG_RUNTIME_DIR if
+ * available, otherwise below $TMPDIR (or /tmp) with the user id in its name.
+ * An existing directory is only accepted if it is ours and not accessible by
+ * anybody else.
+ */
+#[cfg(all(feature = "terminal-exec", unix))]
+pub fn runtime_dir() -> std::io::Result<PathBuf> {
+    let uid = unsafe { libc::getuid() };
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(v) if !v.is_empty() && std::path::Path::new(&v).is_dir() => {
+            let mut dir = PathBuf::from(v);
+            dir.push(PROGRAM_NAME);
+            dir
+        }
+        _ => {
+            let mut dir = std::env::temp_dir();
+            dir.push(format!("{}-{}", PROGRAM_NAME, uid));
+            dir
+        }
+    };
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(_) => {}
+        Err(ref e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            let meta = std::fs::symlink_metadata(&dir)?;
+            if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o077 != 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    format!("{} is not a private directory", dir.to_string_lossy()),
+                ));
+            }
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(dir)
+}
+
+// True if this executable got started through a link created by exec_in_terminal.
+pub fn is_script_link(arg0: &OsStr) -> bool {
+    cfg!(all(
+        feature = "terminal-exec",
+        unix,
+        not(target_os = "macos")
+    )) && arg0.to_string_lossy().ends_with(".cmd")
+}
+
+#[cfg(all(feature = "terminal-exec", unix))]
+pub fn run_link(link: &OsStr) {
+    match std::fs::remove_file(link) {
+        Ok(_) => {}
+        Err(e) => println!("Couldn't delete link {}\n{}", link.to_string_lossy(), e),
+    }
+    run_script(link);
+}
+
+#[cfg(not(all(feature = "terminal-exec", unix)))]
+pub fn run_link(_link: &OsStr) {}
+
+#[cfg(all(feature = "terminal-exec", unix))]
+fn run_script(cmd: &OsStr) {
+    let mut script = OsString::from_vec(cmd.as_bytes()[..cmd.len() - 3].to_vec());
+    script.push("sh");
+    std::process::Command::new("/bin/sh")
+        .arg(script)
+        .spawn()
+        .expect("Couldn't spawn child process.")
+        .wait()
+        .expect("Error during childs execute.");
+}
+
+#[cfg(all(test, feature = "terminal-exec", unix, not(target_os = "macos")))]
+mod tests {
+    use super::{direct_args, template_args, Quirk};
+    use std::ffi::{OsStr, OsString};
+
+    #[test]
+    fn terminal_quirks() {
+        let args = |a: &[&str]| a.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(
+            args(&["start", "--", "/bin/sh", "-c", "echo 'hi'"]),
+            direct_args(&Quirk::Argv(&["start", "--"]), OsStr::new("echo 'hi'"))
+        );
+        assert_eq!(
+            args(&["-e", r"/bin/sh -c 'echo '\''hi'\'''"]),
+            direct_args(&Quirk::String("-e"), OsStr::new("echo 'hi'"))
+        );
+    }
+
+    #[test]
+    fn terminal_templates() {
+        let args = |a: &[&str]| a.iter().map(OsString::from).collect::<Vec<_>>();
+        let link = args(&["/run/x.cmd"]);
+        assert_eq!(
+            args(&["foot", "-e", "/run/x.cmd"]),
+            template_args("foot -e {}", &link)
+        );
+        assert_eq!(
+            args(&["wezterm", "start", "--", "/run/x.cmd", "--hold"]),
+            template_args("wezterm start -- {} --hold", &link)
+        );
+        assert_eq!(
+            args(&["xterm", "-e", "/run/x.cmd"]),
+            template_args("  xterm   -e", &link)
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    create_gtk_accent, create_gtk_buttons, create_gtk_footer, create_gtk_message,
+    create_gtk_step_window, server, spec, Configuration, ParseError, PROGRAM_NAME,
+};
+use gtk::prelude::*;
+use serde_json::{json, Value};
+use std::cell::{Cell, RefCell};
+use std::ffi::OsString;
+use std::path::Path;
+use std::rc::Rc;
+
+/* --wizard FILE shows the dialogs of the page list in FILE one after another
+ * in one window, Back and Next move between them. Buttons of the pages are
+ * answers, their actions are never run. Once the last page is done all
+ * answers are printed as a JSON array with one object per page, like the
+ * replies of --serve. Cancel prints nothing and exits with 1.
+ *
+ *   [[page]]
+ *   message = "Which shell?"
+ *   button = [{label = "_bash"}, {label = "_zsh"}]
+ */
+
+fn pages(path: &Path) -> Result<Vec<Configuration>, ParseError> {
+    let error =
+        |e: String| ParseError::wrong_argument(format!("{}: {}", path.to_string_lossy(), e));
+    let (_, spec) = spec::read(path).map_err(|e| error(e.message))?;
+    let pages = match spec.get("page") {
+        Some(Value::Array(pages)) if !pages.is_empty() => pages,
+        _ => return Err(error(String::from("A wizard needs a list of pages."))),
+    };
+    pages
+        .iter()
+        .map(|page| {
+            let mut args = vec![OsString::from(PROGRAM_NAME)];
+            args.extend(
+                spec::to_args(&spec::with_defaults(&server::with_actions(page))).map_err(error)?,
+            );
+            Configuration::new(&args).map_err(|e| error(e.to_string()))
+        })
+        .collect()
+}
+
+// Pages without buttons only show something, they have no answer.
+fn output(answers: &[Option<Value>]) -> Value {
+    Value::Array(
+        answers
+            .iter()
+            .map(|a| a.clone().unwrap_or_else(|| json!({ "button": null })))
+            .collect(),
+    )
+}
+
+fn create_page(config: &Configuration) -> gtk::Box {
+    let content = gtk::Box::new(gtk::Orientation::Vertical, config.spacing);
+    content.add(&create_gtk_accent(&config.message_type));
+    content.add(&create_gtk_message(config));
+    content.add(&create_gtk_buttons(config).container);
+    if let Some(text) = &config.footer {
+        content.add(&create_gtk_footer(text));
+    }
+    content
+}
+
+pub fn run(config: &Configuration, path: &Path) -> i32 {
+    let mut pages = match pages(path) {
+        Ok(p) => p,
+        Err(e) => return crate::handle_error(e),
+    };
+    let count = pages.len();
+    let stack = gtk::Stack::new();
+    stack.set_transition_type(gtk::StackTransitionType::SlideLeftRight);
+    let back = gtk::Button::new_with_mnemonic("_Back");
+    let next = gtk::Button::new_with_mnemonic("_Next");
+    let answers: Rc<RefCell<Vec<Option<Value>>>> = Rc::new(RefCell::new(vec![None; count]));
+    let current = Rc::new(Cell::new(0));
+    let exit_code = Rc::new(Cell::new(1));
+
+    let needs_answer: Vec<bool> = pages.iter().map(|p| !p.buttons.is_empty()).collect();
+    let show: Rc<dyn Fn(usize)> = {
+        let (stack, back, next) = (stack.clone(), back.clone(), next.clone());
+        let (answers, current) = (answers.clone(), current.clone());
+        Rc::new(move |page: usize| {
+            current.set(page);
+            stack.set_visible_child_name(&page.to_string());
+            back.set_sensitive(page > 0);
+            next.set_label(if page + 1 == count {
+                "_Finish"
+            } else {
+                "_Next"
+            });
+            next.set_sensitive(!needs_answer[page] || answers.borrow()[page].is_some());
+        })
+    };
+    let advance: Rc<dyn Fn()> = {
+        let (answers, current, exit_code, show) = (
+            answers.clone(),
+            current.clone(),
+            exit_code.clone(),
+            show.clone(),
+        );
+        Rc::new(move || {
+            let page = current.get();
+            if page + 1 < count {
+                show(page + 1);
+                return;
+            }
+            println!("{}", output(&answers.borrow()));
+            exit_code.set(0);
+            gtk::main_quit();
+        })
+    };
+
+    for (index, page) in pages.iter_mut().enumerate() {
+        let labels: Vec<String> = page.buttons.iter().map(|b| b.label.clone()).collect();
+        let answers = answers.clone();
+        let advance = advance.clone();
+        page.on_choice = Some(Rc::new(
+            move |choice: Option<usize>, pin: Option<String>| match choice {
+                Some(i) => {
+                    answers.borrow_mut()[index] =
+                        Some(json!({ "button": labels[i], "index": i, "pin": pin }));
+                    advance();
+                }
+                None => gtk::main_quit(),
+            },
+        ));
+        stack.add_named(&create_page(page), &index.to_string());
+    }
+    {
+        let (current, show) = (current.clone(), show.clone());
+        back.connect_clicked(move |_| show(current.get() - 1));
+    }
+    next.connect_clicked(move |_| advance());
+
+    let navigation = gtk::Box::new(gtk::Orientation::Horizontal, config.spacing);
+    navigation.pack_start(&back, false, false, 0);
+    navigation.pack_end(&next, false, false, 0);
+    let content = gtk::Box::new(gtk::Orientation::Vertical, config.spacing);
+    content.add(&stack);
+    content.add(&gtk::Separator::new(gtk::Orientation::Horizontal));
+    content.add(&navigation);
+
+    let window = create_gtk_step_window(config, &content);
+    window.show_all();
+    // The stack only switches to children which are shown.
+    show(0);
+    gtk::main();
+    exit_code.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{output, pages};
+    use serde_json::json;
+
+    #[test]
+    fn pages_and_answers() {
+        let path = std::env::temp_dir().join(format!("wizard-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[[page]]\nmessage = \"Welcome\"\n\
+             [[page]]\nmessage = \"Which shell?\"\nbutton = [{label = \"_bash\"}, {label = \"_zsh\"}]\n",
+        )
+        .unwrap();
+        let result = pages(&path);
+        std::fs::remove_file(&path).unwrap();
+        let pages = result.ok().unwrap();
+        assert_eq!(2, pages.len());
+        assert_eq!("_zsh", pages[1].buttons[1].label);
+
+        let answers = vec![
+            None,
+            Some(json!({"button": "_zsh", "index": 1, "pin": null})),
+        ];
+        assert_eq!(
+            json!([{"button": null}, {"button": "_zsh", "index": 1, "pin": null}]),
+            output(&answers)
+        );
+    }
+}
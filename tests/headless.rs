@@ -0,0 +1,165 @@
+/* Drives the dialog without a real desktop.
+ *
+ * The broadway tests need `broadwayd`, the interaction tests `Xvfb` and `xdotool`,
+ * the offscreen rendering a display to connect to. They are ignored by default,
+ * `cargo test -- --ignored` runs them and fails when a tool is missing.
+ */
+use std::process::{Child, Command, Output, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const BIN: &str = env!("CARGO_BIN_EXE_options-window-gtk");
+
+struct Server {
+    child: Child,
+    display: String,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn available(tool: &str) -> bool {
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!("command -v {}", tool))
+        .stdout(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+fn start_server(program: &str, display: &str) -> Server {
+    assert!(available(program), "{} is not installed", program);
+    let child = Command::new(program)
+        .arg(display)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap_or_else(|e| panic!("Couldn't start {}: {}", program, e));
+    sleep(Duration::from_millis(500));
+    Server {
+        child,
+        display: display.to_string(),
+    }
+}
+
+fn wait_with_timeout(mut child: Child, timeout: Duration) -> Option<Output> {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if let Ok(Some(_)) = child.try_wait() {
+            return child.wait_with_output().ok();
+        }
+        sleep(Duration::from_millis(50));
+    }
+    let _ = child.kill();
+    None
+}
+
+fn xdotool(display: &str, args: &[&str]) {
+    Command::new("xdotool")
+        .env("DISPLAY", display)
+        .args(args)
+        .status()
+        .expect("xdotool failed");
+}
+
+// Tests run in parallel, so every test needs its own display number.
+fn run_on_xvfb(display: &str, args: &[&str], keys: &[&str]) -> Option<Output> {
+    assert!(available("xdotool"), "xdotool is not installed");
+    let server = start_server("Xvfb", display);
+    let child = Command::new(BIN)
+        .env("DISPLAY", &server.display)
+        .args(args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    xdotool(
+        &server.display,
+        &["search", "--sync", "--name", "options-window-gtk"],
+    );
+    sleep(Duration::from_millis(200));
+    for key in keys {
+        xdotool(&server.display, &["key", key]);
+    }
+    wait_with_timeout(child, Duration::from_secs(10))
+}
+
+#[test]
+fn help_and_errors_exit_codes() {
+    let help = Command::new(BIN).arg("--help").output().unwrap();
+    assert_eq!(Some(0), help.status.code());
+    let wrong = Command::new(BIN).arg("--no-such-option").output().unwrap();
+    assert_eq!(Some(1), wrong.status.code());
+}
+
+#[test]
+fn offscreen_needs_an_output() {
+    let output = Command::new(BIN)
+        .args(["--gtk-backend", "offscreen", "-m", "Hello"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--gtk-backend offscreen"));
+}
+
+#[test]
+#[ignore = "needs a display"]
+fn dump_ui_offscreen() {
+    let output = Command::new(BIN)
+        .args(["--gtk-backend", "offscreen", "--dump-ui", "-m", "Hello"])
+        .args(["-B", "_Yes", "true"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let tree = String::from_utf8_lossy(&output.stdout);
+    assert!(tree.contains("GtkOffscreenWindow"));
+    assert!(tree.contains("GtkLabel #message \"Hello\""));
+}
+
+#[test]
+#[ignore = "needs broadwayd"]
+fn dump_ui_on_broadway() {
+    let server = start_server("broadwayd", ":5");
+    let output = Command::new(BIN)
+        .env("BROADWAY_DISPLAY", &server.display)
+        .args(["--gtk-backend", "broadway", "--dump-ui", "-m", "Hello"])
+        .args(["-B", "_Yes", "true"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let tree = String::from_utf8_lossy(&output.stdout);
+    assert!(tree.contains("GtkLabel #message \"Hello\""));
+    assert!(tree.contains("GtkLabel \"Yes\""));
+}
+
+#[test]
+#[ignore = "needs Xvfb and xdotool"]
+fn enter_activates_cancel() {
+    let output = run_on_xvfb(":97", &["-m", "Hello"], &["Return"]).expect("The dialog didn't exit");
+    assert_eq!(Some(0), output.status.code());
+}
+
+#[test]
+#[ignore = "needs Xvfb and xdotool"]
+fn enter_activates_focused_button() {
+    let marker = std::env::temp_dir().join("options-window-gtk-headless-test");
+    let _ = std::fs::remove_file(&marker);
+    let action = format!("touch {}", marker.to_string_lossy());
+    let args = [
+        "--exit-after-action",
+        "--focus",
+        "Touch",
+        "-B",
+        "Touch",
+        action.as_str(),
+    ];
+    let output = run_on_xvfb(":98", &args, &["Return"]).expect("The dialog didn't exit");
+    assert_eq!(Some(0), output.status.code());
+    sleep(Duration::from_millis(200));
+    assert!(marker.exists());
+    let _ = std::fs::remove_file(&marker);
+}